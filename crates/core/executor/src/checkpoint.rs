@@ -0,0 +1,152 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sp1_stark::{baby_bear_poseidon2::BabyBearPoseidon2, StarkVerifyingKey};
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::{ExecutionState, Program, SP1ReduceProof};
+
+/// The name of the manifest file, which is written once all checkpoints are persisted.
+const MANIFEST_FILE: &str = "manifest.bin";
+
+/// A directory holding the checkpoints of a single execution.
+///
+/// Each checkpoint is the bincode-encoded [`ExecutionState`] at the start of a shard batch, stored
+/// in `checkpoint-<index>.bin`. A `manifest.bin` file is written last, so a directory without it
+/// holds an incomplete execution.
+#[derive(Debug, Clone)]
+pub struct CheckpointDir {
+    path: PathBuf,
+}
+
+/// Describes a complete set of checkpoints in a [`CheckpointDir`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointManifest {
+    /// The [`CheckpointManifest::program_digest`] of the executed program.
+    pub program_digest: [u8; 32],
+    /// The [`CheckpointManifest::stdin_digest`] of the inputs the program was executed with.
+    pub stdin_digest: [u8; 32],
+    /// The number of checkpoints in the directory.
+    pub num_checkpoints: usize,
+    /// The shard size the checkpoints were created with.
+    pub shard_size: usize,
+    /// The shard batch size the checkpoints were created with.
+    pub shard_batch_size: usize,
+    /// The public values committed by the execution.
+    pub public_values_stream: Vec<u8>,
+}
+
+impl CheckpointManifest {
+    /// Hash the parts of a program that determine its execution.
+    ///
+    /// The preprocessed shape is left out, since it is only fixed once the program is proven.
+    #[must_use]
+    pub fn program_digest(program: &Program) -> [u8; 32] {
+        let mut memory_image = program.memory_image.iter().collect::<Vec<_>>();
+        memory_image.sort_unstable();
+
+        let mut hasher = Keccak::v256();
+        hasher.update(&program.pc_start.to_le_bytes());
+        hasher.update(&program.pc_base.to_le_bytes());
+        hasher.update(&bincode::serialize(&program.instructions).unwrap());
+        hasher.update(&bincode::serialize(&memory_image).unwrap());
+        let mut digest = [0u8; 32];
+        hasher.finalize(&mut digest);
+        digest
+    }
+
+    /// Hash the input stream and the proof stream an execution starts with.
+    #[must_use]
+    pub fn stdin_digest(
+        input_stream: &[Vec<u8>],
+        proof_stream: &[(
+            SP1ReduceProof<BabyBearPoseidon2>,
+            StarkVerifyingKey<BabyBearPoseidon2>,
+        )],
+    ) -> [u8; 32] {
+        let mut hasher = Keccak::v256();
+        hasher.update(&bincode::serialize(input_stream).unwrap());
+        hasher.update(&bincode::serialize(proof_stream).unwrap());
+        let mut digest = [0u8; 32];
+        hasher.finalize(&mut digest);
+        digest
+    }
+}
+
+impl CheckpointDir {
+    /// Create a [`CheckpointDir`] rooted at `path`. The directory is created on the first write.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The path of the directory.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn checkpoint_path(&self, index: usize) -> PathBuf {
+        self.path.join(format!("checkpoint-{index}.bin"))
+    }
+
+    /// Persist the checkpoint with the given index.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory or file cannot be written.
+    pub fn write_checkpoint(&self, index: usize, checkpoint: &ExecutionState) -> io::Result<()> {
+        fs::create_dir_all(&self.path)?;
+        let mut file = File::create(self.checkpoint_path(index))?;
+        checkpoint.save(&mut file)
+    }
+
+    /// Open the checkpoint with the given index for reading.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the checkpoint file cannot be opened.
+    pub fn open_checkpoint(&self, index: usize) -> io::Result<File> {
+        File::open(self.checkpoint_path(index))
+    }
+
+    /// Remove the manifest, marking the checkpoints in the directory as incomplete. Does nothing if
+    /// there is no manifest.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the manifest exists but cannot be removed.
+    pub fn remove_manifest(&self) -> io::Result<()> {
+        match fs::remove_file(self.path.join(MANIFEST_FILE)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Write the manifest, marking the checkpoints in the directory as complete.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the manifest cannot be written.
+    pub fn write_manifest(&self, manifest: &CheckpointManifest) -> io::Result<()> {
+        fs::create_dir_all(&self.path)?;
+        let mut writer = BufWriter::new(File::create(self.path.join(MANIFEST_FILE))?);
+        bincode::serialize_into(&mut writer, manifest)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        writer.flush()
+    }
+
+    /// Read the manifest of a complete set of checkpoints.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the manifest is missing or cannot be decoded.
+    pub fn read_manifest(&self) -> io::Result<CheckpointManifest> {
+        let reader = BufReader::new(File::open(self.path.join(MANIFEST_FILE))?);
+        bincode::deserialize_from(reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
@@ -1,13 +1,20 @@
 use core::mem::take;
-use std::sync::Arc;
+use std::{ops::Range, path::PathBuf, sync::Arc};
 
 use hashbrown::HashMap;
 
 use crate::{
     hook::{hookify, BoxedHook, HookEnv, HookRegistry},
     subproof::SubproofVerifier,
+    syscalls::SyscallCode,
 };
 
+/// A function that replaces the effect of a syscall, wrapped in a smart pointer.
+///
+/// It receives the words the precompile would read as little-endian bytes, and its output is
+/// written to memory starting at the address given by the first argument.
+pub type PrecompileOverride<'a> = Arc<dyn Fn(&[u8]) -> Vec<u8> + Send + Sync + 'a>;
+
 /// Context to run a program inside SP1.
 #[derive(Clone, Default)]
 pub struct SP1Context<'a> {
@@ -22,8 +29,27 @@ pub struct SP1Context<'a> {
     /// The maximum number of cpu cycles to use for execution.
     pub max_cycles: Option<u64>,
 
+    /// The number of cpu cycles above which the prover warns about a high cycle count.
+    ///
+    /// Note: `None` denotes the prover's default threshold.
+    pub warn_cycles: Option<u64>,
+
     /// Skip deferred proof verification.
     pub skip_deferred_proof_verification: bool,
+
+    /// Syscalls whose effect is replaced by a mock during execution.
+    pub precompile_overrides: HashMap<SyscallCode, PrecompileOverride<'a>>,
+
+    /// The addresses whose accesses are recorded in the execution report's memory trace.
+    ///
+    /// Note: `None` disables the memory trace.
+    pub memory_trace: Option<Range<u32>>,
+
+    /// The directory that execution checkpoints are written to and proving resumes from.
+    pub checkpoint_dir: Option<PathBuf>,
+
+    /// Fail with [`crate::ExecutionError::UnconstrainedPanic`] when an unconstrained block panics.
+    pub strict_unconstrained: bool,
 }
 
 /// A builder for [`SP1Context`].
@@ -33,7 +59,12 @@ pub struct SP1ContextBuilder<'a> {
     hook_registry_entries: Vec<(u32, BoxedHook<'a>)>,
     subproof_verifier: Option<Arc<dyn SubproofVerifier + 'a>>,
     max_cycles: Option<u64>,
+    warn_cycles: Option<u64>,
     skip_deferred_proof_verification: bool,
+    precompile_overrides: HashMap<SyscallCode, PrecompileOverride<'a>>,
+    memory_trace: Option<Range<u32>>,
+    checkpoint_dir: Option<PathBuf>,
+    strict_unconstrained: bool,
 }
 
 impl<'a> SP1Context<'a> {
@@ -72,12 +103,22 @@ impl<'a> SP1ContextBuilder<'a> {
             });
         let subproof_verifier = take(&mut self.subproof_verifier);
         let cycle_limit = take(&mut self.max_cycles);
+        let warn_cycles = take(&mut self.warn_cycles);
         let skip_deferred_proof_verification = take(&mut self.skip_deferred_proof_verification);
+        let precompile_overrides = take(&mut self.precompile_overrides);
+        let memory_trace = take(&mut self.memory_trace);
+        let checkpoint_dir = take(&mut self.checkpoint_dir);
+        let strict_unconstrained = take(&mut self.strict_unconstrained);
         SP1Context {
             hook_registry,
             subproof_verifier,
             max_cycles: cycle_limit,
+            warn_cycles,
             skip_deferred_proof_verification,
+            precompile_overrides,
+            memory_trace,
+            checkpoint_dir,
+            strict_unconstrained,
         }
     }
 
@@ -121,18 +162,69 @@ impl<'a> SP1ContextBuilder<'a> {
         self
     }
 
+    /// Set the number of cpu cycles above which a high cycle count warning is logged.
+    ///
+    /// Unlike [`Self::max_cycles`], crossing this threshold does not stop the execution.
+    pub fn warn_cycles(&mut self, warn_cycles: u64) -> &mut Self {
+        self.warn_cycles = Some(warn_cycles);
+        self
+    }
+
     /// Set the skip deferred proof verification flag.
     pub fn set_skip_deferred_proof_verification(&mut self, skip: bool) -> &mut Self {
         self.skip_deferred_proof_verification = skip;
         self
     }
+
+    /// Replace the effect of the syscall `code` with the output of `f`.
+    ///
+    /// Instead of running the real precompile, `f` is called with the words the precompile would
+    /// read (see [`SyscallCode::precompile_input`]) as little-endian bytes, and the bytes it
+    /// returns are written to memory starting at the address given by the first argument. This is
+    /// intended for testing how a program handles specific precompile results; the prover rejects
+    /// a context with overridden syscalls.
+    pub fn with_precompile_override(
+        &mut self,
+        code: SyscallCode,
+        f: impl Fn(&[u8]) -> Vec<u8> + Send + Sync + 'a,
+    ) -> &mut Self {
+        self.precompile_overrides.insert(code, Arc::new(f));
+        self
+    }
+
+    /// Record every read and write to an address in `addresses` in the execution report.
+    ///
+    /// Registers live at addresses `0..32`, so a range starting at 0 also records register
+    /// accesses. Accesses made in unconstrained blocks are not recorded.
+    pub fn memory_trace(&mut self, addresses: Range<u32>) -> &mut Self {
+        self.memory_trace = Some(addresses);
+        self
+    }
+
+    /// Persist execution checkpoints to `path`, and resume proving from them.
+    ///
+    /// Executing with this context writes a [`crate::CheckpointDir`] of the checkpoints at every
+    /// shard batch boundary. Proving with it traces those checkpoints instead of executing the
+    /// program again, provided that they are complete and were made with the same shard options.
+    /// Proving fails if the checkpoints were taken from another program or stdin.
+    pub fn with_checkpoint_dir(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.checkpoint_dir = Some(path.into());
+        self
+    }
+
+    /// Fail execution with [`crate::ExecutionError::UnconstrainedPanic`] as soon as an
+    /// unconstrained block panics, with the message the panic wrote to stderr.
+    pub fn strict_unconstrained(&mut self) -> &mut Self {
+        self.strict_unconstrained = true;
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
-    use crate::{subproof::DefaultSubproofVerifier, SP1Context};
+    use crate::{subproof::DefaultSubproofVerifier, syscalls::SyscallCode, SP1Context};
 
     #[test]
     fn defaults() {
@@ -143,6 +235,14 @@ mod tests {
         assert!(cycle_limit.is_none());
     }
 
+    #[test]
+    fn warn_cycles() {
+        let SP1Context { max_cycles, warn_cycles, .. } =
+            SP1Context::builder().warn_cycles(1_000).max_cycles(2_000).build();
+        assert_eq!(warn_cycles, Some(1_000));
+        assert_eq!(max_cycles, Some(2_000));
+    }
+
     #[test]
     fn without_default_hooks() {
         let SP1Context { hook_registry, .. } =
@@ -164,6 +264,34 @@ mod tests {
         assert_eq!(&hook_registry.unwrap().table.into_keys().collect::<Vec<_>>(), &[30]);
     }
 
+    #[test]
+    fn with_precompile_override() {
+        let SP1Context { precompile_overrides, .. } = SP1Context::builder()
+            .with_precompile_override(SyscallCode::KECCAK_PERMUTE, |_| vec![0; 200])
+            .build();
+        assert!(precompile_overrides.contains_key(&SyscallCode::KECCAK_PERMUTE));
+    }
+
+    #[test]
+    fn with_checkpoint_dir() {
+        let SP1Context { checkpoint_dir, .. } =
+            SP1Context::builder().with_checkpoint_dir("checkpoints").build();
+        assert_eq!(checkpoint_dir.unwrap(), std::path::Path::new("checkpoints"));
+    }
+
+    #[test]
+    fn strict_unconstrained() {
+        let SP1Context { strict_unconstrained, .. } =
+            SP1Context::builder().strict_unconstrained().build();
+        assert!(strict_unconstrained);
+    }
+
+    #[test]
+    fn memory_trace() {
+        let SP1Context { memory_trace, .. } = SP1Context::builder().memory_trace(32..64).build();
+        assert_eq!(memory_trace, Some(32..64));
+    }
+
     #[test]
     fn subproof_verifier() {
         let SP1Context { subproof_verifier, .. } = SP1Context::builder()
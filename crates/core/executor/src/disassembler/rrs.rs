@@ -362,15 +362,15 @@ impl InstructionProcessor for InstructionTranspiler {
 
 /// Transpile the [`Instruction`]s from the 32-bit encoded instructions.
 ///
-/// # Panics
-///
-/// This function will return an error if the [`Instruction`] cannot be processed.
+/// Encodings that cannot be decoded become [`Instruction::unimp`], so that they only fail if they
+/// are executed.
 #[must_use]
 pub(crate) fn transpile(instructions_u32: &[u32]) -> Vec<Instruction> {
     let mut instructions = Vec::new();
     let mut transpiler = InstructionTranspiler;
     for instruction_u32 in instructions_u32 {
-        let instruction = process_instruction(&mut transpiler, *instruction_u32).unwrap();
+        let instruction = process_instruction(&mut transpiler, *instruction_u32)
+            .unwrap_or_else(Instruction::unimp);
         instructions.push(instruction);
     }
     instructions
@@ -4,7 +4,8 @@ use sp1_stark::SP1CoreOpts;
 use thiserror::Error;
 
 use crate::{
-    context::SP1Context,
+    checkpoint::{CheckpointDir, CheckpointManifest},
+    context::{PrecompileOverride, SP1Context},
     dependencies::{emit_cpu_dependencies, emit_divrem_dependencies},
     events::{
         AluEvent, CpuEvent, LookupId, MemoryAccessPosition, MemoryInitializeFinalizeEvent,
@@ -13,7 +14,7 @@ use crate::{
     hook::{HookEnv, HookRegistry},
     memory::{Entry, PagedMemory},
     record::{ExecutionRecord, MemoryAccessRecord},
-    report::ExecutionReport,
+    report::{ExecutionReport, MemoryAccessKind, MemoryTraceEntry},
     state::{ExecutionState, ForkState},
     subproof::{DefaultSubproofVerifier, SubproofVerifier},
     syscalls::{default_syscall_map, Syscall, SyscallCode, SyscallContext},
@@ -25,7 +26,7 @@ use crate::profiler::Profiler;
 #[cfg(feature = "profiling")]
 use std::{fs::File, io::BufWriter};
 
-use std::sync::Arc;
+use std::{ops::Range, sync::Arc};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Whether to verify deferred proofs during execution.
@@ -120,6 +121,13 @@ pub struct Executor<'a> {
     /// The state of the runtime when in unconstrained mode.
     pub unconstrained_state: ForkState,
 
+    /// Whether a panic in an unconstrained block fails with [`ExecutionError::UnconstrainedPanic`].
+    pub strict_unconstrained: bool,
+
+    /// The bytes written to stderr in the current unconstrained block, kept in strict mode to
+    /// report the panic message.
+    pub unconstrained_stderr: String,
+
     /// Report of the program execution.
     pub report: ExecutionReport,
 
@@ -129,8 +137,14 @@ pub struct Executor<'a> {
     /// Registry of hooks, to be invoked by writing to certain file descriptors.
     pub hook_registry: HookRegistry<'a>,
 
+    /// Syscalls whose effect is replaced by a mock instead of running the real precompile.
+    pub precompile_overrides: HashMap<SyscallCode, PrecompileOverride<'a>>,
+
     /// The maximal shapes for the program.
     pub maximal_shapes: Option<Vec<HashMap<String, usize>>>,
+
+    /// The addresses whose accesses are recorded in [`ExecutionReport::memory_trace`].
+    pub memory_trace: Option<Range<u32>>,
 }
 
 /// The different modes the executor can run in.
@@ -178,6 +192,35 @@ pub enum ExecutionError {
     /// The program ended in unconstrained mode.
     #[error("program ended in unconstrained mode")]
     EndInUnconstrained(),
+
+    /// The program panicked in an unconstrained block, with the message written to stderr.
+    ///
+    /// This is only returned in strict unconstrained mode; otherwise the panic's `HALT` is
+    /// reported as an [`ExecutionError::InvalidSyscallUsage`].
+    #[error("panicked in unconstrained block: {0}")]
+    UnconstrainedPanic(String),
+
+    /// A precompile override was registered for a syscall that does not read its input from
+    /// memory.
+    #[error("syscall {0} can't be overridden")]
+    UnsupportedPrecompileOverride(SyscallCode),
+
+    /// A precompile override returned output that is not a whole number of words.
+    #[error("precompile override for {0} returned {1} bytes, not whole words")]
+    InvalidPrecompileOverrideOutput(SyscallCode, usize),
+
+    /// An execution checkpoint could not be persisted.
+    #[error("failed to persist checkpoint: {0}")]
+    Checkpoint(String),
+
+    /// The execution reached an instruction that SP1 does not support.
+    #[error("unsupported instruction 0x{opcode:08x} at pc 0x{pc:08x}")]
+    UnsupportedInstruction {
+        /// The program counter of the instruction.
+        pc: u32,
+        /// The raw 32-bit encoding of the instruction, or 0 if it is not in the memory image.
+        opcode: u32,
+    },
 }
 
 macro_rules! assert_valid_memory_access {
@@ -272,6 +315,8 @@ impl<'a> Executor<'a> {
             profiler: None,
             unconstrained: false,
             unconstrained_state: ForkState::default(),
+            strict_unconstrained: context.strict_unconstrained,
+            unconstrained_stderr: String::new(),
             syscall_map,
             executor_mode: ExecutorMode::Trace,
             emit_global_memory_events: true,
@@ -280,6 +325,7 @@ impl<'a> Executor<'a> {
             print_report: false,
             subproof_verifier,
             hook_registry,
+            precompile_overrides: context.precompile_overrides,
             opts,
             max_cycles: context.max_cycles,
             deferred_proof_verification: if context.skip_deferred_proof_verification {
@@ -291,6 +337,7 @@ impl<'a> Executor<'a> {
             uninitialized_memory_checkpoint: PagedMemory::new_preallocated(),
             local_memory_access: HashMap::new(),
             maximal_shapes: None,
+            memory_trace: context.memory_trace,
         }
     }
 
@@ -486,13 +533,15 @@ impl<'a> Executor<'a> {
         }
 
         // Construct the memory read record.
-        MemoryReadRecord::new(
+        let read_record = MemoryReadRecord::new(
             record.value,
             record.shard,
             record.timestamp,
             prev_record.shard,
             prev_record.timestamp,
-        )
+        );
+        self.trace_memory_access(addr, MemoryAccessKind::Read, read_record.value);
+        read_record
     }
 
     /// Write a word to memory and create an access record.
@@ -565,14 +614,31 @@ impl<'a> Executor<'a> {
         }
 
         // Construct the memory write record.
-        MemoryWriteRecord::new(
+        let write_record = MemoryWriteRecord::new(
             record.value,
             record.shard,
             record.timestamp,
             prev_record.value,
             prev_record.shard,
             prev_record.timestamp,
-        )
+        );
+        self.trace_memory_access(addr, MemoryAccessKind::Write, write_record.value);
+        write_record
+    }
+
+    /// Append an access to the report's memory trace if `addr` is being traced.
+    #[inline]
+    fn trace_memory_access(&mut self, addr: u32, kind: MemoryAccessKind, value: u32) {
+        if let Some(addresses) = &self.memory_trace {
+            if !self.unconstrained && addresses.contains(&addr) {
+                self.report.memory_trace.push(MemoryTraceEntry {
+                    cycle: self.state.global_clk,
+                    addr,
+                    kind,
+                    value,
+                });
+            }
+        }
     }
 
     /// Read from memory, assuming that all addresses are aligned.
@@ -978,6 +1044,16 @@ impl<'a> Executor<'a> {
                 // which is not permitted in unconstrained mode. This will result in
                 // non-zero memory interactions when generating a proof.
 
+                // A panic halts with a non-zero exit code after writing its message to stderr.
+                if self.unconstrained
+                    && self.strict_unconstrained
+                    && syscall == SyscallCode::HALT
+                    && b != 0
+                {
+                    let message = std::mem::take(&mut self.unconstrained_stderr);
+                    return Err(ExecutionError::UnconstrainedPanic(message.trim_end().to_string()));
+                }
+
                 if self.unconstrained
                     && (syscall != SyscallCode::EXIT_UNCONSTRAINED && syscall != SyscallCode::WRITE)
                 {
@@ -998,13 +1074,42 @@ impl<'a> Executor<'a> {
                 *syscall_count += 1;
 
                 let syscall_impl = self.get_syscall(syscall).cloned();
+                let precompile_override = self.precompile_overrides.get(&syscall).cloned();
                 if syscall.should_send() != 0 && self.executor_mode == ExecutorMode::Trace {
                     self.emit_syscall(clk, syscall.syscall_id(), b, c, syscall_lookup_id);
                 }
                 let mut precompile_rt = SyscallContext::new(self);
                 precompile_rt.syscall_lookup_id = syscall_lookup_id;
                 let (precompile_next_pc, precompile_cycles, returned_exit_code) =
-                    if let Some(syscall_impl) = syscall_impl {
+                    if let Some(precompile_override) = precompile_override {
+                        // Write the mocked output in place of running the precompile, which is
+                        // given the words the precompile would have read.
+                        let Some(input) = syscall.precompile_input() else {
+                            return Err(ExecutionError::UnsupportedPrecompileOverride(syscall));
+                        };
+                        let input = [b, c]
+                            .into_iter()
+                            .zip(input)
+                            .flat_map(|(ptr, (offset, len))| {
+                                precompile_rt.slice_unsafe(ptr + offset, len)
+                            })
+                            .flat_map(u32::to_le_bytes)
+                            .collect::<Vec<_>>();
+                        let output = precompile_override(&input);
+                        if output.len() % 4 != 0 {
+                            return Err(ExecutionError::InvalidPrecompileOverrideOutput(
+                                syscall,
+                                output.len(),
+                            ));
+                        }
+                        let words = output
+                            .chunks_exact(4)
+                            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+                            .collect::<Vec<_>>();
+                        precompile_rt.mw_slice(b, &words);
+                        a = syscall_id;
+                        (precompile_rt.next_pc, 0, precompile_rt.exit_code)
+                    } else if let Some(syscall_impl) = syscall_impl {
                         // Executing a syscall optionally returns a value to write to the t0
                         // register. If it returns None, we just keep the
                         // syscall_id in t0.
@@ -1046,7 +1151,8 @@ impl<'a> Executor<'a> {
 
             // See https://github.com/riscv-non-isa/riscv-asm-manual/blob/master/riscv-asm.md#instruction-aliases
             Opcode::UNIMP => {
-                return Err(ExecutionError::Unimplemented());
+                let opcode = self.program.memory_image.get(&pc).copied().unwrap_or_default();
+                return Err(ExecutionError::UnsupportedInstruction { pc, opcode });
             }
         }
 
@@ -1531,6 +1637,46 @@ impl<'a> Executor<'a> {
         Ok(())
     }
 
+    /// Executes the program in checkpoint mode and persists every checkpoint to `dir`, followed by
+    /// a manifest marking the checkpoints as complete.
+    ///
+    /// The checkpoints can later be traced and proven instead of executing the program again.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the program execution fails or a checkpoint cannot
+    /// be written.
+    pub fn run_checkpointed(&mut self, dir: &CheckpointDir) -> Result<(), ExecutionError> {
+        // Remove the manifest of a previous run first, so the directory does not read as complete
+        // if this run is interrupted after overwriting some of its checkpoints.
+        dir.remove_manifest().map_err(|e| ExecutionError::Checkpoint(e.to_string()))?;
+        let program_digest = CheckpointManifest::program_digest(&self.program);
+        let stdin_digest =
+            CheckpointManifest::stdin_digest(&self.state.input_stream, &self.state.proof_stream);
+
+        self.print_report = true;
+        let mut num_checkpoints = 0;
+        loop {
+            let (checkpoint, done) = self.execute_state(false)?;
+            dir.write_checkpoint(num_checkpoints, &checkpoint)
+                .map_err(|e| ExecutionError::Checkpoint(e.to_string()))?;
+            num_checkpoints += 1;
+            if done {
+                break;
+            }
+        }
+
+        let manifest = CheckpointManifest {
+            program_digest,
+            stdin_digest,
+            num_checkpoints,
+            shard_size: self.opts.shard_size,
+            shard_batch_size: self.opts.shard_batch_size,
+            public_values_stream: self.state.public_values_stream.clone(),
+        };
+        dir.write_manifest(&manifest).map_err(|e| ExecutionError::Checkpoint(e.to_string()))
+    }
+
     /// Executes up to `self.shard_batch_size` cycles of the program, returning whether the program
     /// has finished.
     pub fn execute(&mut self) -> Result<bool, ExecutionError> {
@@ -1736,7 +1882,8 @@ mod tests {
 
     use crate::Register;
 
-    use super::{Executor, Instruction, Opcode, Program};
+    use super::{ExecutionError, Executor, Instruction, Opcode, Program, SP1Context, SyscallCode};
+    use crate::disassembler::transpile;
 
     fn _assert_send<T: Send>() {}
 
@@ -2139,6 +2286,86 @@ mod tests {
         assert_eq!(runtime.state.pc, 108);
     }
 
+    #[test]
+    fn test_opcode_counts_loop() {
+        //   addi x10, x0, 10
+        // loop:
+        //   addi x11, x11, 1
+        //   subi x10, x10, 1
+        //   bne x10, x0, loop
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 10, 0, 10, false, true),
+            Instruction::new(Opcode::ADD, 11, 11, 1, false, true),
+            Instruction::new(Opcode::SUB, 10, 10, 1, false, true),
+            Instruction::new(Opcode::BNE, 10, 0, 8u32.wrapping_neg(), false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        runtime.run().unwrap();
+        assert_eq!(runtime.registers()[Register::X11 as usize], 10);
+
+        // One ADD to set up the counter, then one ADD, SUB and BNE per iteration.
+        let opcode_counts = &runtime.report.opcode_counts;
+        assert_eq!(opcode_counts[Opcode::ADD], 11);
+        assert_eq!(opcode_counts[Opcode::SUB], 10);
+        assert_eq!(opcode_counts[Opcode::BNE], 10);
+        assert_eq!(runtime.report.total_instruction_count(), 31);
+    }
+
+    #[test]
+    fn test_unsupported_instruction() {
+        // `addi x5, x0, 5` followed by `csrrw x0, cycle, x0`, which SP1 does not support.
+        let words = [0x0050_0293, 0xc000_1073];
+        let mut program = Program::new(transpile(&words), 0, 0);
+        program.memory_image = (0..).step_by(4).zip(words).collect();
+        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        assert!(matches!(
+            runtime.run(),
+            Err(ExecutionError::UnsupportedInstruction { pc: 4, opcode: 0xc000_1073 })
+        ));
+        assert_eq!(runtime.register(Register::X5), 5);
+    }
+
+    #[test]
+    fn test_precompile_override() {
+        // Store 7 in the second word of the state at 0x1000, call keccak permute on the state,
+        // then load its first word into x12.
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::KECCAK_PERMUTE as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 0x1000, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 0, false, true),
+            Instruction::new(Opcode::ADD, 13, 0, 7, false, true),
+            Instruction::new(Opcode::SW, 13, 10, 4, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            Instruction::new(Opcode::LW, 12, 0, 0x1000, false, true),
+        ];
+        let program = Program::new(instructions.clone(), 0, 0);
+        let context = SP1Context::builder()
+            .with_precompile_override(SyscallCode::KECCAK_PERMUTE, |input| {
+                // The override is given the state the precompile would have read.
+                let mut state = [0u8; 200];
+                state[4] = 7;
+                assert_eq!(input, state);
+                [0xdead_beef_u32; 50].iter().flat_map(|word| word.to_le_bytes()).collect()
+            })
+            .build();
+        let mut runtime = Executor::with_context(program, SP1CoreOpts::default(), context);
+        runtime.run().unwrap();
+        assert_eq!(runtime.registers()[Register::X12 as usize], 0xdead_beef);
+        assert_eq!(runtime.word(0x1000 + 49 * 4), 0xdead_beef);
+
+        // Output that is not a whole number of words is an error.
+        let program = Program::new(instructions, 0, 0);
+        let context = SP1Context::builder()
+            .with_precompile_override(SyscallCode::KECCAK_PERMUTE, |_| vec![0; 3])
+            .build();
+        let mut runtime = Executor::with_context(program, SP1CoreOpts::default(), context);
+        assert!(matches!(
+            runtime.run(),
+            Err(ExecutionError::InvalidPrecompileOverrideOutput(SyscallCode::KECCAK_PERMUTE, 3))
+        ));
+    }
+
     fn simple_op_code_test(opcode: Opcode, expected: u32, a: u32, b: u32) {
         let instructions = vec![
             Instruction::new(Opcode::ADD, 10, 0, a, false, true),
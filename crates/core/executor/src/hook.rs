@@ -116,6 +116,16 @@ pub struct HookEnv<'a, 'b: 'a> {
     pub runtime: &'a Executor<'b>,
 }
 
+impl<'a, 'b: 'a> HookEnv<'a, 'b> {
+    /// The number of entries of the input stream that the guest has not read yet.
+    ///
+    /// This includes unread data returned by earlier hook invocations.
+    #[must_use]
+    pub fn remaining_input_len(&self) -> usize {
+        self.runtime.state.input_stream.len() - self.runtime.state.input_stream_ptr
+    }
+}
+
 /// Recovers the public key from the signature and message hash using the k256 crate.
 ///
 /// # Arguments
@@ -271,4 +281,33 @@ pub mod tests {
     pub fn registry_empty_is_empty() {
         assert_eq!(HookRegistry::empty().table.len(), 0);
     }
+
+    #[test]
+    pub fn hook_env_remaining_input_len() {
+        use crate::{Program, SP1Context};
+        use sp1_stark::SP1CoreOpts;
+        use std::sync::Mutex;
+        use test_artifacts::HOOK_INPUT_LEN_ELF;
+
+        /// The file descriptor the guest writes to, matching the test program.
+        const FD_INPUT_LEN_HOOK: u32 = 100;
+
+        let lens = Mutex::new(Vec::new());
+        let mut builder = SP1Context::builder();
+        builder.hook(FD_INPUT_LEN_HOOK, |env, _| {
+            lens.lock().unwrap().push(env.remaining_input_len());
+            vec![]
+        });
+        let context = builder.build();
+
+        // The guest invokes the hook, reads one input, and invokes the hook again.
+        let program = Program::from(HOOK_INPUT_LEN_ELF).unwrap();
+        let mut runtime = Executor::with_context(program, SP1CoreOpts::default(), context);
+        runtime.write_stdin_slice(b"first");
+        runtime.write_stdin_slice(b"second");
+        runtime.run().unwrap();
+        drop(runtime);
+
+        assert_eq!(lens.into_inner().unwrap(), vec![2, 1]);
+    }
 }
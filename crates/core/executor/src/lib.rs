@@ -19,6 +19,7 @@
 #![allow(clippy::explicit_iter_loop)]
 #![warn(missing_docs)]
 
+mod checkpoint;
 mod context;
 mod dependencies;
 mod disassembler;
@@ -44,6 +45,7 @@ pub mod subproof;
 pub mod syscalls;
 mod utils;
 
+pub use checkpoint::*;
 pub use context::*;
 pub use executor::*;
 pub use hook::*;
@@ -57,3 +59,10 @@ pub use report::*;
 pub use shape::*;
 pub use state::*;
 pub use utils::*;
+
+/// The global version for all components of SP1.
+///
+/// This string should be updated whenever any step in verifying an SP1 proof changes, including
+/// core, recursion, and plonk-bn254. This string is used to download SP1 artifacts and the gnark
+/// docker image.
+pub const SP1_CIRCUIT_VERSION: &str = "v3.0.0";
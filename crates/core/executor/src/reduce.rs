@@ -1,5 +1,12 @@
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sp1_stark::{Dom, ShardProof, StarkGenericConfig, StarkVerifyingKey};
+use thiserror::Error;
+
+use crate::SP1_CIRCUIT_VERSION;
+
+/// The magic header prepended to versioned [`SP1ReduceProof`] encodings.
+pub const REDUCE_PROOF_MAGIC: &[u8; 8] = b"SP1RDCPF";
+
 /// An intermediate proof which proves the execution.
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(bound(serialize = "ShardProof<SC>: Serialize, Dom<SC>: Serialize"))]
@@ -11,6 +18,81 @@ pub struct SP1ReduceProof<SC: StarkGenericConfig> {
     pub proof: ShardProof<SC>,
 }
 
+/// Errors that can occur when decoding a versioned [`SP1ReduceProof`].
+#[derive(Error, Debug)]
+pub enum VersionedProofError {
+    /// The bytes do not start with [`REDUCE_PROOF_MAGIC`].
+    #[error("invalid header: not a versioned SP1 reduce proof")]
+    InvalidHeader,
+
+    /// The proof was produced by a different circuit version.
+    #[error("proof version mismatch: found {found}, expected {expected}")]
+    VersionMismatch {
+        /// The version recorded in the encoded proof.
+        found: String,
+        /// The version expected by the reader.
+        expected: String,
+    },
+
+    /// The header or proof payload could not be (de)serialized.
+    #[error("failed to decode proof: {0}")]
+    Bincode(#[from] bincode::Error),
+}
+
+impl<SC: StarkGenericConfig> SP1ReduceProof<SC> {
+    /// Serialize the proof, prefixed with a magic header and [`SP1_CIRCUIT_VERSION`].
+    #[must_use]
+    pub fn to_versioned_bytes(&self) -> Vec<u8>
+    where
+        Self: Serialize,
+    {
+        self.to_versioned_bytes_with_version(SP1_CIRCUIT_VERSION)
+    }
+
+    /// Serialize the proof, prefixed with a magic header and the given version string.
+    #[must_use]
+    pub fn to_versioned_bytes_with_version(&self, version: &str) -> Vec<u8>
+    where
+        Self: Serialize,
+    {
+        let mut bytes = REDUCE_PROOF_MAGIC.to_vec();
+        bincode::serialize_into(&mut bytes, version).unwrap();
+        bincode::serialize_into(&mut bytes, self).unwrap();
+        bytes
+    }
+
+    /// Deserialize a proof written by [`Self::to_versioned_bytes`], checking that it was produced
+    /// with the current [`SP1_CIRCUIT_VERSION`].
+    pub fn from_versioned_bytes(bytes: &[u8]) -> Result<Self, VersionedProofError>
+    where
+        Self: DeserializeOwned,
+    {
+        Self::from_versioned_bytes_with_version(bytes, SP1_CIRCUIT_VERSION)
+    }
+
+    /// Deserialize a proof written by [`Self::to_versioned_bytes_with_version`], checking that its
+    /// version matches `expected`.
+    pub fn from_versioned_bytes_with_version(
+        bytes: &[u8],
+        expected: &str,
+    ) -> Result<Self, VersionedProofError>
+    where
+        Self: DeserializeOwned,
+    {
+        let mut payload = bytes
+            .strip_prefix(REDUCE_PROOF_MAGIC.as_slice())
+            .ok_or(VersionedProofError::InvalidHeader)?;
+        let found: String = bincode::deserialize_from(&mut payload)?;
+        if found != expected {
+            return Err(VersionedProofError::VersionMismatch {
+                found,
+                expected: expected.to_string(),
+            });
+        }
+        Ok(bincode::deserialize(payload)?)
+    }
+}
+
 impl<SC: StarkGenericConfig> std::fmt::Debug for SP1ReduceProof<SC> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut debug_struct = f.debug_struct("SP1ReduceProof");
@@ -21,9 +21,113 @@ pub struct ExecutionReport {
     pub cycle_tracker: HashMap<String, u64>,
     /// The unique memory address counts.
     pub touched_memory_addresses: u64,
+    /// The memory accesses made during execution, if a memory trace was requested.
+    pub memory_trace: Vec<MemoryTraceEntry>,
+    /// The number of bytes read from the input stream.
+    pub input_bytes_read: u64,
+    /// The number of bytes committed to the public values stream.
+    pub public_values_bytes: u64,
+}
+
+/// Whether a [`MemoryTraceEntry`] is a read or a write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemoryAccessKind {
+    /// The word was read.
+    Read,
+    /// The word was written.
+    Write,
+}
+
+/// A single memory access recorded in [`ExecutionReport::memory_trace`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MemoryTraceEntry {
+    /// The global cycle at which the access happened.
+    pub cycle: u64,
+    /// The word-aligned address that was accessed.
+    pub addr: u32,
+    /// Whether the access was a read or a write.
+    pub kind: MemoryAccessKind,
+    /// The value read, or the value written.
+    pub value: u32,
+}
+
+/// The difference between two [`ExecutionReport`]s, as returned by [`ExecutionReport::diff`].
+///
+/// Each field holds the value of the report minus the value of the report it is compared against.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionReportDiff {
+    /// The event count deltas.
+    pub event_counts: Box<EnumMap<Opcode, i64>>,
+    /// The opcode count deltas.
+    pub opcode_counts: Box<EnumMap<Opcode, i64>>,
+    /// The syscall count deltas.
+    pub syscall_counts: Box<EnumMap<SyscallCode, i64>>,
+    /// The cycle tracker deltas, for every label whose count differs between the reports.
+    pub cycle_tracker: HashMap<String, i64>,
+    /// The unique memory address count delta.
+    pub touched_memory_addresses: i64,
+    /// The delta of the number of bytes read from the input stream.
+    pub input_bytes_read: i64,
+    /// The delta of the number of bytes committed to the public values stream.
+    pub public_values_bytes: i64,
+}
+
+impl ExecutionReportDiff {
+    /// Compute the change in the total number of instructions run.
+    #[must_use]
+    pub fn total_instruction_count(&self) -> i64 {
+        self.opcode_counts.values().sum()
+    }
+
+    /// Compute the change in the total number of syscalls made.
+    #[must_use]
+    pub fn total_syscall_count(&self) -> i64 {
+        self.syscall_counts.values().sum()
+    }
+
+    /// Whether the two compared reports have the same counts.
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Subtracts the counts of `rhs` from those of `lhs`.
+fn counts_sub<K>(lhs: &EnumMap<K, u64>, rhs: &EnumMap<K, u64>) -> EnumMap<K, i64>
+where
+    K: EnumArray<u64> + EnumArray<i64> + Copy,
+{
+    EnumMap::from_fn(|k| lhs[k] as i64 - rhs[k] as i64)
 }
 
 impl ExecutionReport {
+    /// Compare this report against `other`, returning the change in each count from `other` to
+    /// `self`.
+    #[must_use]
+    pub fn diff(&self, other: &ExecutionReport) -> ExecutionReportDiff {
+        let mut cycle_tracker = HashMap::new();
+        for label in self.cycle_tracker.keys().chain(other.cycle_tracker.keys()) {
+            let count = |report: &ExecutionReport| {
+                report.cycle_tracker.get(label).copied().unwrap_or_default() as i64
+            };
+            let delta = count(self) - count(other);
+            if delta != 0 {
+                cycle_tracker.insert(label.clone(), delta);
+            }
+        }
+
+        ExecutionReportDiff {
+            event_counts: Box::new(counts_sub(&self.event_counts, &other.event_counts)),
+            opcode_counts: Box::new(counts_sub(&self.opcode_counts, &other.opcode_counts)),
+            syscall_counts: Box::new(counts_sub(&self.syscall_counts, &other.syscall_counts)),
+            cycle_tracker,
+            touched_memory_addresses: self.touched_memory_addresses as i64
+                - other.touched_memory_addresses as i64,
+            input_bytes_read: self.input_bytes_read as i64 - other.input_bytes_read as i64,
+            public_values_bytes: self.public_values_bytes as i64 - other.public_values_bytes as i64,
+        }
+    }
+
     /// Compute the total number of instructions run during the execution.
     #[must_use]
     pub fn total_instruction_count(&self) -> u64 {
@@ -53,6 +157,9 @@ impl AddAssign for ExecutionReport {
         counts_add_assign(&mut self.opcode_counts, *rhs.opcode_counts);
         counts_add_assign(&mut self.syscall_counts, *rhs.syscall_counts);
         self.touched_memory_addresses += rhs.touched_memory_addresses;
+        self.memory_trace.extend(rhs.memory_trace);
+        self.input_bytes_read += rhs.input_bytes_read;
+        self.public_values_bytes += rhs.public_values_bytes;
     }
 }
 
@@ -77,6 +184,48 @@ impl Display for ExecutionReport {
             writeln!(f, "  {line}")?;
         }
 
+        writeln!(f, "input bytes read: {}", self.input_bytes_read)?;
+        writeln!(f, "public values bytes committed: {}", self.public_values_bytes)?;
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let mut report = ExecutionReport::default();
+        report.opcode_counts[Opcode::ADD] = 10;
+        report.syscall_counts[SyscallCode::WRITE] = 2;
+        report.cycle_tracker.insert("fib".to_string(), 100);
+        report.touched_memory_addresses = 5;
+
+        let diff = report.diff(&report);
+        assert!(diff.is_zero());
+        assert!(diff.cycle_tracker.is_empty());
+
+        let mut modified = report.clone();
+        modified.opcode_counts[Opcode::ADD] = 7;
+        modified.syscall_counts[SyscallCode::WRITE] = 4;
+        modified.cycle_tracker.insert("fib".to_string(), 120);
+        modified.cycle_tracker.insert("extra".to_string(), 3);
+        modified.touched_memory_addresses = 6;
+
+        let diff = modified.diff(&report);
+        assert!(!diff.is_zero());
+        assert_eq!(diff.opcode_counts[Opcode::ADD], -3);
+        assert_eq!(diff.total_instruction_count(), -3);
+        assert_eq!(diff.syscall_counts[SyscallCode::WRITE], 2);
+        assert_eq!(diff.total_syscall_count(), 2);
+        assert_eq!(diff.cycle_tracker["fib"], 20);
+        assert_eq!(diff.cycle_tracker["extra"], 3);
+        assert_eq!(diff.touched_memory_addresses, 1);
+
+        let reversed = report.diff(&modified);
+        assert_eq!(reversed.opcode_counts[Opcode::ADD], 3);
+        assert_eq!(reversed.cycle_tracker["extra"], -3);
+    }
+}
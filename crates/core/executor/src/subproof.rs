@@ -7,6 +7,15 @@ use std::sync::atomic::AtomicBool;
 
 use crate::SP1ReduceProof;
 
+/// A deferred proof and its verifying key, together with the vk hash and committed value digest
+/// that the proof is expected to have.
+pub type DeferredProofClaim<'p> = (
+    &'p SP1ReduceProof<BabyBearPoseidon2>,
+    &'p StarkVerifyingKey<BabyBearPoseidon2>,
+    [u32; 8],
+    [u32; 8],
+);
+
 /// Verifier used in runtime when `sp1_zkvm::precompiles::verify::verify_sp1_proof` is called. This
 /// is then used to sanity check that the user passed in the correct proof; the actual constraints
 /// happen in the recursion layer.
@@ -22,6 +31,19 @@ pub trait SubproofVerifier: Sync + Send {
         vk_hash: [u32; 8],
         committed_value_digest: [u32; 8],
     ) -> Result<(), MachineVerificationError<BabyBearPoseidon2>>;
+
+    /// Verify a batch of deferred proofs, each checked as in [`Self::verify_deferred_proof`].
+    ///
+    /// The default implementation verifies the proofs one at a time and stops at the first
+    /// failure.
+    fn verify_batch(
+        &self,
+        proofs: &[DeferredProofClaim<'_>],
+    ) -> Result<(), MachineVerificationError<BabyBearPoseidon2>> {
+        proofs.iter().try_for_each(|&(proof, vk, vk_hash, committed_value_digest)| {
+            self.verify_deferred_proof(proof, vk, vk_hash, committed_value_digest)
+        })
+    }
 }
 
 /// A dummy verifier which prints a warning on the first proof and does nothing else.
@@ -222,6 +222,50 @@ impl SyscallCode {
             _ => *self,
         }
     }
+
+    /// The memory a precompile reads its input from: for each of its two arguments, the byte
+    /// offset from the address in that argument and the number of words read there.
+    ///
+    /// Returns `None` for syscalls that are not precompiles reading their input from memory.
+    #[must_use]
+    pub fn precompile_input(self) -> Option<[(u32, usize); 2]> {
+        match self {
+            // Only the first 16 words of the message schedule are read before being written.
+            SyscallCode::SHA_EXTEND => Some([(0, 16), (0, 0)]),
+            SyscallCode::SHA_COMPRESS => Some([(0, 64), (0, 8)]),
+            SyscallCode::KECCAK_PERMUTE => Some([(0, 50), (0, 0)]),
+            SyscallCode::ED_ADD
+            | SyscallCode::SECP256K1_ADD
+            | SyscallCode::SECP256R1_ADD
+            | SyscallCode::BN254_ADD => Some([(0, 16), (0, 16)]),
+            SyscallCode::BLS12381_ADD => Some([(0, 24), (0, 24)]),
+            SyscallCode::SECP256K1_DOUBLE
+            | SyscallCode::SECP256R1_DOUBLE
+            | SyscallCode::BN254_DOUBLE => Some([(0, 16), (0, 0)]),
+            SyscallCode::BLS12381_DOUBLE => Some([(0, 24), (0, 0)]),
+            // Decompression reads the compressed coordinate from the second half of the slice.
+            SyscallCode::ED_DECOMPRESS
+            | SyscallCode::SECP256K1_DECOMPRESS
+            | SyscallCode::SECP256R1_DECOMPRESS => Some([(32, 8), (0, 0)]),
+            SyscallCode::BLS12381_DECOMPRESS => Some([(48, 12), (0, 0)]),
+            // The second argument points at the multiplier followed by the modulus.
+            SyscallCode::UINT256_MUL => Some([(0, 8), (0, 16)]),
+            SyscallCode::U256XU2048_MUL => Some([(0, 8), (0, 64)]),
+            SyscallCode::BN254_FP_ADD | SyscallCode::BN254_FP_SUB | SyscallCode::BN254_FP_MUL => {
+                Some([(0, 8), (0, 8)])
+            }
+            SyscallCode::BN254_FP2_ADD
+            | SyscallCode::BN254_FP2_SUB
+            | SyscallCode::BN254_FP2_MUL => Some([(0, 16), (0, 16)]),
+            SyscallCode::BLS12381_FP_ADD
+            | SyscallCode::BLS12381_FP_SUB
+            | SyscallCode::BLS12381_FP_MUL => Some([(0, 12), (0, 12)]),
+            SyscallCode::BLS12381_FP2_ADD
+            | SyscallCode::BLS12381_FP2_SUB
+            | SyscallCode::BLS12381_FP2_MUL => Some([(0, 24), (0, 24)]),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for SyscallCode {
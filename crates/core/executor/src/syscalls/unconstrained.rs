@@ -12,6 +12,7 @@ impl Syscall for EnterUnconstrainedSyscall {
             panic!("Unconstrained block is already active.");
         }
         ctx.rt.unconstrained = true;
+        ctx.rt.unconstrained_stderr.clear();
         ctx.rt.unconstrained_state = ForkState {
             global_clk: ctx.rt.state.global_clk,
             clk: ctx.rt.state.clk,
@@ -16,9 +16,11 @@ impl Syscall for WriteSyscall {
     ///
     /// If stderr (fd = 2):
     /// - Print the stream to stderr.
+    /// - In strict unconstrained mode, keep the stream written in unconstrained blocks.
     ///
     /// If fd = 3:
-    /// - Update the public value stream.
+    /// - Update the public value stream, counting the bytes in the report outside of unconstrained
+    ///   blocks.
     ///
     /// If fd = 4:
     /// - Update the input stream.
@@ -56,12 +58,18 @@ impl Syscall for WriteSyscall {
             }
         } else if fd == 2 {
             let s = core::str::from_utf8(slice).unwrap();
+            if rt.unconstrained && rt.strict_unconstrained {
+                rt.unconstrained_stderr.push_str(s);
+            }
             let flush_s = update_io_buf(ctx, fd, s);
             if !flush_s.is_empty() {
                 flush_s.into_iter().for_each(|line| println!("stderr: {}", line));
             }
         } else if fd == 3 {
             rt.state.public_values_stream.extend_from_slice(slice);
+            if !rt.unconstrained {
+                rt.report.public_values_bytes += u64::from(nbytes);
+            }
         } else if fd == 4 {
             rt.state.input_stream.push(slice.to_vec());
         } else if let Some(mut hook) = rt.hook_registry.get(fd) {
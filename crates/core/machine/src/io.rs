@@ -1,5 +1,6 @@
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sp1_core_executor::SP1ReduceProof;
+use sp1_primitives::io::TAGGED_INPUTS_MAGIC;
 use sp1_stark::{baby_bear_poseidon2::BabyBearPoseidon2, StarkVerifyingKey};
 
 /// Standard input for the prover.
@@ -53,6 +54,131 @@ impl SP1Stdin {
         self.buffer.push(vec);
     }
 
+    /// Write a length-delimited frame to the buffer.
+    ///
+    /// The bytes are prefixed with their length as a little-endian `u32` and stored as a single
+    /// buffer entry, to be read in the guest with `sp1_zkvm::io::read_frame`.
+    pub fn write_frame(&mut self, bytes: &[u8]) {
+        let len = u32::try_from(bytes.len()).expect("frame is too large");
+        let mut frame = Vec::with_capacity(4 + bytes.len());
+        frame.extend_from_slice(&len.to_le_bytes());
+        frame.extend_from_slice(bytes);
+        self.buffer.push(frame);
+    }
+
+    /// Read a length-delimited frame written by [`SP1Stdin::write_frame`] from the buffer.
+    pub fn read_frame(&mut self) -> Vec<u8> {
+        let frame = &self.buffer[self.ptr];
+        assert!(frame.len() >= 4, "frame is missing its length prefix");
+        let (len, bytes) = frame.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+        assert_eq!(bytes.len(), len, "frame length prefix does not match its contents");
+        let bytes = bytes.to_vec();
+        self.ptr += 1;
+        bytes
+    }
+
+    /// Write a list of byte slices to the buffer as a single entry.
+    ///
+    /// The entry starts with the number of slices and the end offset of each slice, all as
+    /// little-endian `u32`s, followed by the concatenated bytes. It is read in the guest with
+    /// `sp1_zkvm::io::read_vec_of_slices`, which keeps the slices in the buffer it read them into
+    /// instead of allocating each of them separately.
+    pub fn write_vec_of_slices(&mut self, slices: &[&[u8]]) {
+        let count = u32::try_from(slices.len()).expect("too many slices");
+        let data_len: usize = slices.iter().map(|slice| slice.len()).sum();
+        let mut entry = Vec::with_capacity(4 * (1 + slices.len()) + data_len);
+        entry.extend_from_slice(&count.to_le_bytes());
+        let mut end = 0usize;
+        for slice in slices {
+            end += slice.len();
+            let end = u32::try_from(end).expect("slices are too large");
+            entry.extend_from_slice(&end.to_le_bytes());
+        }
+        for slice in slices {
+            entry.extend_from_slice(slice);
+        }
+        self.buffer.push(entry);
+    }
+
+    /// Read a list of byte slices written by [`SP1Stdin::write_vec_of_slices`] from the buffer.
+    pub fn read_vec_of_slices(&mut self) -> Vec<Vec<u8>> {
+        let entry = &self.buffer[self.ptr];
+        let read_u32 = |i: usize| {
+            let bytes = entry.get(4 * i..4 * i + 4).expect("slice table is truncated");
+            u32::from_le_bytes(bytes.try_into().unwrap()) as usize
+        };
+        let count = read_u32(0);
+        let data = entry.get(4 * (1 + count)..).expect("slice table is truncated");
+        let mut start = 0;
+        let slices = (0..count)
+            .map(|i| {
+                let end = read_u32(1 + i);
+                let slice = data.get(start..end).expect("slice table does not match its contents");
+                start = end;
+                slice.to_vec()
+            })
+            .collect();
+        assert_eq!(start, data.len(), "slice table does not match its contents");
+        self.ptr += 1;
+        slices
+    }
+
+    /// Write a value to the buffer under `tag`, to be read in the guest with
+    /// `sp1_zkvm::io::read_tagged` regardless of the order of the writes.
+    ///
+    /// All tagged values are stored in a single entry at the front of the buffer: the
+    /// [`TAGGED_INPUTS_MAGIC`] prefix followed by, for each value, its tag and length as
+    /// little-endian `u32`s and its bincode encoding. Each write appends to that entry. A program
+    /// mixing tagged and positional inputs must therefore call `read_tagged` before its first
+    /// positional read.
+    pub fn write_tagged<T: Serialize>(&mut self, tag: u32, data: &T) {
+        let value = bincode::serialize(data).expect("serialization failed");
+        let len = u32::try_from(value.len()).expect("tagged value is too large");
+        if self.tagged_inputs().is_none() {
+            // Keep the position of the positional reads on the entry it pointed to.
+            self.buffer.insert(0, TAGGED_INPUTS_MAGIC.to_vec());
+            self.ptr += 1;
+        }
+        assert!(
+            self.tagged_inputs().unwrap().all(|(t, _)| t != tag),
+            "tag {tag} was written twice"
+        );
+
+        let entry = &mut self.buffer[0];
+        entry.extend_from_slice(&tag.to_le_bytes());
+        entry.extend_from_slice(&len.to_le_bytes());
+        entry.extend_from_slice(&value);
+    }
+
+    /// Read the value written under `tag` with [`SP1Stdin::write_tagged`], without moving the
+    /// position of the positional reads.
+    pub fn read_tagged<T: DeserializeOwned>(&self, tag: u32) -> T {
+        let mut tagged = self.tagged_inputs().expect("no tagged inputs were written");
+        let (_, value) =
+            tagged.find(|(t, _)| *t == tag).unwrap_or_else(|| panic!("no input with tag {tag}"));
+        bincode::deserialize(value).expect("failed to deserialize")
+    }
+
+    /// The tags and values in the tagged inputs entry, if there is one.
+    fn tagged_inputs(&self) -> Option<impl Iterator<Item = (u32, &[u8])>> {
+        let mut rest = self.buffer.first()?.strip_prefix(TAGGED_INPUTS_MAGIC.as_slice())?;
+        Some(std::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+            let read_u32 = |i: usize| {
+                let bytes = rest.get(i..i + 4).expect("tagged input is truncated");
+                u32::from_le_bytes(bytes.try_into().unwrap())
+            };
+            let tag = read_u32(0);
+            let len = read_u32(4) as usize;
+            let value = rest.get(8..8 + len).expect("tagged input is truncated");
+            rest = &rest[8 + len..];
+            Some((tag, value))
+        }))
+    }
+
     pub fn write_proof(
         &mut self,
         proof: SP1ReduceProof<BabyBearPoseidon2>,
@@ -98,3 +224,168 @@ pub mod proof_serde {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::{AbstractField, PrimeField32};
+    use sp1_core_executor::{Executor, Program};
+    use sp1_primitives::io::{PublicValuesError, SP1PublicValues};
+    use sp1_stark::SP1CoreOpts;
+    use test_artifacts::{
+        COMMIT_FIELD_DIGEST_ELF, FRAME_IO_ELF, PUBLIC_VALUES_DIGEST_ELF, TAGGED_IO_ELF,
+        VEC_OF_SLICES_ELF,
+    };
+
+    use super::SP1Stdin;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let frames: [&[u8]; 3] = [b"", b"hello", &[7u8; 1000]];
+
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&(frames.len() as u32));
+        for frame in frames {
+            stdin.write_frame(frame);
+        }
+
+        // Read the frames back on the host.
+        let mut host_stdin = stdin.clone();
+        let _: u32 = host_stdin.read();
+        for frame in frames {
+            assert_eq!(host_stdin.read_frame(), frame);
+        }
+
+        // Read the frames in the guest, which commits each of them to the public values.
+        let program = Program::from(FRAME_IO_ELF).unwrap();
+        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        runtime.write_vecs(&stdin.buffer);
+        runtime.run().unwrap();
+        for frame in frames {
+            assert_eq!(runtime.read_public_values::<Vec<u8>>(), frame);
+        }
+    }
+
+    #[test]
+    fn test_io_byte_counts() {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&1u32);
+        stdin.write_frame(b"hello");
+
+        let program = Program::from(FRAME_IO_ELF).unwrap();
+        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        runtime.write_vecs(&stdin.buffer);
+        runtime.run().unwrap();
+
+        // The count is 4 bytes, and the frame its 4-byte length prefix followed by 5 bytes.
+        assert_eq!(runtime.report.input_bytes_read, 4 + 4 + 5);
+        // The frame is committed as a bincode `Vec<u8>`, with an 8-byte length prefix.
+        assert_eq!(runtime.report.public_values_bytes, 8 + 5);
+        assert_eq!(
+            runtime.report.public_values_bytes,
+            runtime.state.public_values_stream.len() as u64
+        );
+    }
+
+    #[test]
+    fn test_vec_of_slices_round_trip() {
+        let slices: [&[u8]; 5] = [b"abc", b"", &[1u8; 37], b"d", &[0xffu8; 300]];
+
+        let mut stdin = SP1Stdin::new();
+        stdin.write_vec_of_slices(&slices);
+
+        // Read the slices back on the host.
+        assert_eq!(stdin.clone().read_vec_of_slices(), slices);
+
+        // Read the slices in the guest, which commits their count and then each of them.
+        let program = Program::from(VEC_OF_SLICES_ELF).unwrap();
+        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        runtime.write_vecs(&stdin.buffer);
+        runtime.run().unwrap();
+        assert_eq!(runtime.read_public_values::<u32>(), slices.len() as u32);
+        for slice in slices {
+            assert_eq!(runtime.read_public_values::<Vec<u8>>(), slice);
+        }
+    }
+
+    #[test]
+    fn test_tagged_round_trip() {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&42u64);
+        stdin.write_tagged(3, &vec![1u8, 2, 3]);
+        stdin.write_tagged(7, &5u32);
+        stdin.write_tagged(1, &"tagged".to_string());
+
+        // Read the values back on the host, in another order than they were written.
+        assert_eq!(stdin.read_tagged::<u32>(7), 5);
+        assert_eq!(stdin.read_tagged::<String>(1), "tagged");
+        assert_eq!(stdin.read_tagged::<Vec<u8>>(3), [1, 2, 3]);
+        assert_eq!(stdin.clone().read::<u64>(), 42);
+
+        // A tagged write keeps the position of the positional reads on the host.
+        let mut host_stdin = SP1Stdin::new();
+        host_stdin.write(&1u32);
+        host_stdin.write(&2u32);
+        assert_eq!(host_stdin.read::<u32>(), 1);
+        host_stdin.write_tagged(0, &3u32);
+        assert_eq!(host_stdin.read::<u32>(), 2);
+        assert_eq!(host_stdin.read_tagged::<u32>(0), 3);
+
+        // Read them in the guest by tag and then the positional input, committing each of them.
+        let program = Program::from(TAGGED_IO_ELF).unwrap();
+        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        runtime.write_vecs(&stdin.buffer);
+        runtime.run().unwrap();
+        assert_eq!(runtime.read_public_values::<u32>(), 5);
+        assert_eq!(runtime.read_public_values::<String>(), "tagged");
+        assert_eq!(runtime.read_public_values::<Vec<u8>>(), [1, 2, 3]);
+        assert_eq!(runtime.read_public_values::<u64>(), 42);
+    }
+
+    #[test]
+    fn test_public_values_digest() {
+        let first: &[u8] = b"intermediate state";
+        let second: &[u8] = &[7u8; 100];
+
+        let mut stdin = SP1Stdin::new();
+        stdin.write_slice(first);
+        stdin.write_slice(second);
+
+        // The guest commits both chunks, followed by the digests it read after each of them.
+        let program = Program::from(PUBLIC_VALUES_DIGEST_ELF).unwrap();
+        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        runtime.write_vecs(&stdin.buffer);
+        runtime.run().unwrap();
+
+        let committed = &runtime.state.public_values_stream;
+        let (chunks, digests) = committed.split_at(first.len() + second.len());
+        assert_eq!(chunks, [first, second].concat());
+        assert_eq!(digests[..32], SP1PublicValues::from(first).hash());
+        assert_eq!(digests[32..], SP1PublicValues::from(chunks).hash());
+    }
+
+    #[test]
+    fn test_commit_field_digest() {
+        let words = [0, 1, 2, 0x7800_0000, 42, 1 << 20, 0x7fff_ffff, 0x1234_5678];
+        let digest = words.map(BabyBear::from_wrapped_u32);
+
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&digest.map(|element| element.as_canonical_u32()));
+
+        // The guest commits the digest as field elements, with no byte conversion.
+        let program = Program::from(COMMIT_FIELD_DIGEST_ELF).unwrap();
+        let mut runtime = Executor::new(program, SP1CoreOpts::default());
+        runtime.write_vecs(&stdin.buffer);
+        runtime.run().unwrap();
+
+        let mut public_values = SP1PublicValues::from(&runtime.state.public_values_stream);
+        assert_eq!(public_values.read_field_digest().unwrap(), digest);
+
+        // Words that are not reduced modulo the BabyBear prime are rejected.
+        let mut public_values = SP1PublicValues::from(&[0xff; 32]);
+        assert!(matches!(
+            public_values.read_field_digest(),
+            Err(PublicValuesError::NonCanonicalFieldElement(0xffff_ffff))
+        ));
+    }
+}
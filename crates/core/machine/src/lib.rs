@@ -26,12 +26,11 @@ pub mod riscv;
 pub mod syscall;
 pub mod utils;
 
-/// The global version for all components of SP1.
-///
-/// This string should be updated whenever any step in verifying an SP1 proof changes, including
-/// core, recursion, and plonk-bn254. This string is used to download SP1 artifacts and the gnark
-/// docker image.
-pub const SP1_CIRCUIT_VERSION: &str = "v3.0.0";
+// Re-export the global SP1 circuit version from sp1_core_executor.
+//
+// The version is defined alongside `SP1ReduceProof` so that versioned proof serialization can
+// reference it without a circular dependency.
+pub use sp1_core_executor::SP1_CIRCUIT_VERSION;
 
 // Re-export the `SP1ReduceProof` struct from sp1_core_machine.
 //
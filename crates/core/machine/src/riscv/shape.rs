@@ -44,6 +44,36 @@ pub struct CoreShapeConfig<F: PrimeField32> {
     precompile_allowed_log_heights: HashMap<RiscvAir<F>, (usize, Vec<usize>)>,
 }
 
+/// Why a program was assigned its preprocessed shape, as reported by
+/// [`CoreShapeConfig::explain_shape`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeExplanation {
+    /// The preprocessed shape chosen by [`CoreShapeConfig::fix_preprocessed_shape`].
+    pub shape: CoreShape,
+    /// The shape the program would get if the forcing chip fit in its next smaller allowed
+    /// height, or `None` if every chip is already at its smallest allowed height.
+    pub next_smaller_shape: Option<CoreShape>,
+    /// The chip whose row count forced the jump from `next_smaller_shape` to `shape`.
+    pub forced_by: Option<ShapeJump>,
+}
+
+/// A chip whose row count does not fit in its next smaller allowed height.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShapeJump {
+    /// The name of the chip.
+    pub chip: String,
+    /// The number of rows of the chip before padding.
+    pub rows: usize,
+    /// The log height of the chip in the chosen shape.
+    pub log_height: usize,
+    /// The next smaller allowed log height of the chip.
+    pub smaller_log_height: usize,
+    /// The number of padding rows added to reach the chosen height.
+    pub padding: usize,
+    /// The number of rows that would have to be removed to fit the smaller height.
+    pub excess: usize,
+}
+
 struct CoreShapeSpec {
     cpu_height: Vec<Option<usize>>,
     add_sub_height: Vec<Option<usize>>,
@@ -74,6 +104,54 @@ impl<F: PrimeField32> CoreShapeConfig<F> {
         Ok(())
     }
 
+    /// Explain the preprocessed shape [`Self::fix_preprocessed_shape`] would choose for `program`.
+    ///
+    /// Each preprocessed chip is padded to the smallest allowed height that fits its rows. The
+    /// forcing chip is the largest chip that is not at its smallest allowed height: shrinking it
+    /// below the next smaller height is what moves the program into a cheaper shape.
+    pub fn explain_shape(&self, program: &Program) -> Result<ShapeExplanation, CoreShapeError> {
+        let heights = RiscvAir::<F>::preprocessed_heights(program);
+        let shape =
+            Self::find_shape_from_allowed_heights(&heights, &self.allowed_preprocessed_log_heights)
+                .ok_or(CoreShapeError::PreprocessedShapeError)?;
+
+        let forced_by = heights
+            .iter()
+            .filter_map(|(air, rows)| {
+                let log_height = *shape.inner.get(&air.name())?;
+                let smaller_log_height = self
+                    .allowed_preprocessed_log_heights
+                    .get(air)?
+                    .iter()
+                    .map(|log_height| log_height.unwrap_or_default())
+                    .filter(|&allowed| allowed < log_height)
+                    .max()?;
+                let smaller_height =
+                    if smaller_log_height != 0 { 1 << smaller_log_height } else { 0 };
+                Some(ShapeJump {
+                    chip: air.name(),
+                    rows: *rows,
+                    log_height,
+                    smaller_log_height,
+                    padding: (1 << log_height) - rows,
+                    excess: rows - smaller_height,
+                })
+            })
+            .max_by(|a, b| a.log_height.cmp(&b.log_height).then_with(|| b.chip.cmp(&a.chip)));
+
+        let next_smaller_shape = forced_by.as_ref().map(|jump| {
+            let mut inner = shape.inner.clone();
+            if jump.smaller_log_height != 0 {
+                inner.insert(jump.chip.clone(), jump.smaller_log_height);
+            } else {
+                inner.remove(&jump.chip);
+            }
+            CoreShape { inner }
+        });
+
+        Ok(ShapeExplanation { shape, next_smaller_shape, forced_by })
+    }
+
     #[inline]
     fn find_shape_from_allowed_heights(
         heights: &[(RiscvAir<F>, usize)],
@@ -765,6 +843,39 @@ pub mod tests {
         assert!(num_shapes < 1 << 24);
     }
 
+    #[test]
+    fn test_explain_shape() {
+        use p3_baby_bear::BabyBear;
+        use sp1_core_executor::{Instruction, Opcode};
+
+        let shape_config = CoreShapeConfig::<BabyBear>::default();
+        let instructions =
+            vec![Instruction::new(Opcode::ADD, 29, 0, 5, false, true); (1 << 19) + 1];
+        let program = Program::new(instructions, 0, 0);
+
+        let explanation = shape_config.explain_shape(&program).unwrap();
+        assert_eq!(explanation.shape.inner["Program"], 20);
+        assert_eq!(explanation.shape.inner["MemoryProgram"], 19);
+        assert_eq!(
+            explanation.forced_by,
+            Some(ShapeJump {
+                chip: "Program".to_string(),
+                rows: (1 << 19) + 1,
+                log_height: 20,
+                smaller_log_height: 19,
+                padding: (1 << 19) - 1,
+                excess: 1,
+            })
+        );
+        let next_smaller_shape = explanation.next_smaller_shape.unwrap();
+        assert_eq!(next_smaller_shape.inner["Program"], 19);
+        assert_eq!(next_smaller_shape.inner["MemoryProgram"], 19);
+
+        let mut fixed_program = program.clone();
+        shape_config.fix_preprocessed_shape(&mut fixed_program).unwrap();
+        assert_eq!(fixed_program.preprocessed_shape, Some(explanation.shape));
+    }
+
     #[test]
     fn test_dummy_record() {
         use crate::utils::setup_logger;
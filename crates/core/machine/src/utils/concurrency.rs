@@ -1,4 +1,10 @@
-use std::sync::{Condvar, Mutex};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        mpsc::{SendError, SyncSender},
+        Condvar, Mutex,
+    },
+};
 
 /// A turn-based synchronization primitive.
 pub struct TurnBasedSync {
@@ -27,3 +33,159 @@ impl TurnBasedSync {
         self.cv.notify_all();
     }
 }
+
+/// A channel sender that forwards items in index order, whatever order they are sent in.
+///
+/// Items are sent with their index, starting from 0. An item is forwarded to the underlying
+/// channel once every item with a lower index has been, and is buffered until then. At most
+/// `capacity` indices, starting from the next one to forward, are accepted at a time, so that the
+/// buffered items are bounded.
+pub struct OrderedChannel<T> {
+    state: Mutex<OrderedChannelState<T>>,
+    advanced: Condvar,
+    capacity: usize,
+}
+
+struct OrderedChannelState<T> {
+    tx: SyncSender<T>,
+    next_index: usize,
+    pending: BTreeMap<usize, T>,
+    closed: bool,
+}
+
+impl<T> OrderedChannel<T> {
+    /// Creates a new [OrderedChannel] forwarding to `tx`, which accepts the indices below the next
+    /// one to forward plus `capacity`.
+    pub fn new(tx: SyncSender<T>, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be at least 1");
+        OrderedChannel {
+            state: Mutex::new(OrderedChannelState {
+                tx,
+                next_index: 0,
+                pending: BTreeMap::new(),
+                closed: false,
+            }),
+            advanced: Condvar::new(),
+            capacity,
+        }
+    }
+
+    /// Sends the item with the given index.
+    ///
+    /// This blocks while the index is `capacity` or more ahead of the next one to forward, and
+    /// while the underlying channel is full. It returns an error if the channel was closed or its
+    /// receiver was dropped. An item that is buffered behind a missing index is never forwarded if
+    /// that index is never sent, so a sender that drops an index should [close](Self::close) the
+    /// channel.
+    pub fn send(&self, index: usize, item: T) -> Result<(), SendError<T>> {
+        let mut state = self.state.lock().unwrap();
+        while !state.closed && index >= state.next_index + self.capacity {
+            state = self.advanced.wait(state).unwrap();
+        }
+        if state.closed {
+            return Err(SendError(item));
+        }
+        assert!(
+            index >= state.next_index && !state.pending.contains_key(&index),
+            "index {index} was already sent"
+        );
+        if index != state.next_index {
+            state.pending.insert(index, item);
+            return Ok(());
+        }
+        state.tx.send(item)?;
+        state.next_index += 1;
+        while let Some(item) = state.pending.remove(&state.next_index) {
+            state.tx.send(item)?;
+            state.next_index += 1;
+        }
+        self.advanced.notify_all();
+        Ok(())
+    }
+
+    /// Closes the channel, dropping the buffered items and failing the blocked and future sends.
+    pub fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        state.pending.clear();
+        self.advanced.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc::sync_channel,
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use super::OrderedChannel;
+
+    #[test]
+    fn test_ordered_channel_stress() {
+        const NUM_THREADS: usize = 16;
+        const NUM_ITEMS: usize = 10_000;
+
+        let (tx, rx) = sync_channel(4);
+        let channel = Arc::new(OrderedChannel::new(tx, NUM_ITEMS));
+        let handles = (0..NUM_THREADS)
+            .map(|thread_index| {
+                let channel = Arc::clone(&channel);
+                thread::spawn(move || {
+                    // Each thread sends a strided slice of the indices, from the last one down, so
+                    // that most items arrive before the ones preceding them.
+                    let indices = (thread_index..NUM_ITEMS).step_by(NUM_THREADS).rev();
+                    for index in indices {
+                        channel.send(index, index).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        drop(channel);
+
+        let received = rx.iter().collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(received, (0..NUM_ITEMS).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_ordered_channel_capacity() {
+        let (tx, rx) = sync_channel(8);
+        let channel = Arc::new(OrderedChannel::new(tx, 2));
+        channel.send(1, 1).unwrap();
+
+        // Index 2 is two ahead of the next index to forward, so it blocks until index 0 is sent.
+        let sent = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let channel = Arc::clone(&channel);
+            let sent = Arc::clone(&sent);
+            thread::spawn(move || {
+                channel.send(2, 2).unwrap();
+                sent.store(true, Ordering::SeqCst);
+            })
+        };
+        thread::sleep(Duration::from_millis(100));
+        assert!(!sent.load(Ordering::SeqCst));
+
+        channel.send(0, 0).unwrap();
+        handle.join().unwrap();
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), [0, 1, 2]);
+
+        // Closing the channel fails the sends blocked behind an index that is never sent.
+        let handle = {
+            let channel = Arc::clone(&channel);
+            thread::spawn(move || channel.send(5, 5))
+        };
+        thread::sleep(Duration::from_millis(100));
+        channel.close();
+        assert!(handle.join().unwrap().is_err());
+        assert!(channel.send(3, 3).is_err());
+    }
+}
@@ -4,7 +4,11 @@ use std::{
     io::{
         Seek, {self},
     },
-    sync::{mpsc::sync_channel, Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{sync_channel, SyncSender},
+        Arc, Mutex,
+    },
 };
 use web_time::Instant;
 
@@ -36,13 +40,13 @@ use sp1_core_executor::{
 use sp1_primitives::io::SP1PublicValues;
 
 use sp1_core_executor::{
-    subproof::NoOpSubproofVerifier, ExecutionError, ExecutionRecord, ExecutionReport, Executor,
-    Program, SP1Context,
+    subproof::NoOpSubproofVerifier, CheckpointDir, CheckpointManifest, ExecutionError,
+    ExecutionRecord, ExecutionReport, Executor, Program, SP1Context,
 };
 use sp1_stark::{
     air::{MachineAir, PublicValues},
     Com, CpuProver, DebugConstraintBuilder, InteractionBuilder, MachineProof, MachineProver,
-    MachineRecord, OpeningProof, PcsProverData, ProverConstraintFolder, SP1CoreOpts,
+    MachineRecord, OpeningProof, PcsProverData, ProverConstraintFolder, SP1CoreOpts, ShardProof,
     StarkGenericConfig, StarkMachine, StarkProvingKey, StarkVerifyingKey, UniConfig, Val,
     VerifierConstraintFolder,
 };
@@ -55,6 +59,12 @@ pub enum SP1CoreProverError {
     IoError(io::Error),
     #[error("serialization error: {0}")]
     SerializationError(bincode::Error),
+    #[error("proving was cancelled")]
+    Cancelled,
+    #[error("the checkpoints were taken from a different {0}")]
+    CheckpointMismatch(&'static str),
+    #[error("an execution with precompile overrides cannot be proven")]
+    PrecompileOverrides,
 }
 
 pub fn prove_simple<SC: StarkGenericConfig, P: MachineProver<SC, RiscvAir<SC::Val>>>(
@@ -143,6 +153,151 @@ where
     Com<SC>: Send + Sync,
     PcsProverData<SC>: Send + Sync,
 {
+    prove_with_context_cancellable(
+        prover,
+        pk,
+        program,
+        stdin,
+        opts,
+        context,
+        shape_config,
+        &AtomicBool::new(false),
+    )
+}
+
+/// Like [`prove_with_context`], but stops early and returns [`SP1CoreProverError::Cancelled`]
+/// once `cancel` is set.
+///
+/// Execution stops at the next checkpoint, and the remaining checkpoints are skipped by the trace
+/// generation and proving workers, which have all exited by the time this returns.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_with_context_cancellable<
+    SC: StarkGenericConfig,
+    P: MachineProver<SC, RiscvAir<SC::Val>>,
+>(
+    prover: &P,
+    pk: &P::DeviceProvingKey,
+    program: Program,
+    stdin: &SP1Stdin,
+    opts: SP1CoreOpts,
+    context: SP1Context,
+    shape_config: Option<&CoreShapeConfig<SC::Val>>,
+    cancel: &AtomicBool,
+) -> Result<(MachineProof<SC>, Vec<u8>, u64), SP1CoreProverError>
+where
+    SC::Val: PrimeField32,
+    SC::Challenger: 'static + Clone + Send,
+    OpeningProof<SC>: Send,
+    Com<SC>: Send + Sync,
+    PcsProverData<SC>: Send + Sync,
+{
+    prove_with_context_impl(prover, pk, program, stdin, opts, context, shape_config, cancel, None)
+}
+
+/// Like [`prove_with_context_cancellable`], but hands each shard proof to `sink` as soon as it is
+/// generated instead of collecting them, returning the number of shard proofs.
+///
+/// The sink is called in shard order with the index of the shard proof. Proving stops at the
+/// first error returned by the sink, which is returned as [`SP1CoreProverError::IoError`].
+#[allow(clippy::too_many_arguments)]
+pub fn prove_with_context_to_sink<SC: StarkGenericConfig, P: MachineProver<SC, RiscvAir<SC::Val>>>(
+    prover: &P,
+    pk: &P::DeviceProvingKey,
+    program: Program,
+    stdin: &SP1Stdin,
+    opts: SP1CoreOpts,
+    context: SP1Context,
+    shape_config: Option<&CoreShapeConfig<SC::Val>>,
+    cancel: &AtomicBool,
+    sink: &(dyn Fn(usize, ShardProof<SC>) -> io::Result<()> + Sync),
+) -> Result<(usize, Vec<u8>, u64), SP1CoreProverError>
+where
+    SC::Val: PrimeField32,
+    SC::Challenger: 'static + Clone + Send,
+    OpeningProof<SC>: Send,
+    Com<SC>: Send + Sync,
+    PcsProverData<SC>: Send + Sync,
+{
+    let num_shards = AtomicUsize::new(0);
+    let counting_sink = |index: usize, proof: ShardProof<SC>| -> io::Result<()> {
+        sink(index, proof)?;
+        num_shards.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    };
+    let (_, public_values_stream, cycles) = prove_with_context_impl(
+        prover,
+        pk,
+        program,
+        stdin,
+        opts,
+        context,
+        shape_config,
+        cancel,
+        Some(&counting_sink),
+    )?;
+    Ok((num_shards.into_inner(), public_values_stream, cycles))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn prove_with_context_impl<SC: StarkGenericConfig, P: MachineProver<SC, RiscvAir<SC::Val>>>(
+    prover: &P,
+    pk: &P::DeviceProvingKey,
+    program: Program,
+    stdin: &SP1Stdin,
+    opts: SP1CoreOpts,
+    context: SP1Context,
+    shape_config: Option<&CoreShapeConfig<SC::Val>>,
+    cancel: &AtomicBool,
+    sink: Option<&(dyn Fn(usize, ShardProof<SC>) -> io::Result<()> + Sync)>,
+) -> Result<(MachineProof<SC>, Vec<u8>, u64), SP1CoreProverError>
+where
+    SC::Val: PrimeField32,
+    SC::Challenger: 'static + Clone + Send,
+    OpeningProof<SC>: Send,
+    Com<SC>: Send + Sync,
+    PcsProverData<SC>: Send + Sync,
+{
+    // The overridden syscalls would be executed with their mocked effect, which the real
+    // precompile tables can't prove.
+    if !context.precompile_overrides.is_empty() {
+        return Err(SP1CoreProverError::PrecompileOverrides);
+    }
+
+    // Resume from persisted checkpoints if the context points at a complete, compatible set.
+    // Checkpoints of another program or stdin are an error rather than silently proven.
+    let mut resume = None;
+    if let Some(dir) = context.checkpoint_dir.clone().map(CheckpointDir::new) {
+        match dir.read_manifest() {
+            Ok(manifest) => {
+                if manifest.program_digest != CheckpointManifest::program_digest(&program) {
+                    return Err(SP1CoreProverError::CheckpointMismatch("program"));
+                }
+                if manifest.stdin_digest
+                    != CheckpointManifest::stdin_digest(&stdin.buffer, &stdin.proofs)
+                {
+                    return Err(SP1CoreProverError::CheckpointMismatch("stdin"));
+                }
+                if manifest.shard_size == opts.shard_size
+                    && manifest.shard_batch_size == opts.shard_batch_size
+                {
+                    resume = Some((dir, manifest));
+                } else {
+                    tracing::warn!(
+                        "checkpoints in {} use different shard options, executing instead",
+                        dir.path().display()
+                    );
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "no complete checkpoints in {}, executing instead: {}",
+                    dir.path().display(),
+                    e
+                );
+            }
+        }
+    }
+
     // Setup the runtime.
     let mut runtime = Executor::with_context(program.clone(), opts, context);
     runtime.maximal_shapes = shape_config
@@ -156,6 +311,12 @@ where
     #[cfg(feature = "debug")]
     let (all_records_tx, all_records_rx) = std::sync::mpsc::channel::<Vec<ExecutionRecord>>();
 
+    // A trace worker that can't decode or re-execute its checkpoint records the error here, and
+    // the other threads then wind down as if the proof were cancelled.
+    let trace_error: Mutex<Option<SP1CoreProverError>> = Mutex::new(None);
+    let trace_error = &trace_error;
+    let stopped = move || cancel.load(Ordering::SeqCst) || trace_error.lock().unwrap().is_some();
+
     // Record the start of the process.
     let proving_start = Instant::now();
     let span = tracing::Span::current().clone();
@@ -169,9 +330,19 @@ where
         let checkpoint_generator_handle: ScopedJoinHandle<Result<_, SP1CoreProverError>> =
             s.spawn(move || {
                 let _span = checkpoint_generator_span.enter();
+                if let Some((dir, manifest)) = resume {
+                    return tracing::debug_span!("checkpoint reader").in_scope(|| {
+                        send_persisted_checkpoints(&dir, manifest, &checkpoints_tx, cancel)
+                    });
+                }
                 tracing::debug_span!("checkpoint generator").in_scope(|| {
                     let mut index = 0;
                     loop {
+                        // Stop executing once cancelled or a trace worker failed.
+                        if stopped() {
+                            break Err(SP1CoreProverError::Cancelled);
+                        }
+
                         // Enter the span.
                         let span = tracing::debug_span!("batch");
                         let _span = span.enter();
@@ -237,16 +408,35 @@ where
                         let received = { checkpoints_rx.lock().unwrap().recv() };
 
                         if let Ok((index, mut checkpoint, done)) = received {
-                            // Trace the checkpoint and reconstruct the execution records.
-                            let (mut records, _) = tracing::debug_span!("trace checkpoint")
-                                .in_scope(|| {
-                                    trace_checkpoint::<SC>(
-                                        program.clone(),
-                                        &checkpoint,
-                                        opts,
-                                        shape_config,
-                                    )
-                                });
+                            // Trace the checkpoint and reconstruct the execution records, unless
+                            // proving was stopped.
+                            let traced = if stopped() {
+                                None
+                            } else {
+                                tracing::debug_span!("trace checkpoint")
+                                    .in_scope(|| {
+                                        trace_checkpoint::<SC>(
+                                            program.clone(),
+                                            &checkpoint,
+                                            opts,
+                                            shape_config,
+                                        )
+                                    })
+                                    .map_err(|e| {
+                                        trace_error.lock().unwrap().get_or_insert(e);
+                                    })
+                                    .ok()
+                            };
+
+                            // Once stopped, skip the checkpoint but keep taking turns so that the
+                            // other workers can drain the channel.
+                            let Some((mut records, _)) = traced else {
+                                record_gen_sync.wait_for_turn(index);
+                                record_gen_sync.advance_turn();
+                                trace_gen_sync.wait_for_turn(index);
+                                trace_gen_sync.advance_turn();
+                                continue;
+                            };
                             tracing::debug!("generated {} records", records.len());
                             reset_seek(&mut checkpoint);
 
@@ -363,6 +553,9 @@ where
             let _span = phase_1_prover_span.enter();
             tracing::debug_span!("phase 1 prover").in_scope(|| {
                 for (records, traces) in p1_records_and_traces_rx.iter() {
+                    if stopped() {
+                        continue;
+                    }
                     tracing::debug_span!("batch").in_scope(|| {
                         let span = tracing::Span::current().clone();
 
@@ -416,7 +609,7 @@ where
         });
 
         // Wait until the checkpoint generator handle has fully finished.
-        let public_values_stream = checkpoint_generator_handle.join().unwrap().unwrap();
+        let public_values_stream = checkpoint_generator_handle.join().unwrap();
 
         // Wait until the records and traces have been fully generated.
         p1_record_and_trace_gen_handles.into_iter().for_each(|handle| handle.join().unwrap());
@@ -424,6 +617,14 @@ where
         // Wait until the phase 1 prover has completely finished.
         let mut challenger = phase_1_prover_handle.join().unwrap();
 
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SP1CoreProverError::Cancelled);
+        }
+        if let Some(e) = trace_error.lock().unwrap().take() {
+            return Err(e);
+        }
+        let public_values_stream = public_values_stream?;
+
         // Sample for the global permutation challenges.
         // Obtain the challenges used for the global permutation argument.
         let mut global_permutation_challenges: Vec<SC::Challenge> = Vec::new();
@@ -471,16 +672,35 @@ where
                         // Receive the latest checkpoint.
                         let received = { checkpoints.lock().unwrap().pop_front() };
                         if let Some((index, mut checkpoint, done)) = received {
-                            // Trace the checkpoint and reconstruct the execution records.
-                            let (mut records, report) = tracing::debug_span!("trace checkpoint")
-                                .in_scope(|| {
-                                    trace_checkpoint::<SC>(
-                                        program.clone(),
-                                        &checkpoint,
-                                        opts,
-                                        shape_config,
-                                    )
-                                });
+                            // Trace the checkpoint and reconstruct the execution records, unless
+                            // proving was stopped.
+                            let traced = if stopped() {
+                                None
+                            } else {
+                                tracing::debug_span!("trace checkpoint")
+                                    .in_scope(|| {
+                                        trace_checkpoint::<SC>(
+                                            program.clone(),
+                                            &checkpoint,
+                                            opts,
+                                            shape_config,
+                                        )
+                                    })
+                                    .map_err(|e| {
+                                        trace_error.lock().unwrap().get_or_insert(e);
+                                    })
+                                    .ok()
+                            };
+
+                            // Once stopped, skip the checkpoint but keep taking turns so that the
+                            // other workers can finish.
+                            let Some((mut records, report)) = traced else {
+                                record_gen_sync.wait_for_turn(index);
+                                record_gen_sync.advance_turn();
+                                trace_gen_sync.wait_for_turn(index);
+                                trace_gen_sync.advance_turn();
+                                continue;
+                            };
                             log::debug!("generated {} records", records.len());
                             *report_aggregate.lock().unwrap() += report;
                             reset_seek(&mut checkpoint);
@@ -610,11 +830,17 @@ where
         let p2_prover_handle = s.spawn(move || {
             let _span = p2_prover_span.enter();
             let mut shard_proofs = Vec::new();
+            let mut num_shards = 0;
+            let mut sink_result = Ok(());
             tracing::debug_span!("phase 2 prover").in_scope(|| {
                 for (records, traces) in p2_records_and_traces_rx.into_iter() {
+                    if stopped() || sink_result.is_err() {
+                        continue;
+                    }
                     tracing::debug_span!("batch").in_scope(|| {
                         let span = tracing::Span::current().clone();
-                        shard_proofs.par_extend(
+                        let mut batch_proofs = Vec::new();
+                        batch_proofs.par_extend(
                             records.into_par_iter().zip(traces.into_par_iter()).map(
                                 |(record, (global_traces, local_traces))| {
                                     let _span = span.enter();
@@ -653,10 +879,24 @@ where
                                 },
                             ),
                         );
+
+                        // Hand the proofs to the sink, if any, so that they are not kept in memory.
+                        match sink {
+                            Some(sink) => {
+                                for proof in batch_proofs {
+                                    sink_result = sink(num_shards, proof);
+                                    if sink_result.is_err() {
+                                        break;
+                                    }
+                                    num_shards += 1;
+                                }
+                            }
+                            None => shard_proofs.extend(batch_proofs),
+                        }
                     });
                 }
             });
-            shard_proofs
+            sink_result.map(|()| shard_proofs)
         });
 
         // Wait until the records and traces have been fully generated for phase 2.
@@ -665,6 +905,14 @@ where
         // Wait until the phase 2 prover has finished.
         let shard_proofs = p2_prover_handle.join().unwrap();
 
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SP1CoreProverError::Cancelled);
+        }
+        if let Some(e) = trace_error.lock().unwrap().take() {
+            return Err(e);
+        }
+        let shard_proofs = shard_proofs.map_err(SP1CoreProverError::IoError)?;
+
         // Log some of the `ExecutionReport` information.
         let report_aggregate = report_aggregate.lock().unwrap();
         tracing::info!(
@@ -859,13 +1107,13 @@ fn trace_checkpoint<SC: StarkGenericConfig>(
     file: &File,
     opts: SP1CoreOpts,
     shape_config: Option<&CoreShapeConfig<SC::Val>>,
-) -> (Vec<ExecutionRecord>, ExecutionReport)
+) -> Result<(Vec<ExecutionRecord>, ExecutionReport), SP1CoreProverError>
 where
     <SC as StarkGenericConfig>::Val: PrimeField32,
 {
     let mut reader = std::io::BufReader::new(file);
     let state: ExecutionState =
-        bincode::deserialize_from(&mut reader).expect("failed to deserialize state");
+        bincode::deserialize_from(&mut reader).map_err(SP1CoreProverError::SerializationError)?;
     let mut runtime = Executor::recover(program, state, opts);
     runtime.maximal_shapes = shape_config
         .map(|config| config.maximal_core_shapes().into_iter().map(|s| s.inner).collect());
@@ -875,9 +1123,28 @@ where
     runtime.subproof_verifier = Arc::new(NoOpSubproofVerifier);
 
     // Execute from the checkpoint.
-    let (records, _) = runtime.execute_record(true).unwrap();
+    let (records, _) = runtime.execute_record(true).map_err(SP1CoreProverError::ExecutionError)?;
+
+    Ok((records, runtime.report))
+}
 
-    (records, runtime.report)
+/// Send the checkpoints persisted in `dir` in order, returning the public values stream of the
+/// execution they were taken from.
+fn send_persisted_checkpoints(
+    dir: &CheckpointDir,
+    manifest: CheckpointManifest,
+    checkpoints_tx: &SyncSender<(usize, File, bool)>,
+    cancel: &AtomicBool,
+) -> Result<Vec<u8>, SP1CoreProverError> {
+    for index in 0..manifest.num_checkpoints {
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SP1CoreProverError::Cancelled);
+        }
+        let checkpoint = dir.open_checkpoint(index).map_err(SP1CoreProverError::IoError)?;
+        let done = index + 1 == manifest.num_checkpoints;
+        checkpoints_tx.send((index, checkpoint, done)).unwrap();
+    }
+    Ok(manifest.public_values_stream)
 }
 
 fn reset_seek(file: &mut File) {
@@ -1,24 +1,29 @@
 use std::{
-    error::Error as StdError,
+    collections::BTreeMap,
     future::Future,
     io::{BufReader, Read, Write},
     process::{Command, Stdio},
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, Mutex, MutexGuard, Once, OnceLock, PoisonError,
     },
+    thread::{self, ThreadId},
     time::{Duration, Instant},
 };
 
 use crate::proto::api::ProverServiceClient;
 use async_trait::async_trait;
-use proto::api::ReadyRequest;
+use proto::api::{ReadyRequest, VersionRequest};
 use reqwest::{Request, Response};
 use serde::{Deserialize, Serialize};
 use sp1_core_machine::{io::SP1Stdin, reduce::SP1ReduceProof, utils::SP1CoreProverError};
 use sp1_prover::{
-    types::SP1ProvingKey, InnerSC, OuterSC, SP1CoreProof, SP1RecursionProverError, SP1VerifyingKey,
+    components::CpuProverComponents, types::SP1ProvingKey, CompressedProof, CoreSC, InnerSC,
+    OuterSC, SP1CoreProof, SP1Prover, SP1RecursionProverError, SP1VerifyingKey, ShrunkProof,
+    SP1_CIRCUIT_VERSION,
 };
+use sp1_stark::MachineVerificationError;
+use thiserror::Error;
 use tokio::task::block_in_place;
 use twirp::{
     async_trait,
@@ -32,6 +37,20 @@ pub mod proto {
     pub mod api;
 }
 
+/// The default proving server image, built for [SP1_CIRCUIT_VERSION].
+///
+/// It can be overridden with the `SP1_GPU_IMAGE` environment variable or
+/// [SP1CudaProver::with_image].
+pub const DEFAULT_GPU_IMAGE: &str = "public.ecr.aws/succinct-labs/sp1-gpu:7e66232";
+
+/// The containers started by this process that have not been removed yet, along with the thread
+/// that started each of them and the `cleaned_up` flag of the [SP1CudaProver] that owns it.
+static RUNNING_CONTAINERS: Mutex<BTreeMap<String, (ThreadId, Arc<AtomicBool>)>> =
+    Mutex::new(BTreeMap::new());
+
+/// Guards the installation of the panic hook that removes the [RUNNING_CONTAINERS].
+static INSTALL_PANIC_HOOK: Once = Once::new();
+
 /// A remote client to [sp1_prover::SP1Prover] that runs inside a container.
 ///
 /// This is currently used to provide experimental support for GPU hardware acceleration.
@@ -44,6 +63,55 @@ pub struct SP1CudaProver {
     container_name: String,
     /// A flag to indicate whether the container has already been cleaned up.
     cleaned_up: Arc<AtomicBool>,
+    /// A local prover used to verify the proofs returned by the container, created on first use.
+    verifier: OnceLock<SP1Prover<CpuProverComponents>>,
+}
+
+/// An error returned when the proving server cannot be used by this client.
+#[derive(Error, Debug)]
+pub enum SP1CudaProverError {
+    #[error(
+        "the proving server runs SP1 {server}, but this client runs SP1 {client}; \
+        use a proving server image built for {client}"
+    )]
+    VersionMismatch { server: String, client: String },
+}
+
+/// An error returned when [SP1CudaProver::new] cannot start or reach the proving server.
+#[derive(Error, Debug)]
+pub enum CudaSetupError {
+    #[error(
+        "Docker is not available or you don't have the necessary permissions. Please ensure \
+        Docker is installed and you are part of the docker group."
+    )]
+    DockerUnavailable,
+    #[error(
+        "Failed to pull Docker image: {0}. Please check your internet connection and Docker \
+        permissions."
+    )]
+    ImagePullFailed(std::io::Error),
+    #[error(
+        "Failed to start Docker container: {0}. Please check your Docker installation and \
+        permissions."
+    )]
+    ContainerStartFailed(std::io::Error),
+    #[error(
+        "Timeout: proving server did not become ready within {} seconds. Please check your \
+        Docker container and network settings.",
+        .0.as_secs()
+    )]
+    ServerTimeout(Duration),
+    #[error(transparent)]
+    Server(#[from] SP1CudaProverError),
+}
+
+/// An error returned by [SP1CudaProver::prove_core_verified].
+#[derive(Error, Debug)]
+pub enum SP1CudaVerifiedProofError {
+    #[error(transparent)]
+    Prove(#[from] SP1CoreProverError),
+    #[error("the proving server returned an invalid proof: {0}")]
+    InvalidProof(#[from] MachineVerificationError<CoreSC>),
 }
 
 /// The payload for the [sp1_prover::SP1Prover::prove_core] method.
@@ -75,7 +143,7 @@ pub struct CompressRequestPayload {
 /// We use this object to serialize and deserialize the payload from the client to the server.
 #[derive(Serialize, Deserialize)]
 pub struct ShrinkRequestPayload {
-    pub reduced_proof: SP1ReduceProof<InnerSC>,
+    pub reduced_proof: CompressedProof,
 }
 
 /// The payload for the [sp1_prover::SP1Prover::wrap_bn254] method.
@@ -83,28 +151,48 @@ pub struct ShrinkRequestPayload {
 /// We use this object to serialize and deserialize the payload from the client to the server.
 #[derive(Serialize, Deserialize)]
 pub struct WrapRequestPayload {
-    pub reduced_proof: SP1ReduceProof<InnerSC>,
+    pub reduced_proof: ShrunkProof,
 }
 
 impl SP1CudaProver {
     /// Creates a new [SP1Prover] that runs inside a Docker container and returns a
     /// [SP1ProverClient] that can be used to communicate with the container.
-    pub fn new() -> Result<Self, Box<dyn StdError>> {
+    ///
+    /// The image is read from `SP1_GPU_IMAGE`, defaulting to [DEFAULT_GPU_IMAGE].
+    pub fn new() -> Result<Self, CudaSetupError> {
+        let image_name =
+            std::env::var("SP1_GPU_IMAGE").unwrap_or_else(|_| DEFAULT_GPU_IMAGE.to_string());
+        Self::with_image(&image_name)
+    }
+
+    /// Creates a new [SP1Prover] that runs inside a Docker container started from `image_name`.
+    ///
+    /// The image must be built for [SP1_CIRCUIT_VERSION]: if the server reports another version,
+    /// this returns [SP1CudaProverError::VersionMismatch] before any proving begins.
+    pub fn with_image(image_name: &str) -> Result<Self, CudaSetupError> {
+        Self::start(image_name, Self::check_docker_availability)
+    }
+
+    /// Starts the container like [Self::with_image], using `docker_available` to check whether
+    /// Docker can be used.
+    fn start(
+        image_name: &str,
+        docker_available: impl FnOnce() -> bool,
+    ) -> Result<Self, CudaSetupError> {
         let container_name = "sp1-gpu";
-        let image_name = "public.ecr.aws/succinct-labs/sp1-gpu:7e66232";
 
         let cleaned_up = Arc::new(AtomicBool::new(false));
         let cleanup_name = container_name;
         let cleanup_flag = cleaned_up.clone();
 
         // Check if Docker is available and the user has necessary permissions
-        if !Self::check_docker_availability()? {
-            return Err("Docker is not available or you don't have the necessary permissions. Please ensure Docker is installed and you are part of the docker group.".into());
+        if !docker_available() {
+            return Err(CudaSetupError::DockerUnavailable);
         }
 
         // Pull the docker image if it's not present
         if let Err(e) = Command::new("docker").args(["pull", image_name]).output() {
-            return Err(format!("Failed to pull Docker image: {}. Please check your internet connection and Docker permissions.", e).into());
+            return Err(CudaSetupError::ImagePullFailed(e));
         }
 
         // Start the docker container
@@ -126,7 +214,8 @@ impl SP1CudaProver {
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
-            .map_err(|e| format!("Failed to start Docker container: {}. Please check your Docker installation and permissions.", e))?;
+            .map_err(CudaSetupError::ContainerStartFailed)?;
+        register_container(container_name, cleaned_up.clone());
 
         let stderr = child.stderr.take().unwrap();
         std::thread::spawn(move || {
@@ -187,7 +276,7 @@ impl SP1CudaProver {
             tracing::info!("waiting for proving server to be ready");
             loop {
                 if start_time.elapsed() > timeout {
-                    return Err("Timeout: proving server did not become ready within 60 seconds. Please check your Docker container and network settings.".to_string());
+                    return Err(CudaSetupError::ServerTimeout(timeout));
                 }
 
                 let request = ReadyRequest {};
@@ -215,17 +304,36 @@ impl SP1CudaProver {
         )
         .expect("failed to create client");
 
+        if let Err(e) = block_on(check_server_version(&client)) {
+            cleanup_container(container_name);
+            cleaned_up.store(true, Ordering::SeqCst);
+            return Err(e.into());
+        }
+
         Ok(SP1CudaProver {
             client,
             container_name: container_name.to_string(),
             cleaned_up: cleaned_up.clone(),
+            verifier: OnceLock::new(),
         })
     }
 
-    fn check_docker_availability() -> Result<bool, Box<dyn std::error::Error>> {
+    /// Removes the container right away, if it has not been removed already.
+    ///
+    /// This is done automatically when the prover is dropped or when the thread that created it
+    /// panics, but it
+    /// can also be called from custom panic or signal handlers. The prover cannot be used
+    /// afterwards.
+    pub fn force_cleanup(&self) {
+        if !self.cleaned_up.swap(true, Ordering::SeqCst) {
+            cleanup_container(&self.container_name);
+        }
+    }
+
+    fn check_docker_availability() -> bool {
         match Command::new("docker").arg("version").output() {
-            Ok(output) => Ok(output.status.success()),
-            Err(_) => Ok(false),
+            Ok(output) => output.status.success(),
+            Err(_) => false,
         }
     }
 
@@ -247,6 +355,22 @@ impl SP1CudaProver {
         Ok(proof)
     }
 
+    /// Executes [SP1CudaProver::prove_core] and verifies the returned proof against `vk` locally
+    /// before returning it, so that a misbehaving server is caught early.
+    ///
+    /// **WARNING**: This is an experimental feature and may not work as expected.
+    pub fn prove_core_verified(
+        &self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        vk: &SP1VerifyingKey,
+    ) -> Result<SP1CoreProof, SP1CudaVerifiedProofError> {
+        let proof = self.prove_core(pk, stdin)?;
+        let verifier = self.verifier.get_or_init(SP1Prover::new);
+        verifier.verify(&proof.proof, vk)?;
+        Ok(proof)
+    }
+
     /// Executes the [sp1_prover::SP1Prover::compress] method inside the container.
     ///
     /// You will need at least 24GB of VRAM to run this method.
@@ -257,13 +381,13 @@ impl SP1CudaProver {
         vk: &SP1VerifyingKey,
         proof: SP1CoreProof,
         deferred_proofs: Vec<SP1ReduceProof<InnerSC>>,
-    ) -> Result<SP1ReduceProof<InnerSC>, SP1RecursionProverError> {
+    ) -> Result<CompressedProof, SP1RecursionProverError> {
         let payload = CompressRequestPayload { vk: vk.clone(), proof, deferred_proofs };
         let request =
             crate::proto::api::CompressRequest { data: bincode::serialize(&payload).unwrap() };
 
         let response = block_on(async { self.client.compress(request).await }).unwrap();
-        let proof: SP1ReduceProof<InnerSC> = bincode::deserialize(&response.result).unwrap();
+        let proof: CompressedProof = bincode::deserialize(&response.result).unwrap();
         Ok(proof)
     }
 
@@ -274,14 +398,14 @@ impl SP1CudaProver {
     /// **WARNING**: This is an experimental feature and may not work as expected.
     pub fn shrink(
         &self,
-        reduced_proof: SP1ReduceProof<InnerSC>,
-    ) -> Result<SP1ReduceProof<InnerSC>, SP1RecursionProverError> {
+        reduced_proof: CompressedProof,
+    ) -> Result<ShrunkProof, SP1RecursionProverError> {
         let payload = ShrinkRequestPayload { reduced_proof: reduced_proof.clone() };
         let request =
             crate::proto::api::ShrinkRequest { data: bincode::serialize(&payload).unwrap() };
 
         let response = block_on(async { self.client.shrink(request).await }).unwrap();
-        let proof: SP1ReduceProof<InnerSC> = bincode::deserialize(&response.result).unwrap();
+        let proof: ShrunkProof = bincode::deserialize(&response.result).unwrap();
         Ok(proof)
     }
 
@@ -292,7 +416,7 @@ impl SP1CudaProver {
     /// **WARNING**: This is an experimental feature and may not work as expected.
     pub fn wrap_bn254(
         &self,
-        reduced_proof: SP1ReduceProof<InnerSC>,
+        reduced_proof: ShrunkProof,
     ) -> Result<SP1ReduceProof<OuterSC>, SP1RecursionProverError> {
         let payload = WrapRequestPayload { reduced_proof: reduced_proof.clone() };
         let request =
@@ -314,14 +438,52 @@ impl Drop for SP1CudaProver {
     fn drop(&mut self) {
         if !self.cleaned_up.load(Ordering::SeqCst) {
             tracing::debug!("dropping SP1ProverClient, cleaning up...");
-            cleanup_container(&self.container_name);
-            self.cleaned_up.store(true, Ordering::SeqCst);
+            self.force_cleanup();
+        }
+    }
+}
+
+fn running_containers() -> MutexGuard<'static, BTreeMap<String, (ThreadId, Arc<AtomicBool>)>> {
+    RUNNING_CONTAINERS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Records a freshly started container so that it is removed if the current thread panics.
+///
+/// A panic unwinding out of an async task does not always reach [SP1CudaProver]'s `Drop`, so the
+/// first registration also installs a panic hook that removes the running containers started by
+/// the panicking thread before chaining to the previous hook. Panics in other threads, including
+/// caught ones, leave the container alone.
+fn register_container(container_name: &str, cleaned_up: Arc<AtomicBool>) {
+    running_containers().insert(container_name.to_string(), (thread::current().id(), cleaned_up));
+    INSTALL_PANIC_HOOK.call_once(|| {
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            cleanup_thread_containers(thread::current().id());
+            previous_hook(info);
+        }));
+    });
+}
+
+/// Removes the containers started by the thread `owner` that are still running.
+fn cleanup_thread_containers(owner: ThreadId) {
+    let mut containers = Vec::new();
+    running_containers().retain(|container_name, (thread, cleaned_up)| {
+        if *thread != owner {
+            return true;
+        }
+        containers.push((container_name.clone(), cleaned_up.clone()));
+        false
+    });
+    for (container_name, cleaned_up) in containers {
+        if !cleaned_up.swap(true, Ordering::SeqCst) {
+            cleanup_container(&container_name);
         }
     }
 }
 
 /// Cleans up the a docker container with the given name.
 fn cleanup_container(container_name: &str) {
+    running_containers().remove(container_name);
     if let Err(e) = Command::new("docker").args(["rm", "-f", container_name]).output() {
         eprintln!(
             "Failed to remove container: {}. You may need to manually remove it using 'docker rm -f {}'",
@@ -330,6 +492,28 @@ fn cleanup_container(container_name: &str) {
     }
 }
 
+/// Checks that the proving server was built for [SP1_CIRCUIT_VERSION].
+///
+/// Images that predate the `Version` RPC cannot be checked, in which case only a warning is logged.
+async fn check_server_version(client: &Client) -> Result<(), SP1CudaProverError> {
+    match client.version(VersionRequest {}).await {
+        Ok(response) if response.version == SP1_CIRCUIT_VERSION => Ok(()),
+        Ok(response) => Err(SP1CudaProverError::VersionMismatch {
+            server: response.version,
+            client: SP1_CIRCUIT_VERSION.to_string(),
+        }),
+        Err(e) => {
+            tracing::warn!(
+                "could not query the proving server version, make sure its image is built for \
+                {}: {}",
+                SP1_CIRCUIT_VERSION,
+                e
+            );
+            Ok(())
+        }
+    }
+}
+
 /// Utility method for blocking on an async function.
 ///
 /// If we're already in a tokio runtime, we'll block in place. Otherwise, we'll create a new
@@ -371,11 +555,98 @@ mod tests {
     use test_artifacts::FIBONACCI_ELF;
     use twirp::{url::Url, Client};
 
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    };
+
     use crate::{
-        proto::api::ProverServiceClient, CompressRequestPayload, ProveCoreRequestPayload,
-        SP1CudaProver, SP1Stdin,
+        block_on, check_server_version,
+        proto::api::{
+            router, CompressRequest, CompressResponse, ProveCoreRequest, ProveCoreResponse,
+            ProverService, ProverServiceClient, ReadyRequest, ReadyResponse, ShrinkRequest,
+            ShrinkResponse, VersionRequest, VersionResponse, WrapRequest, WrapResponse,
+            SERVICE_FQN,
+        },
+        register_container, running_containers, CompressRequestPayload, CudaSetupError,
+        ProveCoreRequestPayload, SP1CudaProver, SP1CudaProverError, SP1CudaVerifiedProofError,
+        SP1Stdin, SP1_CIRCUIT_VERSION,
     };
 
+    /// A proving server that reports a fixed version and answers every `prove_core` request with
+    /// the same proof.
+    struct MockServer {
+        version: String,
+        proof: Vec<u8>,
+    }
+
+    #[twirp::async_trait::async_trait]
+    impl ProverService for MockServer {
+        async fn ready(
+            &self,
+            _ctx: twirp::Context,
+            _req: ReadyRequest,
+        ) -> Result<ReadyResponse, twirp::TwirpErrorResponse> {
+            Ok(ReadyResponse { ready: true })
+        }
+
+        async fn version(
+            &self,
+            _ctx: twirp::Context,
+            _req: VersionRequest,
+        ) -> Result<VersionResponse, twirp::TwirpErrorResponse> {
+            Ok(VersionResponse { version: self.version.clone() })
+        }
+
+        async fn prove_core(
+            &self,
+            _ctx: twirp::Context,
+            _req: ProveCoreRequest,
+        ) -> Result<ProveCoreResponse, twirp::TwirpErrorResponse> {
+            Ok(ProveCoreResponse { result: self.proof.clone() })
+        }
+
+        async fn compress(
+            &self,
+            _ctx: twirp::Context,
+            _req: CompressRequest,
+        ) -> Result<CompressResponse, twirp::TwirpErrorResponse> {
+            Err(twirp::internal("unimplemented"))
+        }
+
+        async fn shrink(
+            &self,
+            _ctx: twirp::Context,
+            _req: ShrinkRequest,
+        ) -> Result<ShrinkResponse, twirp::TwirpErrorResponse> {
+            Err(twirp::internal("unimplemented"))
+        }
+
+        async fn wrap(
+            &self,
+            _ctx: twirp::Context,
+            _req: WrapRequest,
+        ) -> Result<WrapResponse, twirp::TwirpErrorResponse> {
+            Err(twirp::internal("unimplemented"))
+        }
+    }
+
+    /// Serves `server` on a local port from a background thread and returns a client to it.
+    fn spawn_mock_server(server: MockServer) -> Client {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        listener.set_nonblocking(true).unwrap();
+        std::thread::spawn(move || {
+            tokio::runtime::Runtime::new().unwrap().block_on(async move {
+                let twirp_routes = axum::Router::new().nest(SERVICE_FQN, router(Arc::new(server)));
+                let app = axum::Router::new().nest("/twirp", twirp_routes);
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                axum::serve(listener, app).await.unwrap();
+            })
+        });
+        Client::from_base_url(Url::parse(&format!("http://{addr}/twirp/")).unwrap()).unwrap()
+    }
+
     #[test]
     fn test_client() {
         setup_logger();
@@ -434,4 +705,91 @@ mod tests {
         tracing::info!("verify compressed");
         prover.verify_compressed(&compressed_proof, &vk).unwrap();
     }
+
+    #[test]
+    fn test_prove_core_verified_rejects_corrupted_proof() {
+        setup_logger();
+
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let (pk, vk) = prover.setup(FIBONACCI_ELF);
+        let mut proof = prover
+            .prove_core(&pk, &SP1Stdin::new(), Default::default(), Default::default())
+            .unwrap();
+        proof.proof.0[0].public_values.rotate_left(1);
+
+        // Serve the corrupted proof from a local server instead of the GPU container.
+        let server = MockServer {
+            version: SP1_CIRCUIT_VERSION.to_string(),
+            proof: bincode::serialize(&proof).unwrap(),
+        };
+        let client = SP1CudaProver {
+            client: spawn_mock_server(server),
+            container_name: String::new(),
+            cleaned_up: Arc::new(AtomicBool::new(true)),
+            verifier: OnceLock::new(),
+        };
+        let result = client.prove_core_verified(&pk, &SP1Stdin::new(), &vk);
+        assert!(matches!(result, Err(SP1CudaVerifiedProofError::InvalidProof(_))));
+    }
+
+    #[test]
+    fn test_server_version_mismatch() {
+        let server = MockServer { version: "v0.0.0-mismatch".to_string(), proof: vec![] };
+        let client = spawn_mock_server(server);
+        let result = block_on(check_server_version(&client));
+        assert!(matches!(
+            result,
+            Err(SP1CudaProverError::VersionMismatch { server, .. }) if server == "v0.0.0-mismatch"
+        ));
+    }
+
+    #[test]
+    fn test_docker_unavailable() {
+        let result = SP1CudaProver::start("unused", || false);
+        assert!(matches!(result, Err(CudaSetupError::DockerUnavailable)));
+    }
+
+    #[test]
+    fn test_panic_removes_container() {
+        let container_name = "sp1-gpu-test-panic";
+        let cleaned_up = Arc::new(AtomicBool::new(false));
+        register_container(container_name, cleaned_up.clone());
+        let prover = SP1CudaProver {
+            client: Client::from_base_url(Url::parse("http://localhost:3000/twirp/").unwrap())
+                .unwrap(),
+            container_name: container_name.to_string(),
+            cleaned_up: cleaned_up.clone(),
+            verifier: OnceLock::new(),
+        };
+
+        // Keep the prover from being dropped during unwinding, so that only the panic hook can
+        // remove the container.
+        let result = std::panic::catch_unwind(move || {
+            let _prover = std::mem::ManuallyDrop::new(prover);
+            panic!("simulated panic during a proving call");
+        });
+
+        assert!(result.is_err());
+        assert!(cleaned_up.load(Ordering::SeqCst));
+        assert!(!running_containers().contains_key(container_name));
+    }
+
+    #[test]
+    fn test_panic_in_other_thread_keeps_container() {
+        let container_name = "sp1-gpu-test-other-thread-panic";
+        let cleaned_up = Arc::new(AtomicBool::new(false));
+        register_container(container_name, cleaned_up.clone());
+
+        // A panic caught in another thread, like the ones in the shape code, must not remove a
+        // container that is still in use.
+        let result = std::thread::spawn(|| {
+            std::panic::catch_unwind(|| panic!("simulated caught panic")).is_err()
+        })
+        .join();
+
+        assert!(result.unwrap());
+        assert!(!cleaned_up.load(Ordering::SeqCst));
+        assert!(running_containers().contains_key(container_name));
+        running_containers().remove(container_name);
+    }
 }
@@ -9,6 +9,15 @@ pub struct ReadyResponse {
     pub ready: bool,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct VersionRequest {}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VersionResponse {
+    #[prost(string, tag = "1")]
+    pub version: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ProveCoreRequest {
     #[prost(bytes = "vec", tag = "1")]
@@ -65,6 +74,11 @@ pub trait ProverService {
         ctx: twirp::Context,
         req: ReadyRequest,
     ) -> Result<ReadyResponse, twirp::TwirpErrorResponse>;
+    async fn version(
+        &self,
+        ctx: twirp::Context,
+        req: VersionRequest,
+    ) -> Result<VersionResponse, twirp::TwirpErrorResponse>;
     async fn prove_core(
         &self,
         ctx: twirp::Context,
@@ -98,6 +112,13 @@ where
     ) -> Result<ReadyResponse, twirp::TwirpErrorResponse> {
         T::ready(&*self, ctx, req).await
     }
+    async fn version(
+        &self,
+        ctx: twirp::Context,
+        req: VersionRequest,
+    ) -> Result<VersionResponse, twirp::TwirpErrorResponse> {
+        T::version(&*self, ctx, req).await
+    }
     async fn prove_core(
         &self,
         ctx: twirp::Context,
@@ -138,6 +159,12 @@ where
                 api.ready(ctx, req).await
             },
         )
+        .route(
+            "/Version",
+            |api: T, ctx: twirp::Context, req: VersionRequest| async move {
+                api.version(ctx, req).await
+            },
+        )
         .route(
             "/ProveCore",
             |api: T, ctx: twirp::Context, req: ProveCoreRequest| async move {
@@ -170,6 +197,10 @@ pub trait ProverServiceClient: Send + Sync + std::fmt::Debug {
         &self,
         req: ReadyRequest,
     ) -> Result<ReadyResponse, twirp::ClientError>;
+    async fn version(
+        &self,
+        req: VersionRequest,
+    ) -> Result<VersionResponse, twirp::ClientError>;
     async fn prove_core(
         &self,
         req: ProveCoreRequest,
@@ -192,6 +223,12 @@ impl ProverServiceClient for twirp::client::Client {
     ) -> Result<ReadyResponse, twirp::ClientError> {
         self.request("api.ProverService/Ready", req).await
     }
+    async fn version(
+        &self,
+        req: VersionRequest,
+    ) -> Result<VersionResponse, twirp::ClientError> {
+        self.request("api.ProverService/Version", req).await
+    }
     async fn prove_core(
         &self,
         req: ProveCoreRequest,
@@ -1,14 +1,19 @@
 use anyhow::Result;
-use clap::{command, Parser};
+use clap::{command, Parser, ValueEnum};
 use reqwest::Client;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use slack_rust::chat::post_message::{post_message, PostMessageRequest};
 use slack_rust::http_client::default_client;
 use sp1_prover::{components::SP1ProverComponents, utils::get_cycles, SP1Prover};
 use sp1_sdk::{SP1Context, SP1Stdin};
 use sp1_stark::SP1ProverOpts;
-use std::time::{Duration, Instant};
+use std::{
+    fs::File,
+    io::Write,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 use program::load_program;
 
@@ -16,6 +21,16 @@ use crate::program::{TesterProgram, PROGRAMS};
 
 mod program;
 
+/// The format in which evaluation results are emitted.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// A human readable markdown table.
+    #[default]
+    Markdown,
+    /// The raw performance reports serialized as JSON.
+    Json,
+}
+
 #[derive(Parser, Clone)]
 #[command(about = "Evaluate the performance of SP1 on programs.")]
 struct EvalArgs {
@@ -70,20 +85,36 @@ struct EvalArgs {
     /// The author of the commit.
     #[arg(long)]
     pub author: Option<String>,
+
+    /// The format to emit results in.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Markdown)]
+    pub output: OutputFormat,
+
+    /// Whether to also measure the time it takes to verify the compressed proof.
+    #[arg(long, default_missing_value="true", num_args=0..=1)]
+    pub with_verify: Option<bool>,
+
+    /// The number of times to prove each program, reporting the median timings.
+    #[arg(long, default_value_t = 1)]
+    pub iterations: usize,
+
+    /// The file to write JSON results to, only used if output is json. Defaults to stdout.
+    #[arg(long)]
+    pub json_path: Option<PathBuf>,
 }
 
 pub async fn evaluate_performance<C: SP1ProverComponents>(
-    opts: SP1ProverOpts,
+    mut opts: SP1ProverOpts,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    println!("opts: {:?}", opts);
-
     let args = EvalArgs::parse();
 
-    // Set environment variables to configure the prover.
+    // Override the shard size for these proofs only, rather than through the process environment.
     if let Some(shard_size) = args.shard_size {
-        std::env::set_var("SHARD_SIZE", format!("{}", 1 << shard_size));
+        opts.core_opts.shard_size = 1 << shard_size;
     }
 
+    println!("opts: {:?}", opts);
+
     // Choose which programs to evaluate.
     let programs: Vec<&TesterProgram> = if args.programs.is_empty() {
         PROGRAMS.iter().collect()
@@ -101,7 +132,14 @@ pub async fn evaluate_performance<C: SP1ProverComponents>(
     for program in &programs {
         println!("Evaluating program: {}", program.name);
         let (elf, stdin) = load_program(program.elf, program.input);
-        let report = run_evaluation::<C>(program.name, &elf, &stdin, opts);
+        let report = run_evaluation::<C>(
+            program.name,
+            &elf,
+            &stdin,
+            opts,
+            args.with_verify.unwrap_or(false),
+            args.iterations,
+        );
         reports.push(report);
         println!("Finished Program: {}", program.name);
     }
@@ -112,7 +150,10 @@ pub async fn evaluate_performance<C: SP1ProverComponents>(
     let results_text = format_results(&args, &reports);
 
     // Print results
-    println!("{}", results_text.join("\n"));
+    match args.output {
+        OutputFormat::Markdown => println!("{}", results_text.join("\n")),
+        OutputFormat::Json => write_json_results(args.json_path.as_ref(), &reports)?,
+    }
 
     // Post to Slack if applicable
     if args.post_to_slack.unwrap_or(false) {
@@ -149,13 +190,23 @@ pub async fn evaluate_performance<C: SP1ProverComponents>(
     Ok(())
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PerformanceReport {
     program: String,
     cycles: u64,
+    iterations: usize,
     exec_khz: f64,
     core_khz: f64,
+    core_khz_p90: f64,
     compressed_khz: f64,
+    compressed_khz_p90: f64,
+    setup_time: f64,
+    core_time: f64,
+    compress_time: f64,
+    #[serde(default)]
+    verify_khz: Option<f64>,
+    #[serde(default)]
+    verify_time: Option<f64>,
     time: f64,
     success: bool,
 }
@@ -165,31 +216,96 @@ fn run_evaluation<C: SP1ProverComponents>(
     elf: &[u8],
     stdin: &SP1Stdin,
     opts: SP1ProverOpts,
+    with_verify: bool,
+    iterations: usize,
 ) -> PerformanceReport {
     let cycles = get_cycles(elf, stdin);
 
     let prover = SP1Prover::<C>::new();
-    let (pk, vk) = prover.setup(elf);
+    let ((pk, vk), setup_duration) = time_operation(|| prover.setup(elf));
 
     let context = SP1Context::default();
 
-    let (_, exec_duration) = time_operation(|| prover.execute(elf, stdin, context.clone()));
+    // Only the proving phases are repeated, the setup is shared across iterations.
+    let timings = (0..iterations.max(1))
+        .map(|_| {
+            let (_, exec_duration) = time_operation(|| prover.execute(elf, stdin, context.clone()));
+
+            let (core_proof, core_duration) =
+                time_operation(|| prover.prove_core(&pk, stdin, opts, context.clone()).unwrap());
+
+            let (compressed_proof, compress_duration) =
+                time_operation(|| prover.compress(&vk, core_proof, vec![], opts).unwrap());
 
-    let (core_proof, core_duration) =
-        time_operation(|| prover.prove_core(&pk, stdin, opts, context).unwrap());
+            let verify_duration = with_verify.then(|| {
+                let (_, verify_duration) =
+                    time_operation(|| prover.verify_compressed(&compressed_proof, &vk).unwrap());
+                verify_duration
+            });
+
+            IterationTimings { exec_duration, core_duration, compress_duration, verify_duration }
+        })
+        .collect::<Vec<_>>();
+
+    summarize_iterations(program_name, cycles, setup_duration, &timings)
+}
 
-    let (_, compress_duration) =
-        time_operation(|| prover.compress(&vk, core_proof, vec![], opts).unwrap());
+/// The timings of a single proving iteration of a program.
+struct IterationTimings {
+    exec_duration: Duration,
+    core_duration: Duration,
+    compress_duration: Duration,
+    verify_duration: Option<Duration>,
+}
 
-    let total_duration = exec_duration + core_duration + compress_duration;
+/// Aggregate the timings of all iterations into a report of the median (and p90) values.
+fn summarize_iterations(
+    program_name: &str,
+    cycles: u64,
+    setup_duration: Duration,
+    timings: &[IterationTimings],
+) -> PerformanceReport {
+    let exec_khz =
+        timings.iter().map(|t| calculate_khz(cycles, t.exec_duration)).collect::<Vec<_>>();
+    let core_khz =
+        timings.iter().map(|t| calculate_khz(cycles, t.core_duration)).collect::<Vec<_>>();
+    let compressed_khz = timings
+        .iter()
+        .map(|t| calculate_khz(cycles, t.compress_duration + t.core_duration))
+        .collect::<Vec<_>>();
+    let core_time = timings.iter().map(|t| t.core_duration.as_secs_f64()).collect::<Vec<_>>();
+    let compress_time =
+        timings.iter().map(|t| t.compress_duration.as_secs_f64()).collect::<Vec<_>>();
+    let compressed_time = timings
+        .iter()
+        .map(|t| (t.compress_duration + t.core_duration).as_secs_f64())
+        .collect::<Vec<_>>();
+    // The p90 is taken over the durations, so it reports the speed of the slowest iterations.
+    let p90_khz =
+        |times: &[f64]| calculate_khz(cycles, Duration::from_secs_f64(percentile(times, 90.0)));
+    let verify_durations = timings.iter().filter_map(|t| t.verify_duration).collect::<Vec<_>>();
+    let verify_khz = verify_durations.iter().map(|d| calculate_khz(cycles, *d)).collect::<Vec<_>>();
+    let verify_time = verify_durations.iter().map(|d| d.as_secs_f64()).collect::<Vec<_>>();
+    let time = timings
+        .iter()
+        .map(|t| (t.exec_duration + t.core_duration + t.compress_duration).as_secs_f64())
+        .collect::<Vec<_>>();
 
     PerformanceReport {
         program: program_name.to_string(),
         cycles,
-        exec_khz: calculate_khz(cycles, exec_duration),
-        core_khz: calculate_khz(cycles, core_duration),
-        compressed_khz: calculate_khz(cycles, compress_duration + core_duration),
-        time: total_duration.as_secs_f64(),
+        iterations: timings.len(),
+        exec_khz: median(&exec_khz),
+        core_khz: median(&core_khz),
+        core_khz_p90: p90_khz(&core_time),
+        compressed_khz: median(&compressed_khz),
+        compressed_khz_p90: p90_khz(&compressed_time),
+        setup_time: setup_duration.as_secs_f64(),
+        core_time: median(&core_time),
+        compress_time: median(&compress_time),
+        verify_khz: (!verify_khz.is_empty()).then(|| median(&verify_khz)),
+        verify_time: (!verify_time.is_empty()).then(|| median(&verify_time)),
+        time: median(&time),
         success: true,
     }
 }
@@ -205,20 +321,47 @@ fn format_results(args: &EvalArgs, results: &[PerformanceReport]) -> Vec<String>
     if let Some(author) = &args.author {
         detail_text.push_str(&format!("*Author*: {}\n", author));
     }
+    if args.iterations > 1 {
+        detail_text.push_str(&format!("*Iterations*: {} (median)\n", args.iterations));
+    }
+
+    // Only show the verification columns if verification was measured.
+    let with_verify = results.iter().any(|result| result.verify_time.is_some());
 
     let mut table_text = String::new();
     table_text.push_str("```\n");
-    table_text.push_str("| program           | cycles      | execute (mHz)  | core (kHZ)     | compress (KHz) | time   | success  |\n");
-    table_text.push_str("|-------------------|-------------|----------------|----------------|----------------|--------|----------|");
+    table_text.push_str("| program           | cycles      | execute (mHz)  | core (kHZ)     | compress (KHz) | setup  | core   | compress |");
+    if with_verify {
+        table_text.push_str(" verify (KHz)   | verify |");
+    }
+    table_text.push_str(" time   | success  |\n");
+    table_text.push_str("|-------------------|-------------|----------------|----------------|----------------|--------|--------|----------|");
+    if with_verify {
+        table_text.push_str("----------------|--------|");
+    }
+    table_text.push_str("--------|----------|");
 
     for result in results.iter() {
         table_text.push_str(&format!(
-            "\n| {:<17} | {:>11} | {:>14.2} | {:>14.2} | {:>14.2} | {:>6} | {:<7} |",
+            "\n| {:<17} | {:>11} | {:>14.2} | {:>14.2} | {:>14.2} | {:>6} | {:>6} | {:>8} |",
             result.program,
             result.cycles,
             result.exec_khz / 1000.0,
             result.core_khz,
             result.compressed_khz,
+            format_duration(result.setup_time),
+            format_duration(result.core_time),
+            format_duration(result.compress_time),
+        ));
+        if with_verify {
+            table_text.push_str(&format!(
+                " {:>14.2} | {:>6} |",
+                result.verify_khz.unwrap_or_default(),
+                result.verify_time.map(format_duration).unwrap_or_else(|| "-".to_string()),
+            ));
+        }
+        table_text.push_str(&format!(
+            " {:>6} | {:<7} |",
             format_duration(result.time),
             if result.success { "✅" } else { "❌" }
         ));
@@ -228,6 +371,21 @@ fn format_results(args: &EvalArgs, results: &[PerformanceReport]) -> Vec<String>
     vec!["*SP1 Performance Test Results*\n".to_string(), detail_text, table_text]
 }
 
+fn write_json_results(
+    json_path: Option<&PathBuf>,
+    reports: &[PerformanceReport],
+) -> Result<(), Box<dyn std::error::Error>> {
+    match json_path {
+        Some(path) => serde_json::to_writer_pretty(File::create(path)?, reports)?,
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, reports)?;
+            writeln!(stdout)?;
+        }
+    }
+    Ok(())
+}
+
 pub fn time_operation<T, F: FnOnce() -> T>(operation: F) -> (T, Duration) {
     let start = Instant::now();
     let result = operation();
@@ -235,6 +393,32 @@ pub fn time_operation<T, F: FnOnce() -> T>(operation: F) -> (T, Duration) {
     (result, duration)
 }
 
+/// The median of the samples, or zero if there are none.
+fn median(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// The nearest-rank percentile of the samples, or zero if there are none.
+fn percentile(samples: &[f64], percentile: f64) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let rank = ((percentile / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
 fn calculate_khz(cycles: u64, duration: Duration) -> f64 {
     let duration_secs = duration.as_secs_f64();
     if duration_secs > 0.0 {
@@ -370,18 +554,34 @@ mod tests {
             PerformanceReport {
                 program: "fibonacci".to_string(),
                 cycles: 11291,
+                iterations: 1,
                 exec_khz: 29290.0,
                 core_khz: 30.0,
+                core_khz_p90: 30.0,
                 compressed_khz: 0.1,
+                compressed_khz_p90: 0.1,
+                setup_time: 12.5,
+                core_time: 90.2,
+                compress_time: 519.1,
+                verify_khz: None,
+                verify_time: None,
                 time: 622.385,
                 success: true,
             },
             PerformanceReport {
                 program: "super-program".to_string(),
                 cycles: 275735600,
+                iterations: 1,
                 exec_khz: 70190.0,
                 core_khz: 310.0,
+                core_khz_p90: 310.0,
                 compressed_khz: 120.0,
+                compressed_khz_p90: 120.0,
+                setup_time: 3.2,
+                core_time: 540.0,
+                compress_time: 240.4,
+                verify_khz: None,
+                verify_time: None,
                 time: 812.285,
                 success: true,
             },
@@ -401,6 +601,10 @@ mod tests {
             branch_name: Some("feature-branch".to_string()),
             commit_hash: Some("abcdef1234567890".to_string()),
             author: Some("John Doe".to_string()),
+            with_verify: None,
+            iterations: 1,
+            output: OutputFormat::Markdown,
+            json_path: None,
         };
 
         let formatted_results = format_results(&args, &dummy_reports);
@@ -416,5 +620,104 @@ mod tests {
         assert!(formatted_results[1].contains("*Author*: John Doe"));
         assert!(formatted_results[2].contains("fibonacci"));
         assert!(formatted_results[2].contains("super-program"));
+        assert!(formatted_results[2].contains("| setup  | core   | compress |"));
+        let fibonacci_row =
+            formatted_results[2].lines().find(|line| line.contains("fibonacci")).unwrap();
+        assert!(fibonacci_row.contains("|    13s |  1m30s |    8m39s | 10m22s |"));
+
+        assert!(!formatted_results[2].contains("verify"));
+
+        let github_message = format_github_message(&formatted_results);
+        assert!(github_message.contains("| setup  | core   | compress |"));
+        assert!(!github_message.contains("```"));
+
+        let mut verified_reports = dummy_reports;
+        verified_reports[0].verify_khz = Some(5000.0);
+        verified_reports[0].verify_time = Some(0.002);
+        let formatted_results = format_results(&args, &verified_reports);
+        assert!(formatted_results[2].contains("| verify (KHz)   | verify |"));
+        let fibonacci_row =
+            formatted_results[2].lines().find(|line| line.contains("fibonacci")).unwrap();
+        assert!(fibonacci_row.contains("|        5000.00 |    2ms |"));
+    }
+
+    #[test]
+    fn test_json_output() {
+        let json_path = std::env::temp_dir().join("sp1-eval-test-results.json");
+        let args = EvalArgs::try_parse_from([
+            "sp1-eval",
+            "--output",
+            "json",
+            "--json-path",
+            json_path.to_str().unwrap(),
+        ])
+        .unwrap();
+        assert_eq!(args.output, OutputFormat::Json);
+
+        let reports = vec![PerformanceReport {
+            program: "fibonacci".to_string(),
+            cycles: 11291,
+            iterations: 1,
+            exec_khz: 29290.0,
+            core_khz: 30.0,
+            core_khz_p90: 30.0,
+            compressed_khz: 0.1,
+            compressed_khz_p90: 0.1,
+            setup_time: 12.5,
+            core_time: 90.2,
+            compress_time: 519.1,
+            verify_khz: None,
+            verify_time: None,
+            time: 622.385,
+            success: true,
+        }];
+        write_json_results(args.json_path.as_ref(), &reports).unwrap();
+
+        let parsed: Vec<PerformanceReport> =
+            serde_json::from_reader(File::open(&json_path).unwrap()).unwrap();
+        std::fs::remove_file(&json_path).unwrap();
+
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].program, "fibonacci");
+        assert_eq!(parsed[0].cycles, 11291);
+        assert_eq!(parsed[0].compress_time, 519.1);
+        assert!(parsed[0].success);
+    }
+
+    #[test]
+    fn test_summarize_iterations() {
+        let timings = [(4, 20, 60), (2, 10, 30), (3, 40, 50)]
+            .into_iter()
+            .map(|(exec, core, compress)| IterationTimings {
+                exec_duration: Duration::from_millis(exec),
+                core_duration: Duration::from_secs(core),
+                compress_duration: Duration::from_secs(compress),
+                verify_duration: None,
+            })
+            .collect::<Vec<_>>();
+
+        let report = summarize_iterations("fibonacci", 1_000_000, Duration::from_secs(5), &timings);
+        assert_eq!(report.iterations, 3);
+        assert_eq!(report.setup_time, 5.0);
+        assert_eq!(report.core_time, 20.0);
+        assert_eq!(report.compress_time, 50.0);
+        assert_eq!(report.exec_khz, calculate_khz(1_000_000, Duration::from_millis(3)));
+        assert_eq!(report.core_khz, calculate_khz(1_000_000, Duration::from_secs(20)));
+        assert_eq!(report.core_khz_p90, calculate_khz(1_000_000, Duration::from_secs(40)));
+        assert_eq!(report.compressed_khz, calculate_khz(1_000_000, Duration::from_secs(80)));
+        assert_eq!(report.compressed_khz_p90, calculate_khz(1_000_000, Duration::from_secs(90)));
+        assert!(report.core_khz_p90 < report.core_khz);
+        assert!(report.verify_khz.is_none());
+        assert!(report.verify_time.is_none());
+
+        // Summarizing the same samples in a different order yields the same report.
+        let reordered = summarize_iterations(
+            "fibonacci",
+            1_000_000,
+            Duration::from_secs(5),
+            &timings.into_iter().rev().collect::<Vec<_>>(),
+        );
+        assert_eq!(reordered.core_khz, report.core_khz);
+        assert_eq!(reordered.compressed_khz, report.compressed_khz);
     }
 }
@@ -93,8 +93,8 @@ fn main() {
             let vk_u32 = vk.hash_u32();
             stdin.write::<[u32; 8]>(&vk_u32);
             stdin.write::<Vec<Vec<u8>>>(&vec![pv.clone(), pv.clone()]);
-            stdin.write_proof(compress_proof.clone(), vk.vk.clone());
-            stdin.write_proof(compress_proof.clone(), vk.vk.clone());
+            stdin.write_proof(compress_proof.clone().into_inner(), vk.vk.clone());
+            stdin.write_proof(compress_proof.clone().into_inner(), vk.vk.clone());
 
             let context = SP1Context::default();
             let (core_proof, _) = time_operation(|| {
@@ -1,7 +1,33 @@
 use crate::types::Buffer;
 use num_bigint::BigUint;
+use p3_baby_bear::BabyBear;
+use p3_field::{AbstractField, PrimeField32};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// The prefix of the input holding the values written with `SP1Stdin::write_tagged`.
+///
+/// It is followed by, for each value, its tag and length as little-endian `u32`s and its bincode
+/// encoding.
+pub const TAGGED_INPUTS_MAGIC: &[u8; 8] = b"SP1TAGS\0";
+
+/// An error that occurs when reading from [`SP1PublicValues`].
+#[derive(Error, Debug)]
+pub enum PublicValuesError {
+    /// Fewer bytes remain than were requested.
+    #[error("requested {requested} bytes but only {remaining} remain")]
+    UnexpectedEnd { requested: usize, remaining: usize },
+    /// The remaining bytes could not be deserialized into the requested type.
+    #[error("failed to deserialize public values: {0}")]
+    Deserialize(#[from] bincode::Error),
+    /// The length prefix of an encoding does not match the number of bytes that follow it.
+    #[error("public values declare {declared} bytes but {actual} follow")]
+    LengthMismatch { declared: usize, actual: usize },
+    /// A committed word is not the canonical value of a BabyBear element.
+    #[error("{0} is not a canonical BabyBear element")]
+    NonCanonicalFieldElement(u32),
+}
 
 /// Public values for the prover.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -32,6 +58,36 @@ impl SP1PublicValues {
         self.buffer.data.clone()
     }
 
+    /// Encode the public values so they can be persisted independently of the proof.
+    ///
+    /// The encoding is the length of the committed bytes as a little-endian `u32`, followed by the
+    /// bytes themselves. Empty public values encode to an empty byte string. The read position is
+    /// not part of the encoding. Use [`Self::from_bytes`] to decode.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        if self.buffer.data.is_empty() {
+            return Vec::new();
+        }
+        let mut bytes = Vec::with_capacity(4 + self.buffer.data.len());
+        bytes.extend_from_slice(&(self.buffer.data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.buffer.data);
+        bytes
+    }
+
+    /// Decode public values written by [`Self::as_bytes`], checking the length prefix.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PublicValuesError> {
+        if bytes.is_empty() {
+            return Ok(Self::new());
+        }
+        let (len, data) = bytes
+            .split_first_chunk::<4>()
+            .ok_or(PublicValuesError::UnexpectedEnd { requested: 4, remaining: bytes.len() })?;
+        let declared = u32::from_le_bytes(*len) as usize;
+        if declared != data.len() {
+            return Err(PublicValuesError::LengthMismatch { declared, actual: data.len() });
+        }
+        Ok(Self::from(data))
+    }
+
     /// Read a value from the buffer.    
     pub fn read<T: Serialize + DeserializeOwned>(&mut self) -> T {
         self.buffer.read()
@@ -42,6 +98,29 @@ impl SP1PublicValues {
         self.buffer.read_slice(slice);
     }
 
+    /// Read a value from the buffer, returning an error if the remaining bytes are truncated or
+    /// malformed.
+    pub fn try_read<T: Serialize + DeserializeOwned>(&mut self) -> Result<T, PublicValuesError> {
+        self.buffer.try_read()
+    }
+
+    /// Fill `buf` from the buffer, returning an error if fewer than `buf.len()` bytes remain.
+    pub fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), PublicValuesError> {
+        self.buffer.try_read_slice(buf)
+    }
+
+    /// Read a digest committed with `sp1_zkvm::io::commit_field_digest`, as field elements.
+    ///
+    /// Returns an error if the remaining bytes are truncated or a word is not less than the
+    /// BabyBear modulus, since such a digest was not committed as field elements.
+    pub fn read_field_digest(&mut self) -> Result<[BabyBear; 8], PublicValuesError> {
+        let words: [u32; 8] = self.try_read()?;
+        if let Some(&word) = words.iter().find(|&&word| word >= BabyBear::ORDER_U32) {
+            return Err(PublicValuesError::NonCanonicalFieldElement(word));
+        }
+        Ok(words.map(BabyBear::from_canonical_u32))
+    }
+
     /// Write a value to the buffer.
     pub fn write<T: Serialize>(&mut self, data: &T) {
         self.buffer.write(data);
@@ -104,4 +183,47 @@ mod tests {
 
         assert_eq!(hash, expected_hash_biguint);
     }
+
+    #[test]
+    fn test_read_past_end_returns_err() {
+        let mut public_values = SP1PublicValues::new();
+        public_values.write(&7u32);
+
+        let mut buf = [0u8; 8];
+        assert!(matches!(
+            public_values.read_exact(&mut buf),
+            Err(PublicValuesError::UnexpectedEnd { requested: 8, remaining: 4 })
+        ));
+        assert!(matches!(public_values.try_read::<u64>(), Err(PublicValuesError::Deserialize(_))));
+
+        // A failed read does not consume anything.
+        assert_eq!(public_values.try_read::<u32>().unwrap(), 7);
+        assert!(public_values.try_read::<u32>().is_err());
+        assert!(public_values.read_exact(&mut [0u8; 1]).is_err());
+        public_values.read_exact(&mut []).unwrap();
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let empty = SP1PublicValues::new();
+        assert!(empty.as_bytes().is_empty());
+        assert!(SP1PublicValues::from_bytes(&[]).unwrap().as_slice().is_empty());
+
+        let mut public_values = SP1PublicValues::new();
+        public_values.write(&7u32);
+        public_values.write_slice(b"sp1");
+        let bytes = public_values.as_bytes();
+        assert_eq!(bytes[..4], 7u32.to_le_bytes());
+        let decoded = SP1PublicValues::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.as_slice(), public_values.as_slice());
+
+        assert!(matches!(
+            SP1PublicValues::from_bytes(&bytes[..2]),
+            Err(PublicValuesError::UnexpectedEnd { requested: 4, remaining: 2 })
+        ));
+        assert!(matches!(
+            SP1PublicValues::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(PublicValuesError::LengthMismatch { declared: 7, actual: 6 })
+        ));
+    }
 }
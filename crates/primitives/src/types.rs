@@ -1,5 +1,7 @@
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+use crate::io::PublicValuesError;
+
 #[derive(Debug, Clone, Copy)]
 pub enum RecursionProgramType {
     Core,
@@ -45,6 +47,27 @@ impl Buffer {
         self.ptr += slice.len();
     }
 
+    /// Read the serializable object from the buffer, returning an error instead of panicking if
+    /// the remaining bytes are truncated or malformed.
+    pub fn try_read<T: Serialize + DeserializeOwned>(&mut self) -> Result<T, PublicValuesError> {
+        let remaining = self.data.get(self.ptr..).unwrap_or_default();
+        let result: T = bincode::deserialize(remaining)?;
+        let nb_bytes = bincode::serialized_size(&result)?;
+        self.ptr += nb_bytes as usize;
+        Ok(result)
+    }
+
+    /// Fill the slice from the buffer, returning an error instead of panicking if fewer than
+    /// `slice.len()` bytes remain.
+    pub fn try_read_slice(&mut self, slice: &mut [u8]) -> Result<(), PublicValuesError> {
+        let remaining = self.data.len().saturating_sub(self.ptr);
+        if slice.len() > remaining {
+            return Err(PublicValuesError::UnexpectedEnd { requested: slice.len(), remaining });
+        }
+        self.read_slice(slice);
+        Ok(())
+    }
+
     /// Write the serializable object from the buffer.                                            
     pub fn write<T: Serialize>(&mut self, data: &T) {
         let mut tmp = Vec::new();
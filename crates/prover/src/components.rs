@@ -5,6 +5,10 @@ use crate::{CompressAir, CoreSC, InnerSC, OuterSC, ShrinkAir, WrapAir};
 
 pub trait SP1ProverComponents: Send + Sync {
     /// The prover for making SP1 core proofs.
+    ///
+    /// Core proving keys are copied to the prover's device representation with
+    /// [`MachineProver::pk_to_device`] before proving, and back with [`MachineProver::pk_to_host`],
+    /// so a backend that manages device memory itself does so in its [`MachineProver`] impl.
     type CoreProver: MachineProver<CoreSC, RiscvAir<<CoreSC as StarkGenericConfig>::Val>>
         + Send
         + Sync;
@@ -25,6 +29,12 @@ pub trait SP1ProverComponents: Send + Sync {
         + Sync;
 }
 
+/// The device representation of a core proving key for the components `C`.
+pub type CoreDeviceProvingKey<C> = <<C as SP1ProverComponents>::CoreProver as MachineProver<
+    CoreSC,
+    RiscvAir<<CoreSC as StarkGenericConfig>::Val>,
+>>::DeviceProvingKey;
+
 pub struct CpuProverComponents;
 
 impl SP1ProverComponents for CpuProverComponents {
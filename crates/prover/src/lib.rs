@@ -13,6 +13,7 @@
 
 pub mod build;
 pub mod components;
+pub mod schema;
 pub mod shapes;
 pub mod types;
 pub mod utils;
@@ -22,27 +23,36 @@ use std::{
     borrow::Borrow,
     collections::BTreeMap,
     env,
+    fs::{self, File},
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{BufWriter, Seek, SeekFrom, Write},
     num::NonZeroUsize,
-    path::Path,
+    path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicUsize, Ordering},
-        mpsc::sync_channel,
-        Arc, Mutex, OnceLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{sync_channel, RecvTimeoutError},
+        Arc, Condvar, Mutex, OnceLock,
     },
     thread,
+    time::Duration,
 };
 
 use lru::LruCache;
 use p3_baby_bear::BabyBear;
+use p3_bn254_fr::Bn254Fr;
 use p3_challenger::CanObserve;
 use p3_field::{AbstractField, PrimeField, PrimeField32};
 use p3_matrix::dense::RowMajorMatrix;
-use sp1_core_executor::{ExecutionError, ExecutionReport, Executor, Program, SP1Context};
+use p3_maybe_rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use schema::PublicValuesSchema;
+use sp1_core_executor::{
+    CheckpointDir, ExecutionError, ExecutionReport, Executor, Program, SP1Context,
+};
 use sp1_core_machine::{
     io::SP1Stdin,
     reduce::SP1ReduceProof,
     riscv::{CoreShapeConfig, RiscvAir},
-    utils::{concurrency::TurnBasedSync, SP1CoreProverError},
+    utils::{concurrency::OrderedChannel, SP1CoreProverError},
 };
 use sp1_primitives::{hash_deferred_proof, io::SP1PublicValues};
 use sp1_recursion_circuit::{
@@ -60,28 +70,37 @@ use sp1_recursion_circuit::{
 };
 use sp1_recursion_compiler::{
     circuit::AsmCompiler,
-    config::InnerConfig,
+    config::{InnerConfig, OuterConfig},
     ir::{Builder, Witness},
 };
 use sp1_recursion_core::{
-    air::RecursionPublicValues, machine::RecursionAir, runtime::ExecutionRecord,
-    shape::RecursionShapeConfig, stark::BabyBearPoseidon2Outer, RecursionProgram,
-    Runtime as RecursionRuntime,
+    air::RecursionPublicValues,
+    machine::RecursionAir,
+    runtime::ExecutionRecord,
+    shape::{RecursionShapeConfig, ShapeTooLarge},
+    stark::BabyBearPoseidon2Outer,
+    RecursionProgram, Runtime as RecursionRuntime,
 };
 pub use sp1_recursion_gnark_ffi::proof::{Groth16Bn254Proof, PlonkBn254Proof};
-use sp1_recursion_gnark_ffi::{groth16_bn254::Groth16Bn254Prover, plonk_bn254::PlonkBn254Prover};
+use sp1_recursion_gnark_ffi::{
+    groth16_bn254::Groth16Bn254Prover,
+    plonk_bn254::{LoadedPlonkArtifacts, PlonkBn254Prover},
+};
 use sp1_stark::{air::InteractionScope, MachineProvingKey, ProofShape};
 use sp1_stark::{
     air::PublicValues, baby_bear_poseidon2::BabyBearPoseidon2, Challenge, Challenger,
-    MachineProver, SP1CoreOpts, SP1ProverOpts, ShardProof, StarkGenericConfig, StarkVerifyingKey,
-    Val, Word, DIGEST_SIZE,
+    CompressSchedule, MachineProver, SP1CoreOpts, SP1ProverOpts, ShardProof, StarkGenericConfig,
+    StarkVerifyingKey, Val, Word, DIGEST_SIZE,
 };
 use tracing::instrument;
 
 pub use types::*;
-use utils::{sp1_committed_values_digest_bn254, sp1_vkey_digest_bn254, words_to_bytes};
+use utils::{
+    compute_vk_root, sp1_committed_values_digest_bn254, sp1_vkey_digest_bn254, words_to_bytes,
+};
 
-use components::{CpuProverComponents, SP1ProverComponents};
+use components::{CoreDeviceProvingKey, CpuProverComponents, SP1ProverComponents};
+use shapes::SP1CompressProgramShape;
 
 pub use sp1_core_machine::SP1_CIRCUIT_VERSION;
 
@@ -102,6 +121,12 @@ const CORE_CACHE_SIZE: usize = 5;
 const COMPRESS_CACHE_SIZE: usize = 3;
 pub const REDUCE_BATCH_SIZE: usize = 2;
 
+/// The default number of cycles above which a high cycle count warning is logged.
+const DEFAULT_WARN_CYCLES: u64 = 100_000_000;
+
+/// How often the compress pipeline checks for cancellation while waiting for proofs.
+const COMPRESS_CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 // TODO: FIX
 //
 // const SHAPES_URL_PREFIX: &str = "https://sp1-circuits.s3.us-east-2.amazonaws.com/shapes";
@@ -164,6 +189,21 @@ pub struct SP1Prover<C: SP1ProverComponents = CpuProverComponents> {
     pub wrap_vk: OnceLock<StarkVerifyingKey<OuterSC>>,
 
     pub vk_verification: bool,
+
+    /// The directory compiled recursion and compress programs are persisted to, if any.
+    pub program_cache_dir: Option<PathBuf>,
+
+    pub program_cache_disk_hits: AtomicUsize,
+
+    /// The core proving keys copied to the device, keyed by their vk digest, if enabled. Each
+    /// entry is filled once by the first prover that needs it.
+    pub device_pk_cache: Option<
+        Mutex<LruCache<[BabyBear; DIGEST_SIZE], Arc<OnceLock<Arc<CoreDeviceProvingKey<C>>>>>>,
+    >,
+
+    /// The number of core proving keys copied to the device.
+    #[cfg(test)]
+    pk_to_device_conversions: AtomicUsize,
 }
 
 impl<C: SP1ProverComponents> SP1Prover<C> {
@@ -175,18 +215,49 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
 
     /// Creates a new [SP1Prover] with lazily initialized components.
     pub fn uninitialized() -> Self {
+        Self::from_configs(
+            CoreSC::default(),
+            InnerSC::default(),
+            InnerSC::compressed(),
+            OuterSC::default(),
+        )
+    }
+
+    /// Creates a new [SP1Prover] whose core, compress, shrink and wrap configs all use
+    /// `num_queries` FRI queries instead of the defaults.
+    ///
+    /// **WARNING: this is for development and testing only.** The number of FRI queries directly
+    /// determines the soundness of the proofs; a small value such as `1` makes them trivially
+    /// forgeable. The resulting proofs only verify against a prover built with the same override,
+    /// and the wrap proofs do not match the prebuilt Groth16/PLONK circuit artifacts.
+    pub fn with_fri_queries(num_queries: usize) -> Self {
+        tracing::warn!("overriding the FRI queries to {}: proofs are NOT secure", num_queries);
+        Self::from_configs(
+            CoreSC::default().with_fri_queries(num_queries),
+            InnerSC::default().with_fri_queries(num_queries),
+            InnerSC::compressed().with_fri_queries(num_queries),
+            OuterSC::default().with_fri_queries(num_queries),
+        )
+    }
+
+    fn from_configs(
+        core_config: CoreSC,
+        compress_config: InnerSC,
+        shrink_config: InnerSC,
+        wrap_config: OuterSC,
+    ) -> Self {
         // Initialize the provers.
-        let core_machine = RiscvAir::machine(CoreSC::default());
+        let core_machine = RiscvAir::machine(core_config);
         let core_prover = C::CoreProver::new(core_machine);
 
-        let compress_machine = CompressAir::compress_machine(InnerSC::default());
+        let compress_machine = CompressAir::compress_machine(compress_config);
         let compress_prover = C::CompressProver::new(compress_machine);
 
         // TODO: Put the correct shrink and wrap machines here.
-        let shrink_machine = ShrinkAir::shrink_machine(InnerSC::compressed());
+        let shrink_machine = ShrinkAir::shrink_machine(shrink_config);
         let shrink_prover = C::ShrinkProver::new(shrink_machine);
 
-        let wrap_machine = WrapAir::wrap_machine(OuterSC::default());
+        let wrap_machine = WrapAir::wrap_machine(wrap_config);
         let wrap_prover = C::WrapProver::new(wrap_machine);
 
         let core_cache_size = NonZeroUsize::new(
@@ -227,7 +298,7 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             bincode::deserialize(include_bytes!("../dummy_vk_map.bin")).unwrap()
         };
 
-        let (root, merkle_tree) = MerkleTree::commit(allowed_vk_map.keys().copied().collect());
+        let (root, merkle_tree) = compute_vk_root(&allowed_vk_map);
 
         Self {
             core_prover,
@@ -246,7 +317,79 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             vk_verification,
             wrap_program: OnceLock::new(),
             wrap_vk: OnceLock::new(),
+            program_cache_dir: None,
+            program_cache_disk_hits: AtomicUsize::new(0),
+            device_pk_cache: None,
+            #[cfg(test)]
+            pk_to_device_conversions: AtomicUsize::new(0),
+        }
+    }
+
+    /// Persists compiled recursion and compress programs to `dir` and, on an in-memory cache miss,
+    /// loads them from there instead of recompiling.
+    ///
+    /// The file names include [SP1_CIRCUIT_VERSION], so programs written by another version of the
+    /// prover are never loaded.
+    pub fn with_program_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.program_cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Keeps up to `capacity` core proving keys on the device between calls to
+    /// [`Self::prove_core`], instead of copying the proving key to the device on every call.
+    ///
+    /// Keys are identified by the digest of their verifying key, so proving many inputs of the
+    /// same program only copies its proving key once.
+    pub fn with_device_pk_cache(mut self, capacity: NonZeroUsize) -> Self {
+        self.device_pk_cache = Some(Mutex::new(LruCache::new(capacity)));
+        self
+    }
+
+    /// Returns the device copy of a core proving key, reusing a cached one if enabled.
+    ///
+    /// The cache lock is only held to look up the entry of the key. The copy itself happens
+    /// outside of it, so provers of different programs don't wait on each other, while provers of
+    /// the same program wait for the first copy instead of making their own.
+    fn core_pk_to_device(&self, pk: &SP1ProvingKey) -> Arc<CoreDeviceProvingKey<C>> {
+        let to_device = || {
+            #[cfg(test)]
+            self.pk_to_device_conversions.fetch_add(1, Ordering::Relaxed);
+            Arc::new(self.core_prover.pk_to_device(&pk.pk))
+        };
+        let Some(cache) = &self.device_pk_cache else {
+            return to_device();
+        };
+        let entry = {
+            let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+            Arc::clone(cache.get_or_insert(pk.vk.hash_babybear(), Default::default))
+        };
+        Arc::clone(entry.get_or_init(to_device))
+    }
+
+    /// Replaces the allowed vk map with the bincode-encoded map stored at `path`, recomputing the
+    /// vk root and Merkle tree from it.
+    ///
+    /// The map replaces the baked-in one selected by `VERIFY_VK`, which still controls whether the
+    /// vks of proofs are checked against it. Its values are the Merkle leaf indices of the vks, so
+    /// they must be `0..n` in key order, or an [`std::io::ErrorKind::InvalidData`] error is
+    /// returned.
+    pub fn with_allowed_vk_map_path(mut self, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let allowed_vk_map: BTreeMap<[BabyBear; DIGEST_SIZE], usize> = bincode::deserialize(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        if let Some((position, index)) =
+            allowed_vk_map.values().enumerate().find(|(position, index)| position != *index)
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("the vk at position {position} of the map has leaf index {index}"),
+            ));
         }
+        let (root, merkle_tree) = compute_vk_root(&allowed_vk_map);
+        self.vk_root = root;
+        self.vk_merkle_tree = merkle_tree;
+        self.allowed_vk_map = allowed_vk_map;
+        Ok(self)
     }
 
     /// Fully initializes the programs, proving keys, and verifying keys that are normally
@@ -256,7 +399,13 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
     /// Creates a proving key and a verifying key for a given RISC-V ELF.
     #[instrument(name = "setup", level = "debug", skip_all)]
     pub fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
-        let program = self.get_program(elf).unwrap();
+        self.try_setup(elf).unwrap()
+    }
+
+    /// Like [`Self::setup`], but returns an error instead of panicking if the ELF cannot be
+    /// parsed or its preprocessed shape cannot be fixed.
+    pub fn try_setup(&self, elf: &[u8]) -> Result<(SP1ProvingKey, SP1VerifyingKey), ProgramError> {
+        let program = self.get_program(elf)?;
         let (pk, vk) = self.core_prover.setup(&program);
         let vk = SP1VerifyingKey { vk };
         let pk = SP1ProvingKey {
@@ -264,12 +413,12 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             elf: elf.to_vec(),
             vk: vk.clone(),
         };
-        (pk, vk)
+        Ok((pk, vk))
     }
 
     /// Get a program with an allowed preprocessed shape.
-    pub fn get_program(&self, elf: &[u8]) -> eyre::Result<Program> {
-        let mut program = Program::from(elf)?;
+    pub fn get_program(&self, elf: &[u8]) -> Result<Program, ProgramError> {
+        let mut program = Program::from(elf).map_err(ProgramError::Parse)?;
         if let Some(core_shape_config) = &self.core_shape_config {
             core_shape_config.fix_preprocessed_shape(&mut program)?;
         }
@@ -285,6 +434,8 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         mut context: SP1Context<'a>,
     ) -> Result<(SP1PublicValues, ExecutionReport), ExecutionError> {
         context.subproof_verifier.replace(Arc::new(self));
+        let warn_cycles = context.warn_cycles;
+        let checkpoint_dir = context.checkpoint_dir.clone().map(CheckpointDir::new);
         let opts = SP1CoreOpts::default();
         let mut runtime = Executor::with_context_and_elf(opts, context, elf);
 
@@ -292,25 +443,63 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         for (proof, vkey) in stdin.proofs.iter() {
             runtime.write_proof(proof.clone(), vkey.clone());
         }
-        runtime.run_fast()?;
+        match checkpoint_dir {
+            // The checkpoints must split the execution exactly like the core prover does.
+            Some(dir) => {
+                runtime.maximal_shapes = self.core_shape_config.as_ref().map(|config| {
+                    config.maximal_core_shapes().into_iter().map(|s| s.inner).collect()
+                });
+                runtime.run_checkpointed(&dir)?;
+            }
+            None => runtime.run_fast()?,
+        }
+        Self::check_for_high_cycles(runtime.state.global_clk, warn_cycles);
         Ok((SP1PublicValues::from(&runtime.state.public_values_stream), runtime.report))
     }
 
+    /// Execute a program like [`Self::execute`], then check that the committed public values match
+    /// `schema`.
+    pub fn execute_with_schema<'a>(
+        &'a self,
+        elf: &[u8],
+        stdin: &SP1Stdin,
+        context: SP1Context<'a>,
+        schema: &PublicValuesSchema,
+    ) -> Result<(SP1PublicValues, ExecutionReport), ExecuteWithSchemaError> {
+        let (public_values, report) = self.execute(elf, stdin, context)?;
+        schema.validate(public_values.as_slice())?;
+        Ok((public_values, report))
+    }
+
     /// Generate shard proofs which split up and prove the valid execution of a RISC-V program with
     /// the core prover. Uses the provided context.
-    #[instrument(name = "prove_core", level = "info", skip_all)]
     pub fn prove_core<'a>(
+        &'a self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        opts: SP1ProverOpts,
+        context: SP1Context<'a>,
+    ) -> Result<SP1CoreProof, SP1CoreProverError> {
+        self.prove_core_cancellable(pk, stdin, opts, context, &AtomicBool::new(false))
+    }
+
+    /// Like [`Self::prove_core`], but stops early and returns
+    /// [`SP1CoreProverError::Cancelled`] once `cancel` is set.
+    #[instrument(name = "prove_core", level = "info", skip_all)]
+    pub fn prove_core_cancellable<'a>(
         &'a self,
         pk: &SP1ProvingKey,
         stdin: &SP1Stdin,
         opts: SP1ProverOpts,
         mut context: SP1Context<'a>,
+        cancel: &AtomicBool,
     ) -> Result<SP1CoreProof, SP1CoreProverError> {
         context.subproof_verifier.replace(Arc::new(self));
+        let warn_cycles = context.warn_cycles;
         let program = self.get_program(&pk.elf).unwrap();
-        let pk = self.core_prover.pk_to_device(&pk.pk);
+        let pk = self.core_pk_to_device(pk);
         let (proof, public_values_stream, cycles) =
-            sp1_core_machine::utils::prove_with_context::<_, C::CoreProver>(
+            sp1_core_machine::utils::prove_with_context_cancellable::<_, C::CoreProver>(
                 &self.core_prover,
                 &pk,
                 program,
@@ -318,8 +507,9 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                 opts.core_opts,
                 context,
                 self.core_shape_config.as_ref(),
+                cancel,
             )?;
-        Self::check_for_high_cycles(cycles);
+        Self::check_for_high_cycles(cycles, warn_cycles);
         let public_values = SP1PublicValues::from(&public_values_stream);
         Ok(SP1CoreProof {
             proof: SP1CoreProofData(proof.shard_proofs),
@@ -329,77 +519,211 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         })
     }
 
+    /// Like [`Self::prove_core`], but appends each shard proof to the file at `path` as soon as it
+    /// is generated instead of keeping it in memory.
+    ///
+    /// The file is written in the format of [`SP1CoreProof::save`], and is returned opened with
+    /// [`SP1CoreProof::open_lazy`].
+    #[instrument(name = "prove_core_to_file", level = "info", skip_all)]
+    pub fn prove_core_to_file<'a>(
+        &'a self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        opts: SP1ProverOpts,
+        mut context: SP1Context<'a>,
+        path: &Path,
+    ) -> Result<LazySP1CoreProof, SP1CoreProverError> {
+        context.subproof_verifier.replace(Arc::new(self));
+        let warn_cycles = context.warn_cycles;
+        let program = self.get_program(&pk.elf).unwrap();
+        let pk = self.core_pk_to_device(pk);
+        let to_io_error = |e| std::io::Error::new(std::io::ErrorKind::Other, e);
+
+        // The number of shard proofs is not known until they have all been generated, so a
+        // placeholder is written in its place and overwritten at the end.
+        let mut writer = BufWriter::new(File::create(path).map_err(SP1CoreProverError::IoError)?);
+        bincode::serialize_into(&mut writer, &0u64)
+            .map_err(to_io_error)
+            .map_err(SP1CoreProverError::IoError)?;
+        let writer = Mutex::new((writer, Vec::new()));
+        let write_shard = |_: usize, proof: ShardProof<CoreSC>| -> std::io::Result<()> {
+            let (writer, offsets) = &mut *writer.lock().unwrap();
+            offsets.push(writer.stream_position()?);
+            bincode::serialize_into(writer, &proof).map_err(to_io_error)
+        };
+        let (num_shards, public_values_stream, cycles) =
+            sp1_core_machine::utils::prove_with_context_to_sink::<_, C::CoreProver>(
+                &self.core_prover,
+                &pk,
+                program,
+                stdin,
+                opts.core_opts,
+                context,
+                self.core_shape_config.as_ref(),
+                &AtomicBool::new(false),
+                &write_shard,
+            )?;
+        Self::check_for_high_cycles(cycles, warn_cycles);
+        let public_values = SP1PublicValues::from(&public_values_stream);
+
+        // Append the metadata and fill in the number of shard proofs.
+        let (mut writer, offsets) = writer.into_inner().unwrap();
+        let write_metadata = |writer: &mut BufWriter<File>| -> std::io::Result<()> {
+            bincode::serialize_into(&mut *writer, stdin).map_err(to_io_error)?;
+            bincode::serialize_into(&mut *writer, &public_values).map_err(to_io_error)?;
+            bincode::serialize_into(&mut *writer, &cycles).map_err(to_io_error)?;
+            writer.seek(SeekFrom::Start(0))?;
+            bincode::serialize_into(&mut *writer, &(num_shards as u64)).map_err(to_io_error)?;
+            writer.flush()
+        };
+        write_metadata(&mut writer).map_err(SP1CoreProverError::IoError)?;
+        Ok(LazySP1CoreProof::new(path.to_path_buf(), offsets, stdin.clone(), public_values, cycles))
+    }
+
+    /// The recursion program verifying `input`'s shape, or [`ShapeTooLarge`] if it does not fit
+    /// any allowed recursion shape.
     pub fn recursion_program(
         &self,
         input: &SP1RecursionWitnessValues<CoreSC>,
-    ) -> Arc<RecursionProgram<BabyBear>> {
+    ) -> Result<Arc<RecursionProgram<BabyBear>>, ShapeTooLarge> {
         let mut cache = self.recursion_programs.lock().unwrap_or_else(|e| e.into_inner());
         cache
-            .get_or_insert(input.shape(), || {
+            .try_get_or_insert(input.shape(), || {
                 let misses = self.recursion_cache_misses.fetch_add(1, Ordering::Relaxed);
                 tracing::debug!("core cache miss, misses: {}", misses);
-                // Get the operations.
-                let builder_span = tracing::debug_span!("build recursion program").entered();
-                let mut builder = Builder::<InnerConfig>::default();
-
-                let input = input.read(&mut builder);
-                SP1RecursiveVerifier::verify(&mut builder, self.core_prover.machine(), input);
-                let operations = builder.into_operations();
-                builder_span.exit();
+                let shape = SP1CompressProgramShape::Recursion(input.shape());
+                let program = self.load_or_compile_program(&shape, || {
+                    // Get the operations.
+                    let builder_span = tracing::debug_span!("build recursion program").entered();
+                    let mut builder = Builder::<InnerConfig>::default();
+
+                    let input = input.read(&mut builder);
+                    SP1RecursiveVerifier::verify(&mut builder, self.core_prover.machine(), input);
+                    let operations = builder.into_operations();
+                    builder_span.exit();
+
+                    // Compile the program.
+                    let compiler_span = tracing::debug_span!("compile recursion program").entered();
+                    let mut compiler = AsmCompiler::<InnerConfig>::default();
+                    let mut program = compiler.compile(operations);
+                    if let Some(recursion_shape_config) = &self.recursion_shape_config {
+                        recursion_shape_config.try_fix_shape(&mut program)?;
+                    }
+                    compiler_span.exit();
+                    Ok(program)
+                })?;
+                Ok(Arc::new(program))
+            })
+            .cloned()
+    }
 
-                // Compile the program.
-                let compiler_span = tracing::debug_span!("compile recursion program").entered();
-                let mut compiler = AsmCompiler::<InnerConfig>::default();
-                let mut program = compiler.compile(operations);
-                if let Some(recursion_shape_config) = &self.recursion_shape_config {
-                    recursion_shape_config.fix_shape(&mut program);
+    /// Loads the program for `shape` from the program cache directory, or compiles it with
+    /// `compile` and writes it there.
+    ///
+    /// Failing to read or write the cache only logs a warning.
+    fn load_or_compile_program(
+        &self,
+        shape: &SP1CompressProgramShape,
+        compile: impl FnOnce() -> Result<RecursionProgram<BabyBear>, ShapeTooLarge>,
+    ) -> Result<RecursionProgram<BabyBear>, ShapeTooLarge> {
+        let Some(dir) = &self.program_cache_dir else {
+            return compile();
+        };
+        let path = dir.join(format!(
+            "program-{}-{:016x}.bin",
+            SP1_CIRCUIT_VERSION,
+            self.program_cache_key(shape)
+        ));
+
+        if let Ok(bytes) = fs::read(&path) {
+            match bincode::deserialize(&bytes) {
+                Ok(program) => {
+                    self.program_cache_disk_hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(program);
                 }
-                let program = Arc::new(program);
-                compiler_span.exit();
-                program
-            })
-            .clone()
+                Err(e) => tracing::warn!("ignoring cached program {}: {}", path.display(), e),
+            }
+        }
+
+        let program = compile()?;
+        // Write to a uniquely named temporary file first so that a concurrent reader never sees a
+        // partial file, and concurrent writers never write to the same file.
+        let result = fs::create_dir_all(dir).and_then(|_| {
+            let mut file = tempfile::NamedTempFile::new_in(dir)?;
+            file.write_all(&bincode::serialize(&program).unwrap())?;
+            file.persist(&path).map_err(|e| e.error)?;
+            Ok(())
+        });
+        if let Err(e) = result {
+            tracing::warn!("failed to cache program {}: {}", path.display(), e);
+        }
+        Ok(program)
     }
 
+    /// The key of `shape`'s program in the program cache directory.
+    ///
+    /// Besides the shape, the compiled program depends on whether it checks vks against the
+    /// allowed vk map and on the FRI parameters of the proofs it verifies, which
+    /// [`Self::with_fri_queries`] changes.
+    fn program_cache_key(&self, shape: &SP1CompressProgramShape) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        shape.hash(&mut hasher);
+        self.vk_verification.hash(&mut hasher);
+        for fri_config in [
+            self.core_prover.machine().config().pcs().fri_config(),
+            self.compress_prover.machine().config().pcs().fri_config(),
+        ] {
+            fri_config.log_blowup.hash(&mut hasher);
+            fri_config.num_queries.hash(&mut hasher);
+            fri_config.proof_of_work_bits.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// The compress program verifying `input`'s shape, or [`ShapeTooLarge`] if it does not fit any
+    /// allowed recursion shape.
     pub fn compress_program(
         &self,
         input: &SP1CompressWithVKeyWitnessValues<InnerSC>,
-    ) -> Arc<RecursionProgram<BabyBear>> {
+    ) -> Result<Arc<RecursionProgram<BabyBear>>, ShapeTooLarge> {
         let mut cache = self.compress_programs.lock().unwrap_or_else(|e| e.into_inner());
         let shape = input.shape();
         cache
-            .get_or_insert(shape.clone(), || {
+            .try_get_or_insert(shape.clone(), || {
                 let misses = self.compress_cache_misses.fetch_add(1, Ordering::Relaxed);
                 tracing::debug!("compress cache miss, misses: {}", misses);
-                // Get the operations.
-                let builder_span = tracing::debug_span!("build compress program").entered();
-                let mut builder = Builder::<InnerConfig>::default();
-
-                // read the input.
-                let input = input.read(&mut builder);
-                // Verify the proof.
-                SP1CompressWithVKeyVerifier::verify(
-                    &mut builder,
-                    self.compress_prover.machine(),
-                    input,
-                    self.vk_verification,
-                    PublicValuesOutputDigest::Reduce,
-                );
-                let operations = builder.into_operations();
-                builder_span.exit();
-
-                // Compile the program.
-                let compiler_span = tracing::debug_span!("compile compress program").entered();
-                let mut compiler = AsmCompiler::<InnerConfig>::default();
-                let mut program = compiler.compile(operations);
-                if let Some(recursion_shape_config) = &self.recursion_shape_config {
-                    recursion_shape_config.fix_shape(&mut program);
-                }
-                let program = Arc::new(program);
-                compiler_span.exit();
-                program
+                let shape = SP1CompressProgramShape::Compress(shape.clone());
+                let program = self.load_or_compile_program(&shape, || {
+                    // Get the operations.
+                    let builder_span = tracing::debug_span!("build compress program").entered();
+                    let mut builder = Builder::<InnerConfig>::default();
+
+                    // read the input.
+                    let input = input.read(&mut builder);
+                    // Verify the proof.
+                    SP1CompressWithVKeyVerifier::verify(
+                        &mut builder,
+                        self.compress_prover.machine(),
+                        input,
+                        self.vk_verification,
+                        PublicValuesOutputDigest::Reduce,
+                    );
+                    let operations = builder.into_operations();
+                    builder_span.exit();
+
+                    // Compile the program.
+                    let compiler_span = tracing::debug_span!("compile compress program").entered();
+                    let mut compiler = AsmCompiler::<InnerConfig>::default();
+                    let mut program = compiler.compile(operations);
+                    if let Some(recursion_shape_config) = &self.recursion_shape_config {
+                        recursion_shape_config.try_fix_shape(&mut program)?;
+                    }
+                    compiler_span.exit();
+                    Ok(program)
+                })?;
+                Ok(Arc::new(program))
             })
-            .clone()
+            .cloned()
     }
 
     pub fn shrink_program(
@@ -476,10 +800,12 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             .clone()
     }
 
+    /// The deferred program verifying `input`'s shape, or [`ShapeTooLarge`] if it does not fit any
+    /// allowed recursion shape.
     pub fn deferred_program(
         &self,
         input: &SP1DeferredWitnessValues<InnerSC>,
-    ) -> Arc<RecursionProgram<BabyBear>> {
+    ) -> Result<Arc<RecursionProgram<BabyBear>>, ShapeTooLarge> {
         // Compile the program.
 
         // Get the operations.
@@ -506,11 +832,11 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         let mut compiler = AsmCompiler::<InnerConfig>::default();
         let mut program = compiler.compile(operations);
         if let Some(recursion_shape_config) = &self.recursion_shape_config {
-            recursion_shape_config.fix_shape(&mut program);
+            recursion_shape_config.try_fix_shape(&mut program)?;
         }
         let program = Arc::new(program);
         compiler_span.exit();
-        program
+        Ok(program)
     }
 
     pub fn get_recursion_core_inputs(
@@ -556,6 +882,11 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         core_inputs
     }
 
+    /// Prepare the inputs verifying `deferred_proofs` in batches of `batch_size`.
+    ///
+    /// The deferred proofs may come from programs with different vks: each proof's compress vk is
+    /// looked up in the allowed vk map on its own, and each proof's `sp1_vk_digest` is folded into
+    /// the deferred proofs digest along with its committed values.
     pub fn get_recursion_deferred_inputs<'a>(
         &'a self,
         vk: &'a StarkVerifyingKey<CoreSC>,
@@ -597,6 +928,32 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         deferred_inputs
     }
 
+    /// Check that the public values of each deferred proof were set, as an all-zero
+    /// `committed_value_digest` or `sp1_vk_digest` would be folded into a meaningless deferred
+    /// proofs digest by [`Self::get_recursion_deferred_inputs`].
+    fn check_deferred_proofs_initialized(
+        deferred_proofs: &[SP1ReduceProof<InnerSC>],
+    ) -> Result<(), SP1RecursionProverError> {
+        for (index, proof) in deferred_proofs.iter().enumerate() {
+            let pv: &RecursionPublicValues<BabyBear> =
+                proof.proof.public_values.as_slice().borrow();
+            let uninitialized =
+                |field| SP1RecursionProverError::UninitializedDeferredProof { index, field };
+            if pv
+                .committed_value_digest
+                .iter()
+                .flat_map(|word| word.0)
+                .all(|byte| byte == BabyBear::zero())
+            {
+                return Err(uninitialized("committed_value_digest"));
+            }
+            if pv.sp1_vk_digest.iter().all(|&elt| elt == BabyBear::zero()) {
+                return Err(uninitialized("sp1_vk_digest"));
+            }
+        }
+        Ok(())
+    }
+
     /// Generate the inputs for the first layer of recursive proofs.
     #[allow(clippy::type_complexity)]
     pub fn get_first_layer_inputs<'a>(
@@ -631,46 +988,310 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
     }
 
     /// Reduce shards proofs to a single shard proof using the recursion prover.
-    #[instrument(name = "compress", level = "info", skip_all)]
     pub fn compress(
         &self,
         vk: &SP1VerifyingKey,
         proof: SP1CoreProof,
         deferred_proofs: Vec<SP1ReduceProof<InnerSC>>,
         opts: SP1ProverOpts,
-    ) -> Result<SP1ReduceProof<InnerSC>, SP1RecursionProverError> {
-        // The batch size for reducing two layers of recursion.
-        let batch_size = REDUCE_BATCH_SIZE;
-        // The batch size for reducing the first layer of recursion.
-        let first_layer_batch_size = 1;
+    ) -> Result<CompressedProof, SP1RecursionProverError> {
+        self.compress_cancellable(vk, proof, deferred_proofs, opts, &AtomicBool::new(false))
+    }
+
+    /// Reduce shards proofs to a single shard proof like [`Self::compress`], calling `progress`
+    /// each time a proof of the recursion tree is done.
+    ///
+    /// The callback is invoked from a single worker thread, in order, once for every proof of
+    /// every layer, ending with the root.
+    pub fn compress_with_progress(
+        &self,
+        vk: &SP1VerifyingKey,
+        proof: SP1CoreProof,
+        deferred_proofs: Vec<SP1ReduceProof<InnerSC>>,
+        opts: SP1ProverOpts,
+        progress: Option<CompressProgressFn>,
+    ) -> Result<CompressedProof, SP1RecursionProverError> {
+        self.compress_impl(
+            vk,
+            proof,
+            deferred_proofs,
+            opts,
+            &AtomicBool::new(false),
+            progress.as_deref(),
+            None,
+        )
+    }
+
+    /// Reduce shards proofs to a single shard proof like [`Self::compress`], calling
+    /// `queue_depths` each time the number of items queued on a channel of the recursion pipeline
+    /// changes.
+    ///
+    /// The callback is invoked from the worker threads concurrently, so consecutive reports may be
+    /// observed out of order. It is meant to size the worker pools and channel capacities of
+    /// [`SP1ProverOpts::recursion_opts`]: a channel that stays full points at the pool draining it.
+    pub fn compress_with_queue_depths(
+        &self,
+        vk: &SP1VerifyingKey,
+        proof: SP1CoreProof,
+        deferred_proofs: Vec<SP1ReduceProof<InnerSC>>,
+        opts: SP1ProverOpts,
+        queue_depths: Option<CompressQueueDepthsFn>,
+    ) -> Result<CompressedProof, SP1RecursionProverError> {
+        self.compress_impl(
+            vk,
+            proof,
+            deferred_proofs,
+            opts,
+            &AtomicBool::new(false),
+            None,
+            queue_depths.as_deref(),
+        )
+    }
+
+    /// Reduce shards proofs to a single shard proof like [`Self::compress`], returning
+    /// [`SP1RecursionProverError::Cancelled`] once `cancel` is set.
+    ///
+    /// Proofs already in flight are finished, but no new recursion proofs are started after
+    /// cancellation, and all worker threads have exited by the time this returns.
+    pub fn compress_cancellable(
+        &self,
+        vk: &SP1VerifyingKey,
+        proof: SP1CoreProof,
+        deferred_proofs: Vec<SP1ReduceProof<InnerSC>>,
+        opts: SP1ProverOpts,
+        cancel: &AtomicBool,
+    ) -> Result<CompressedProof, SP1RecursionProverError> {
+        self.compress_impl(vk, proof, deferred_proofs, opts, cancel, None, None)
+    }
 
+    #[instrument(name = "compress", level = "info", skip_all)]
+    fn compress_impl(
+        &self,
+        vk: &SP1VerifyingKey,
+        proof: SP1CoreProof,
+        deferred_proofs: Vec<SP1ReduceProof<InnerSC>>,
+        opts: SP1ProverOpts,
+        cancel: &AtomicBool,
+        progress: Option<&(dyn Fn(CompressProgress) + Send + Sync)>,
+        queue_depths: Option<&(dyn Fn(CompressQueueDepths) + Send + Sync)>,
+    ) -> Result<CompressedProof, SP1RecursionProverError> {
         let shard_proofs = &proof.proof.0;
+        self.compress_shards(
+            vk,
+            shard_proofs.len(),
+            |index| Ok(&shard_proofs[index]),
+            deferred_proofs,
+            opts,
+            cancel,
+            progress,
+            queue_depths,
+        )
+    }
+
+    /// Like [`Self::compress`], but takes a core proof opened with [`SP1CoreProof::open_lazy`] and
+    /// decodes its shard proofs from disk as the recursion tree needs them, rather than holding all
+    /// of them in memory.
+    #[instrument(name = "compress_lazy", level = "info", skip_all)]
+    pub fn compress_lazy(
+        &self,
+        vk: &SP1VerifyingKey,
+        proof: &LazySP1CoreProof,
+        deferred_proofs: Vec<SP1ReduceProof<InnerSC>>,
+        opts: SP1ProverOpts,
+    ) -> Result<CompressedProof, SP1RecursionProverError> {
+        self.compress_shards(
+            vk,
+            proof.len(),
+            |index| proof.shard(index).map_err(SP1RecursionProverError::ReadShardProof),
+            deferred_proofs,
+            opts,
+            &AtomicBool::new(false),
+            None,
+            None,
+        )
+    }
+
+    /// Reduce the `num_shards` shard proofs returned by `fetch_shard` to a single proof, with one
+    /// shard proof per first layer input.
+    ///
+    /// Each shard proof is fetched twice, once for the leaf challenger and once for its input, so
+    /// that only the inputs queued in the recursion pipeline are held in memory.
+    #[allow(clippy::too_many_arguments)]
+    fn compress_shards<P: Borrow<ShardProof<CoreSC>>>(
+        &self,
+        vk: &SP1VerifyingKey,
+        num_shards: usize,
+        fetch_shard: impl Fn(usize) -> Result<P, SP1RecursionProverError> + Send,
+        deferred_proofs: Vec<SP1ReduceProof<InnerSC>>,
+        opts: SP1ProverOpts,
+        cancel: &AtomicBool,
+        progress: Option<&(dyn Fn(CompressProgress) + Send + Sync)>,
+        queue_depths: Option<&(dyn Fn(CompressQueueDepths) + Send + Sync)>,
+    ) -> Result<CompressedProof, SP1RecursionProverError> {
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SP1RecursionProverError::Cancelled);
+        }
+        if opts.recursion_opts.recursion_trace_workers == 0 {
+            return Err(SP1RecursionProverError::InvalidOpts(
+                "recursion_trace_workers must be at least 1",
+            ));
+        }
+        if opts.recursion_opts.recursion_prove_workers == 0 {
+            return Err(SP1RecursionProverError::InvalidOpts(
+                "recursion_prove_workers must be at least 1",
+            ));
+        }
+        if opts.recursion_opts.compress_schedule == CompressSchedule::Unrecognized {
+            return Err(SP1RecursionProverError::InvalidOpts(
+                "COMPRESS_SCHEDULE must be `pipelined` or `breadth-first`",
+            ));
+        }
+        if num_shards == 0 {
+            return Err(SP1RecursionProverError::RuntimeError("the core proof is empty".into()));
+        }
+        Self::check_deferred_proofs_initialized(&deferred_proofs)?;
 
-        // Get the leaf challenger.
+        // Get the leaf challenger and the public values of the last shard.
         let mut leaf_challenger = self.core_prover.config().challenger();
         vk.vk.observe_into(&mut leaf_challenger);
-        shard_proofs.iter().for_each(|proof| {
-            leaf_challenger.observe(proof.commitment.global_main_commit);
-            leaf_challenger.observe_slice(&proof.public_values[0..self.core_prover.num_pv_elts()]);
+        let mut last_proof_pv = PublicValues::<Word<BabyBear>, BabyBear>::default();
+        for index in 0..num_shards {
+            let shard_proof = fetch_shard(index)?;
+            let shard_proof = shard_proof.borrow();
+            leaf_challenger.observe(shard_proof.commitment.global_main_commit);
+            leaf_challenger
+                .observe_slice(&shard_proof.public_values[0..self.core_prover.num_pv_elts()]);
+            last_proof_pv = *shard_proof.public_values.as_slice().borrow();
+        }
+
+        // Generate the core inputs lazily.
+        let is_complete = num_shards == 1 && deferred_proofs.is_empty();
+        let mut reconstruct_challenger = self.core_prover.config().challenger();
+        vk.vk.observe_into(&mut reconstruct_challenger);
+        let core_leaf_challenger = leaf_challenger.clone();
+        let core_inputs = (0..num_shards).map(move |index| {
+            let shard_proof = fetch_shard(index)?;
+            let shard_proof = shard_proof.borrow();
+            let initial_reconstruct_challenger = reconstruct_challenger.clone();
+            reconstruct_challenger.observe(shard_proof.commitment.global_main_commit);
+            reconstruct_challenger
+                .observe_slice(&shard_proof.public_values[0..self.core_prover.num_pv_elts()]);
+            Ok(SP1CircuitWitness::Core(SP1RecursionWitnessValues {
+                vk: vk.vk.clone(),
+                shard_proofs: vec![shard_proof.clone()],
+                leaf_challenger: core_leaf_challenger.clone(),
+                initial_reconstruct_challenger,
+                is_complete,
+                is_first_shard: index == 0,
+                vk_root: self.vk_root,
+            }))
         });
 
-        // Generate the first layer inputs.
-        let first_layer_inputs = self.get_first_layer_inputs(
-            vk,
+        let deferred_inputs = self.get_recursion_deferred_inputs(
+            &vk.vk,
             &leaf_challenger,
-            shard_proofs,
+            &last_proof_pv,
             &deferred_proofs,
-            first_layer_batch_size,
+            1,
         );
+        let num_first_layer_inputs = num_shards + deferred_inputs.len();
+        let first_layer_inputs = core_inputs
+            .chain(deferred_inputs.into_iter().map(|input| Ok(SP1CircuitWitness::Deferred(input))));
+        self.compress_layers(
+            first_layer_inputs,
+            num_first_layer_inputs,
+            opts,
+            cancel,
+            progress,
+            queue_depths,
+        )
+    }
 
-        // Calculate the expected height of the tree.
-        let mut expected_height = if first_layer_inputs.len() == 1 { 0 } else { 1 };
-        let num_first_layer_inputs = first_layer_inputs.len();
-        let mut num_layer_inputs = num_first_layer_inputs;
-        while num_layer_inputs > batch_size {
-            num_layer_inputs = num_layer_inputs.div_ceil(2);
-            expected_height += 1;
-        }
+    /// Reduce the first layer inputs to a single proof by proving the recursion tree layer by
+    /// layer in a pipeline of worker threads.
+    fn compress_layers(
+        &self,
+        first_layer_inputs: impl Iterator<Item = Result<SP1CircuitWitness, SP1RecursionProverError>>
+            + Send,
+        num_first_layer_inputs: usize,
+        opts: SP1ProverOpts,
+        cancel: &AtomicBool,
+        progress: Option<&(dyn Fn(CompressProgress) + Send + Sync)>,
+        queue_depths: Option<&(dyn Fn(CompressQueueDepths) + Send + Sync)>,
+    ) -> Result<CompressedProof, SP1RecursionProverError> {
+        // The batch size for reducing two layers of recursion.
+        let batch_size = REDUCE_BATCH_SIZE;
+
+        // Calculate the size of each layer and the expected height of the tree.
+        let layer_sizes = compress_layer_sizes(num_first_layer_inputs);
+        let expected_height = layer_sizes.len() - 1;
+        let report_progress = |layer: usize, completed_in_layer: usize| {
+            if let Some(progress) = progress {
+                progress(CompressProgress {
+                    layer,
+                    completed_in_layer,
+                    total_in_layer: layer_sizes[layer],
+                });
+            }
+        };
+
+        // Count the items queued on each channel, from the start of their send to their receipt,
+        // and the records of each layer from their generation to their proving.
+        let inputs_depth = &AtomicUsize::new(0);
+        let records_and_traces_depth = &AtomicUsize::new(0);
+        let proofs_depth = &AtomicUsize::new(0);
+        let records_in_layer =
+            &(0..=expected_height).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+        let track_queue_depth = |depth: &AtomicUsize, delta: isize| {
+            if delta >= 0 {
+                depth.fetch_add(delta.unsigned_abs(), Ordering::SeqCst);
+            } else {
+                depth.fetch_sub(delta.unsigned_abs(), Ordering::SeqCst);
+            }
+            if let Some(queue_depths) = queue_depths {
+                queue_depths(CompressQueueDepths {
+                    inputs: inputs_depth.load(Ordering::SeqCst),
+                    records_and_traces: records_and_traces_depth.load(Ordering::SeqCst),
+                    proofs: proofs_depth.load(Ordering::SeqCst),
+                    layers_in_flight: records_in_layer
+                        .iter()
+                        .filter(|records| records.load(Ordering::SeqCst) > 0)
+                        .count(),
+                });
+            }
+        };
+        let track_queue_depth = &track_queue_depth;
+
+        // The first error of a worker, such as a first layer input that could not be generated or
+        // a program too large for any allowed shape, stops the workers like a cancellation, and
+        // is returned once they have exited.
+        let worker_error = &Mutex::new(None);
+        let failed = &AtomicBool::new(false);
+        let fail = |err: SP1RecursionProverError| {
+            worker_error.lock().unwrap().get_or_insert(err);
+            failed.store(true, Ordering::SeqCst);
+        };
+        let fail = &fail;
+        let stopped = || cancel.load(Ordering::SeqCst) || failed.load(Ordering::SeqCst);
+        let stopped = &stopped;
+
+        // The number of layers that are fully proven. Under the breadth-first schedule, the
+        // records of a layer are only generated once the previous layer is fully proven.
+        let breadth_first = opts.recursion_opts.compress_schedule == CompressSchedule::BreadthFirst;
+        let proven_layers = &(Mutex::new(0), Condvar::new());
+        let wait_for_previous_layer = |height: usize| {
+            let (proven, proven_changed) = proven_layers;
+            let mut proven = proven.lock().unwrap();
+            while *proven < height {
+                if stopped() {
+                    return false;
+                }
+                proven =
+                    proven_changed.wait_timeout(proven, COMPRESS_CANCEL_POLL_INTERVAL).unwrap().0;
+            }
+            true
+        };
+        let wait_for_previous_layer = &wait_for_previous_layer;
 
         // Generate the proofs.
         let span = tracing::Span::current().clone();
@@ -678,139 +1299,109 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             let _span = span.enter();
 
             // Spawn a worker that sends the first layer inputs to a bounded channel.
-            let input_sync = Arc::new(TurnBasedSync::new());
             let (input_tx, input_rx) = sync_channel::<(usize, usize, SP1CircuitWitness, bool)>(
                 opts.recursion_opts.checkpoints_channel_capacity,
             );
-            let input_tx = Arc::new(Mutex::new(input_tx));
+            let input_tx = Arc::new(OrderedChannel::new(
+                input_tx,
+                opts.recursion_opts.checkpoints_channel_capacity.max(1),
+            ));
             {
                 let input_tx = Arc::clone(&input_tx);
-                let input_sync = Arc::clone(&input_sync);
                 s.spawn(move || {
-                    for (index, input) in first_layer_inputs.into_iter().enumerate() {
-                        input_sync.wait_for_turn(index);
-                        input_tx.lock().unwrap().send((index, 0, input, false)).unwrap();
-                        input_sync.advance_turn();
+                    for (index, input) in first_layer_inputs.enumerate() {
+                        let input = match input {
+                            Ok(input) => input,
+                            Err(err) => {
+                                fail(err);
+                                input_tx.close();
+                                break;
+                            }
+                        };
+                        track_queue_depth(inputs_depth, 1);
+                        if input_tx.send(index, (index, 0, input, false)).is_err() {
+                            break;
+                        }
                     }
                 });
             }
 
             // Spawn workers who generate the records and traces.
-            let record_and_trace_sync = Arc::new(TurnBasedSync::new());
             let (record_and_trace_tx, record_and_trace_rx) =
                 sync_channel::<(usize, usize, TracesOrInput)>(
                     opts.recursion_opts.records_and_traces_channel_capacity,
                 );
-            let record_and_trace_tx = Arc::new(Mutex::new(record_and_trace_tx));
+            // Bound the records and traces that trace workers ahead of the slowest one can hold, as
+            // these are the largest items of the pipeline.
+            let record_and_trace_tx = Arc::new(OrderedChannel::new(
+                record_and_trace_tx,
+                opts.recursion_opts.records_and_traces_channel_capacity.max(1),
+            ));
             let record_and_trace_rx = Arc::new(Mutex::new(record_and_trace_rx));
             let input_rx = Arc::new(Mutex::new(input_rx));
-            for _ in 0..opts.recursion_opts.trace_gen_workers {
-                let record_and_trace_sync = Arc::clone(&record_and_trace_sync);
+            for _ in 0..opts.recursion_opts.recursion_trace_workers {
                 let record_and_trace_tx = Arc::clone(&record_and_trace_tx);
                 let input_rx = Arc::clone(&input_rx);
                 let span = tracing::debug_span!("generate records and traces");
                 s.spawn(move || {
                     let _span = span.enter();
                     loop {
-                        let received = { input_rx.lock().unwrap().recv() };
+                        // Check for cancellation while holding the lock, so that every input
+                        // after the first dropped one is dropped as well. Dropping an input closes
+                        // the channel, so that no worker stays blocked behind its index.
+                        let (received, cancelled) = {
+                            let input_rx = input_rx.lock().unwrap();
+                            (input_rx.recv(), stopped())
+                        };
+                        if received.is_ok() {
+                            track_queue_depth(inputs_depth, -1);
+                        }
+                        if cancelled && received.is_ok() {
+                            record_and_trace_tx.close();
+                            continue;
+                        }
+                        if let Ok((_, height, _, _)) = &received {
+                            if breadth_first && !wait_for_previous_layer(*height) {
+                                record_and_trace_tx.close();
+                                continue;
+                            }
+                        }
                         if let Ok((index, height, input, false)) = received {
-                            // Get the program and witness stream.
-                            let (program, witness_stream) = tracing::debug_span!(
-                                "get program and witness stream"
-                            )
-                            .in_scope(|| match input {
-                                SP1CircuitWitness::Core(input) => {
-                                    let mut witness_stream = Vec::new();
-                                    Witnessable::<InnerConfig>::write(&input, &mut witness_stream);
-                                    (self.recursion_program(&input), witness_stream)
+                            let _batch_span =
+                                tracing::debug_span!("batch", index, height).entered();
+
+                            let (program, record, traces) = match self
+                                .generate_compress_record_and_traces(input, &opts.recursion_opts)
+                            {
+                                Ok(program_record_traces) => program_record_traces,
+                                Err(err) => {
+                                    fail(err.into());
+                                    record_and_trace_tx.close();
+                                    continue;
                                 }
-                                SP1CircuitWitness::Deferred(input) => {
-                                    let mut witness_stream = Vec::new();
-                                    Witnessable::<InnerConfig>::write(&input, &mut witness_stream);
-                                    (self.deferred_program(&input), witness_stream)
-                                }
-                                SP1CircuitWitness::Compress(input) => {
-                                    let mut witness_stream = Vec::new();
-
-                                    let input_with_merkle = self.make_merkle_proofs(input);
-
-                                    Witnessable::<InnerConfig>::write(
-                                        &input_with_merkle,
-                                        &mut witness_stream,
-                                    );
-
-                                    (self.compress_program(&input_with_merkle), witness_stream)
-                                }
-                            });
-
-                            // Execute the runtime.
-                            let record = tracing::debug_span!("execute runtime").in_scope(|| {
-                                let mut runtime =
-                                    RecursionRuntime::<Val<InnerSC>, Challenge<InnerSC>, _>::new(
-                                        program.clone(),
-                                        self.compress_prover.config().perm.clone(),
-                                    );
-                                runtime.witness_stream = witness_stream.into();
-                                runtime
-                                    .run()
-                                    .map_err(|e| {
-                                        SP1RecursionProverError::RuntimeError(e.to_string())
-                                    })
-                                    .unwrap();
-                                runtime.record
-                            });
-
-                            // Generate the dependencies.
-                            let mut records = vec![record];
-                            tracing::debug_span!("generate dependencies").in_scope(|| {
-                                self.compress_prover.machine().generate_dependencies(
-                                    &mut records,
-                                    &opts.recursion_opts,
-                                    None,
-                                )
-                            });
-
-                            // Generate the traces.
-                            let record = records.into_iter().next().unwrap();
-                            let traces = tracing::debug_span!("generate traces").in_scope(|| {
-                                self.compress_prover
-                                    .generate_traces(&record, InteractionScope::Local)
-                            });
-
-                            // Wait for our turn to update the state.
-                            record_and_trace_sync.wait_for_turn(index);
-
-                            // Send the record and traces to the worker.
-                            record_and_trace_tx
-                                .lock()
-                                .unwrap()
-                                .send((
+                            };
+                            track_queue_depth(&records_in_layer[height], 1);
+
+                            // Send the record and traces to the worker. This only fails once the
+                            // channel was closed after a stop.
+                            track_queue_depth(records_and_traces_depth, 1);
+                            let _ = record_and_trace_tx.send(
+                                index,
+                                (
                                     index,
                                     height,
                                     TracesOrInput::ProgramRecordTraces(Box::new((
                                         program, record, traces,
                                     ))),
-                                ))
-                                .unwrap();
-
-                            // Advance the turn.
-                            record_and_trace_sync.advance_turn();
+                                ),
+                            );
                         } else if let Ok((index, height, input, true)) = received {
-                            record_and_trace_sync.wait_for_turn(index);
-
-                            // Send the record and traces to the worker.
-                            record_and_trace_tx
-                                .lock()
-                                .unwrap()
-                                .send((
-                                    index,
-                                    height,
-                                    TracesOrInput::CircuitWitness(Box::new(input)),
-                                ))
-                                .unwrap();
-
-                            // Advance the turn.
-                            record_and_trace_sync.advance_turn();
+                            // Send the input to the worker.
+                            track_queue_depth(records_and_traces_depth, 1);
+                            let _ = record_and_trace_tx.send(
+                                index,
+                                (index, height, TracesOrInput::CircuitWitness(Box::new(input))),
+                            );
                         } else {
                             break;
                         }
@@ -819,108 +1410,63 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             }
 
             // Spawn workers who generate the compress proofs.
-            let proofs_sync = Arc::new(TurnBasedSync::new());
             let (proofs_tx, proofs_rx) =
                 sync_channel::<(usize, usize, StarkVerifyingKey<InnerSC>, ShardProof<InnerSC>)>(
                     num_first_layer_inputs * 2,
                 );
-            let proofs_tx = Arc::new(Mutex::new(proofs_tx));
+            let proofs_tx = Arc::new(OrderedChannel::new(proofs_tx, num_first_layer_inputs * 2));
             let proofs_rx = Arc::new(Mutex::new(proofs_rx));
             let mut prover_handles = Vec::new();
-            for _ in 0..opts.recursion_opts.shard_batch_size {
-                let prover_sync = Arc::clone(&proofs_sync);
+            for _ in 0..opts.recursion_opts.recursion_prove_workers {
                 let record_and_trace_rx = Arc::clone(&record_and_trace_rx);
                 let proofs_tx = Arc::clone(&proofs_tx);
                 let span = tracing::debug_span!("prove");
                 let handle = s.spawn(move || {
                     let _span = span.enter();
                     loop {
-                        let received = { record_and_trace_rx.lock().unwrap().recv() };
-                        if let Ok((index, height, TracesOrInput::ProgramRecordTraces(boxed_prt))) = received {
+                        let (received, cancelled) = {
+                            let record_and_trace_rx = record_and_trace_rx.lock().unwrap();
+                            (record_and_trace_rx.recv(), stopped())
+                        };
+                        if received.is_ok() {
+                            track_queue_depth(records_and_traces_depth, -1);
+                        }
+                        if cancelled && received.is_ok() {
+                            proofs_tx.close();
+                            continue;
+                        }
+                        if let Ok((index, height, TracesOrInput::ProgramRecordTraces(boxed_prt))) =
+                            received
+                        {
                             let (program, record, traces) = *boxed_prt;
-                            tracing::debug_span!("batch").in_scope(|| {
-                                // Get the keys.
-                                let (pk, vk) = tracing::debug_span!("Setup compress program")
-                                    .in_scope(|| self.compress_prover.setup(&program));
-
-                                // Observe the proving key.
-                                let mut challenger = self.compress_prover.config().challenger();
-                                tracing::debug_span!("observe proving key").in_scope(|| {
-                                    pk.observe_into(&mut challenger);
-                                });
-
-                                #[cfg(feature = "debug")]
-                                self.compress_prover.debug_constraints(
-                                    &self.compress_prover.pk_to_host(&pk),
-                                    vec![record.clone()],
-                                    &mut challenger.clone(),
-                                );
-
-                                // Commit to the record and traces.
-                                let local_data = tracing::debug_span!("commit")
-                                    .in_scope(|| self.compress_prover.commit(&record, traces));
-
-                                // Observe the commitment.
-                                tracing::debug_span!("observe public values").in_scope(|| {
-                                    challenger.observe_slice(
-                                        &local_data.public_values[0..self.compress_prover.num_pv_elts()],
-                                    );
-                                });
-
-                                // Generate the proof.
-                                let proof = tracing::debug_span!("open").in_scope(|| {
-                                    self.compress_prover
-                                        .open(
-                                            &pk,
-                                            None,
-                                            local_data,
-                                            &mut challenger,
-                                            &[
-                                                <BabyBearPoseidon2 as StarkGenericConfig>::Challenge::zero(),
-                                                <BabyBearPoseidon2 as StarkGenericConfig>::Challenge::zero(),
-                                            ],
-                                        )
-                                        .unwrap()
-                                });
-
-                                // Verify the proof.
-                                #[cfg(feature = "debug")]
-                                self.compress_prover
-                                    .machine()
-                                    .verify(
-                                        &vk,
-                                        &sp1_stark::MachineProof {
-                                            shard_proofs: vec![proof.clone()],
-                                        },
-                                        &mut self.compress_prover.config().challenger(),
-                                    )
-                                    .unwrap();
-
-                                // Wait for our turn to update the state.
-                                prover_sync.wait_for_turn(index);
+                            tracing::debug_span!("batch", index, height).in_scope(|| {
+                                let (vk, proof) =
+                                    self.prove_compress_record(&program, record, traces);
+                                track_queue_depth(&records_in_layer[height], -1);
 
                                 // Send the proof.
-                                proofs_tx.lock().unwrap().send((index, height, vk, proof)).unwrap();
-
-                                // Advance the turn.
-                                prover_sync.advance_turn();
+                                track_queue_depth(proofs_depth, 1);
+                                let _ = proofs_tx.send(index, (index, height, vk, proof));
                             });
-                        } else if let Ok((index, height, TracesOrInput::CircuitWitness(witness_box))) = received {
+                        } else if let Ok((
+                            index,
+                            height,
+                            TracesOrInput::CircuitWitness(witness_box),
+                        )) = received
+                        {
                             let witness = *witness_box;
                             if let SP1CircuitWitness::Compress(inner_witness) = witness {
-                                let SP1CompressWitnessValues { vks_and_proofs, is_complete: _ } = inner_witness;
-                                assert!(vks_and_proofs.len()==1);
+                                let SP1CompressWitnessValues { vks_and_proofs, is_complete: _ } =
+                                    inner_witness;
+                                assert!(vks_and_proofs.len() == 1);
                                 let (vk, proof) = vks_and_proofs.last().unwrap();
-                        // Wait for our turn to update the state.
-                        prover_sync.wait_for_turn(index);
 
-                        // Send the proof.
-                        proofs_tx.lock().unwrap().send((index, height, vk.clone(), proof.clone())).unwrap();
-
-                        // Advance the turn.
-                        prover_sync.advance_turn();
-                        }
-                    } else {
+                                // Send the proof.
+                                track_queue_depth(proofs_depth, 1);
+                                let _ = proofs_tx
+                                    .send(index, (index, height, vk.clone(), proof.clone()));
+                            }
+                        } else {
                             break;
                         }
                     }
@@ -933,8 +1479,10 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                 let input_tx = Arc::clone(&input_tx);
                 let proofs_rx = Arc::clone(&proofs_rx);
                 let span = tracing::debug_span!("generate next layer inputs");
+                let report_progress = &report_progress;
                 s.spawn(move || {
                     let _span = span.enter();
+                    let mut completed_in_layer = vec![0; expected_height + 1];
                     let mut count = num_first_layer_inputs;
                     let mut batch: Vec<(
                         usize,
@@ -943,8 +1491,26 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                         ShardProof<InnerSC>,
                     )> = Vec::new();
                     loop {
-                        let received = { proofs_rx.lock().unwrap().recv() };
+                        // Stop generating inputs once cancelled, which lets the other workers
+                        // drain their channels and exit.
+                        if stopped() {
+                            break;
+                        }
+                        let received = {
+                            proofs_rx.lock().unwrap().recv_timeout(COMPRESS_CANCEL_POLL_INTERVAL)
+                        };
+                        if let Err(RecvTimeoutError::Timeout) = received {
+                            continue;
+                        }
                         if let Ok((index, height, vk, proof)) = received {
+                            track_queue_depth(proofs_depth, -1);
+                            completed_in_layer[height] += 1;
+                            report_progress(height, completed_in_layer[height]);
+                            if completed_in_layer[height] == layer_sizes[height] {
+                                *proven_layers.0.lock().unwrap() = height + 1;
+                                proven_layers.1.notify_all();
+                            }
+
                             batch.push((index, height, vk, proof));
 
                             // If we haven't reached the batch size, continue.
@@ -976,13 +1542,13 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                                 is_complete,
                             });
 
-                            input_sync.wait_for_turn(count);
-                            input_tx
-                                .lock()
-                                .unwrap()
-                                .send((count, next_input_height, input, is_last))
-                                .unwrap();
-                            input_sync.advance_turn();
+                            track_queue_depth(inputs_depth, 1);
+                            if input_tx
+                                .send(count, (count, next_input_height, input, is_last))
+                                .is_err()
+                            {
+                                break;
+                            }
                             count += 1;
 
                             // If we're at the root of the tree, stop generating inputs.
@@ -1013,22 +1579,161 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             }
             handle.join().unwrap();
 
+            if let Some(err) = worker_error.lock().unwrap().take() {
+                return Err(err);
+            }
+            if cancel.load(Ordering::SeqCst) {
+                return Err(SP1RecursionProverError::Cancelled);
+            }
             let (_, _, vk, proof) = proofs_rx.lock().unwrap().recv().unwrap();
-            (vk, proof)
+            track_queue_depth(proofs_depth, -1);
+            report_progress(expected_height, 1);
+            Ok((vk, proof))
+        })?;
+
+        Ok(CompressedProof(SP1ReduceProof { vk, proof }))
+    }
+
+    /// Execute the recursion program for a compress input and generate its record and traces.
+    #[allow(clippy::type_complexity)]
+    fn generate_compress_record_and_traces(
+        &self,
+        input: SP1CircuitWitness,
+        opts: &SP1CoreOpts,
+    ) -> Result<
+        (
+            Arc<RecursionProgram<BabyBear>>,
+            ExecutionRecord<BabyBear>,
+            Vec<(String, RowMajorMatrix<BabyBear>)>,
+        ),
+        ShapeTooLarge,
+    > {
+        // Get the program and witness stream.
+        let (program, witness_stream) = tracing::debug_span!("get program and witness stream")
+            .in_scope(|| match input {
+                SP1CircuitWitness::Core(input) => {
+                    let mut witness_stream = Vec::new();
+                    Witnessable::<InnerConfig>::write(&input, &mut witness_stream);
+                    (self.recursion_program(&input), witness_stream)
+                }
+                SP1CircuitWitness::Deferred(input) => {
+                    let mut witness_stream = Vec::new();
+                    Witnessable::<InnerConfig>::write(&input, &mut witness_stream);
+                    (self.deferred_program(&input), witness_stream)
+                }
+                SP1CircuitWitness::Compress(input) => {
+                    let mut witness_stream = Vec::new();
+
+                    let input_with_merkle = self.make_merkle_proofs(input);
+
+                    Witnessable::<InnerConfig>::write(&input_with_merkle, &mut witness_stream);
+
+                    (self.compress_program(&input_with_merkle), witness_stream)
+                }
+            });
+        let program = program?;
+
+        // Execute the runtime.
+        let record = tracing::debug_span!("execute runtime").in_scope(|| {
+            let mut runtime = RecursionRuntime::<Val<InnerSC>, Challenge<InnerSC>, _>::new(
+                program.clone(),
+                self.compress_prover.config().perm.clone(),
+            );
+            runtime.witness_stream = witness_stream.into();
+            runtime
+                .run()
+                .map_err(|e| SP1RecursionProverError::RuntimeError(e.to_string()))
+                .unwrap();
+            runtime.record
+        });
+
+        // Generate the dependencies.
+        let mut records = vec![record];
+        tracing::debug_span!("generate dependencies").in_scope(|| {
+            self.compress_prover.machine().generate_dependencies(&mut records, opts, None)
         });
 
-        Ok(SP1ReduceProof { vk, proof })
+        // Generate the traces.
+        let record = records.into_iter().next().unwrap();
+        let traces = tracing::debug_span!("generate traces")
+            .in_scope(|| self.compress_prover.generate_traces(&record, InteractionScope::Local));
+
+        Ok((program, record, traces))
+    }
+
+    /// Prove a recursion program from its record and traces with the compress prover.
+    fn prove_compress_record(
+        &self,
+        program: &RecursionProgram<BabyBear>,
+        record: ExecutionRecord<BabyBear>,
+        traces: Vec<(String, RowMajorMatrix<BabyBear>)>,
+    ) -> (StarkVerifyingKey<InnerSC>, ShardProof<InnerSC>) {
+        // Get the keys.
+        let (pk, vk) = tracing::debug_span!("Setup compress program")
+            .in_scope(|| self.compress_prover.setup(program));
+
+        // Observe the proving key.
+        let mut challenger = self.compress_prover.config().challenger();
+        tracing::debug_span!("observe proving key").in_scope(|| {
+            pk.observe_into(&mut challenger);
+        });
+
+        #[cfg(feature = "debug")]
+        self.compress_prover.debug_constraints(
+            &self.compress_prover.pk_to_host(&pk),
+            vec![record.clone()],
+            &mut challenger.clone(),
+        );
+
+        // Commit to the record and traces.
+        let local_data = tracing::debug_span!("commit")
+            .in_scope(|| self.compress_prover.commit(&record, traces));
+
+        // Observe the commitment.
+        tracing::debug_span!("observe public values").in_scope(|| {
+            challenger
+                .observe_slice(&local_data.public_values[0..self.compress_prover.num_pv_elts()]);
+        });
+
+        // Generate the proof.
+        let proof = tracing::debug_span!("open").in_scope(|| {
+            self.compress_prover
+                .open(
+                    &pk,
+                    None,
+                    local_data,
+                    &mut challenger,
+                    &[
+                        <BabyBearPoseidon2 as StarkGenericConfig>::Challenge::zero(),
+                        <BabyBearPoseidon2 as StarkGenericConfig>::Challenge::zero(),
+                    ],
+                )
+                .unwrap()
+        });
+
+        // Verify the proof.
+        #[cfg(feature = "debug")]
+        self.compress_prover
+            .machine()
+            .verify(
+                &vk,
+                &sp1_stark::MachineProof { shard_proofs: vec![proof.clone()] },
+                &mut self.compress_prover.config().challenger(),
+            )
+            .unwrap();
+
+        (vk, proof)
     }
 
     /// Wrap a reduce proof into a STARK proven over a SNARK-friendly field.
     #[instrument(name = "shrink", level = "info", skip_all)]
     pub fn shrink(
         &self,
-        reduced_proof: SP1ReduceProof<InnerSC>,
+        reduced_proof: CompressedProof,
         opts: SP1ProverOpts,
-    ) -> Result<SP1ReduceProof<InnerSC>, SP1RecursionProverError> {
+    ) -> Result<ShrunkProof, SP1RecursionProverError> {
         // Make the compress proof.
-        let SP1ReduceProof { vk: compressed_vk, proof: compressed_proof } = reduced_proof;
+        let SP1ReduceProof { vk: compressed_vk, proof: compressed_proof } = reduced_proof.0;
         let input = SP1CompressWitnessValues {
             vks_and_proofs: vec![(compressed_vk, compressed_proof)],
             is_complete: true,
@@ -1064,17 +1769,21 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             .prove(&shrink_pk, vec![runtime.record], &mut compress_challenger, opts.recursion_opts)
             .unwrap();
 
-        Ok(SP1ReduceProof { vk: shrink_vk, proof: compress_proof.shard_proofs.pop().unwrap() })
+        Ok(ShrunkProof(SP1ReduceProof {
+            vk: shrink_vk,
+            proof: compress_proof.shard_proofs.pop().unwrap(),
+        }))
     }
 
     /// Wrap a reduce proof into a STARK proven over a SNARK-friendly field.
     #[instrument(name = "wrap_bn254", level = "info", skip_all)]
     pub fn wrap_bn254(
         &self,
-        compressed_proof: SP1ReduceProof<InnerSC>,
+        compressed_proof: ShrunkProof,
         opts: SP1ProverOpts,
     ) -> Result<SP1ReduceProof<OuterSC>, SP1RecursionProverError> {
-        let SP1ReduceProof { vk: compressed_vk, proof: compressed_proof } = compressed_proof;
+        compressed_proof.assert_shrink_shape()?;
+        let SP1ReduceProof { vk: compressed_vk, proof: compressed_proof } = compressed_proof.0;
         let input = SP1CompressWitnessValues {
             vks_and_proofs: vec![(compressed_vk, compressed_proof)],
             is_complete: true,
@@ -1123,24 +1832,29 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         Ok(SP1ReduceProof { vk: wrap_vk, proof: wrap_proof.shard_proofs.pop().unwrap() })
     }
 
+    /// The verifying key of the wrap program, computed from the program if [`Self::wrap_bn254`]
+    /// has not set it yet.
+    ///
+    /// This is the vk of every proof returned by [`Self::wrap_bn254`], so it can be used to derive
+    /// verifier constants without wrapping a proof.
+    pub fn wrap_verifying_key(&self) -> &StarkVerifyingKey<OuterSC> {
+        self.wrap_vk.get_or_init(|| {
+            tracing::debug_span!("setup wrap")
+                .in_scope(|| self.wrap_prover.setup(&self.wrap_program()).1)
+        })
+    }
+
     /// Wrap the STARK proven over a SNARK-friendly field into a PLONK proof.
+    ///
+    /// Returns [`SP1RecursionProverError::WrapVkMismatch`] if `proof` was not produced by
+    /// [`Self::wrap_bn254`].
     #[instrument(name = "wrap_plonk_bn254", level = "info", skip_all)]
     pub fn wrap_plonk_bn254(
         &self,
         proof: SP1ReduceProof<OuterSC>,
         build_dir: &Path,
-    ) -> PlonkBn254Proof {
-        let input = SP1CompressWitnessValues {
-            vks_and_proofs: vec![(proof.vk.clone(), proof.proof.clone())],
-            is_complete: true,
-        };
-        let vkey_hash = sp1_vkey_digest_bn254(&proof);
-        let committed_values_digest = sp1_committed_values_digest_bn254(&proof);
-
-        let mut witness = Witness::default();
-        input.write(&mut witness);
-        witness.write_committed_values_digest(committed_values_digest);
-        witness.write_vkey_hash(vkey_hash);
+    ) -> Result<PlonkBn254Proof, SP1RecursionProverError> {
+        let (witness, vkey_hash, committed_values_digest) = self.plonk_bn254_witness(&proof)?;
 
         let prover = PlonkBn254Prover::new();
         let proof = prover.prove(witness, build_dir.to_path_buf());
@@ -1153,7 +1867,59 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             build_dir,
         );
 
-        proof
+        Ok(proof)
+    }
+
+    /// Wrap the STARK proven over a SNARK-friendly field into a PLONK proof, like
+    /// [`Self::wrap_plonk_bn254`] but with circuit artifacts that were loaded ahead of time.
+    ///
+    /// Loading the artifacts dominates the cost of small PLONK proofs, so callers wrapping many
+    /// proofs should load them once and reuse the same [`LoadedPlonkArtifacts`]. This only saves
+    /// the load with the `native` gnark backend; the docker backend reads them on every proof.
+    #[instrument(name = "wrap_plonk_bn254_with_loaded", level = "info", skip_all)]
+    pub fn wrap_plonk_bn254_with_loaded(
+        &self,
+        proof: SP1ReduceProof<OuterSC>,
+        artifacts: &LoadedPlonkArtifacts,
+    ) -> Result<PlonkBn254Proof, SP1RecursionProverError> {
+        let (witness, vkey_hash, committed_values_digest) = self.plonk_bn254_witness(&proof)?;
+
+        let prover = PlonkBn254Prover::new();
+        let proof = prover.prove_with_loaded(witness, artifacts);
+
+        // Verify the proof.
+        prover.verify(
+            &proof,
+            &vkey_hash.as_canonical_biguint(),
+            &committed_values_digest.as_canonical_biguint(),
+            artifacts.build_dir(),
+        );
+
+        Ok(proof)
+    }
+
+    /// Builds the PLONK circuit witness for a wrapped proof, along with its public inputs.
+    fn plonk_bn254_witness(
+        &self,
+        proof: &SP1ReduceProof<OuterSC>,
+    ) -> Result<(Witness<OuterConfig>, Bn254Fr, Bn254Fr), SP1RecursionProverError> {
+        if proof.vk.commit != self.wrap_verifying_key().commit {
+            return Err(SP1RecursionProverError::WrapVkMismatch);
+        }
+
+        let input = SP1CompressWitnessValues {
+            vks_and_proofs: vec![(proof.vk.clone(), proof.proof.clone())],
+            is_complete: true,
+        };
+        let vkey_hash = sp1_vkey_digest_bn254(proof);
+        let committed_values_digest = sp1_committed_values_digest_bn254(proof);
+
+        let mut witness = Witness::default();
+        input.write(&mut witness);
+        witness.write_committed_values_digest(committed_values_digest);
+        witness.write_vkey_hash(vkey_hash);
+
+        Ok((witness, vkey_hash, committed_values_digest))
     }
 
     /// Wrap the STARK proven over a SNARK-friendly field into a Groth16 proof.
@@ -1190,46 +1956,110 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
     }
 
     /// Accumulate deferred proofs into a single digest.
+    ///
+    /// The digest is a hash chain, `d_{i+1} = H(d_i || vk_digest_i || pv_digest_i)`, so the fold
+    /// itself is inherently sequential and cannot be split into independent partial folds without
+    /// changing the digest definition checked by the recursion circuits. The per-proof digest
+    /// extraction is independent, however, and is done in parallel before the fold.
     pub fn hash_deferred_proofs(
         prev_digest: [Val<CoreSC>; DIGEST_SIZE],
         deferred_proofs: &[SP1ReduceProof<InnerSC>],
     ) -> [Val<CoreSC>; 8] {
-        let mut digest = prev_digest;
-        for proof in deferred_proofs.iter() {
-            let pv: &RecursionPublicValues<Val<CoreSC>> =
-                proof.proof.public_values.as_slice().borrow();
-            let committed_values_digest = words_to_bytes(&pv.committed_value_digest);
-            digest = hash_deferred_proof(
-                &digest,
-                &pv.sp1_vk_digest,
-                &committed_values_digest.try_into().unwrap(),
-            );
-        }
-        digest
+        Self::deferred_proof_digests(deferred_proofs).iter().fold(
+            prev_digest,
+            |digest, (vk_digest, committed_values_digest)| {
+                hash_deferred_proof(&digest, vk_digest, committed_values_digest)
+            },
+        )
+    }
+
+    /// Check that `deferred_proofs`, folded in order starting from the zero digest, hash to
+    /// `expected`.
+    ///
+    /// This is the digest a program commits to when it verifies these proofs, so a mismatch, e.g.
+    /// from passing the proofs in a different order than the program verified them, can be caught
+    /// before compression.
+    pub fn verify_deferred_digest_chain(
+        deferred_proofs: &[SP1ReduceProof<InnerSC>],
+        expected: [Val<CoreSC>; DIGEST_SIZE],
+    ) -> bool {
+        Self::hash_deferred_proofs([Val::<CoreSC>::zero(); DIGEST_SIZE], deferred_proofs)
+            == expected
+    }
+
+    /// Extract the `sp1_vk_digest` and the byte-decomposed `committed_value_digest` from the public
+    /// values of each deferred proof, in order.
+    pub fn deferred_proof_digests(
+        deferred_proofs: &[SP1ReduceProof<InnerSC>],
+    ) -> Vec<([Val<CoreSC>; DIGEST_SIZE], [Val<CoreSC>; 32])> {
+        deferred_proofs
+            .par_iter()
+            .map(|proof| {
+                let pv: &RecursionPublicValues<Val<CoreSC>> =
+                    proof.proof.public_values.as_slice().borrow();
+                let committed_values_digest = words_to_bytes(&pv.committed_value_digest);
+                (pv.sp1_vk_digest, committed_values_digest.try_into().unwrap())
+            })
+            .collect()
     }
 
     pub fn make_merkle_proofs(
         &self,
         input: SP1CompressWitnessValues<CoreSC>,
     ) -> SP1CompressWithVKeyWitnessValues<CoreSC> {
-        let num_vks = self.allowed_vk_map.len();
-        let (vk_indices, vk_digest_values): (Vec<_>, Vec<_>) = if self.vk_verification {
+        self.make_merkle_proofs_with_indices(input, &BTreeMap::new())
+    }
+
+    /// Like [`Self::make_merkle_proofs`], but when vk verification is disabled, the vks whose
+    /// digests appear in `index_overrides` are mapped to the given dummy leaves instead of one
+    /// derived from their digest.
+    ///
+    /// The overrides are ignored when vk verification is enabled, since every vk must then be
+    /// proven against its actual leaf.
+    pub fn make_merkle_proofs_with_indices(
+        &self,
+        input: SP1CompressWitnessValues<CoreSC>,
+        index_overrides: &BTreeMap<[BabyBear; DIGEST_SIZE], usize>,
+    ) -> SP1CompressWithVKeyWitnessValues<CoreSC> {
+        // The proofs of an input are usually of the same vk, so only hash each distinct vk once.
+        let vk_digests = {
+            let mut hashed: Vec<(&StarkVerifyingKey<CoreSC>, _)> = Vec::new();
             input
                 .vks_and_proofs
                 .iter()
                 .map(|(vk, _)| {
-                    let vk_digest = vk.hash_babybear();
+                    if let Some((_, digest)) =
+                        hashed.iter().find(|(other, _)| same_vk_digest(vk, other))
+                    {
+                        return *digest;
+                    }
+                    let digest = vk.hash_babybear();
+                    hashed.push((vk, digest));
+                    digest
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let num_vks = self.allowed_vk_map.len();
+        let (vk_indices, vk_digest_values): (Vec<_>, Vec<_>) = if self.vk_verification {
+            vk_digests
+                .into_iter()
+                .map(|vk_digest| {
                     let index = self.allowed_vk_map.get(&vk_digest).expect("vk not allowed");
                     (index, vk_digest)
                 })
                 .unzip()
         } else {
-            input
-                .vks_and_proofs
-                .iter()
-                .map(|(vk, _)| {
-                    let vk_digest = vk.hash_babybear();
-                    let index = (vk_digest[0].as_canonical_u32() as usize) % num_vks;
+            vk_digests
+                .into_iter()
+                .map(|vk_digest| {
+                    let index = match index_overrides.get(&vk_digest) {
+                        Some(&index) => {
+                            assert!(index < num_vks, "dummy vk index {index} out of range");
+                            index
+                        }
+                        None => (vk_digest[0].as_canonical_u32() as usize) % num_vks,
+                    };
                     (index, [BabyBear::from_canonical_usize(index); 8])
                 })
                 .unzip()
@@ -1252,8 +2082,8 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         SP1CompressWithVKeyWitnessValues { compress_val: input, merkle_val }
     }
 
-    fn check_for_high_cycles(cycles: u64) {
-        if cycles > 100_000_000 {
+    fn check_for_high_cycles(cycles: u64, warn_cycles: Option<u64>) {
+        if cycles > warn_cycles.unwrap_or(DEFAULT_WARN_CYCLES) {
             tracing::warn!(
                 "high cycle count, consider using the prover network for proof generation: https://docs.succinct.xyz/generating-proofs/prover-network"
             );
@@ -1261,11 +2091,38 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
     }
 }
 
+/// Whether two verifying keys have the same digest, which only covers the preprocessed commitment,
+/// the start pc and the domains of the preprocessed traces.
+fn same_vk_digest(vk: &StarkVerifyingKey<CoreSC>, other: &StarkVerifyingKey<CoreSC>) -> bool {
+    vk.commit == other.commit
+        && vk.pc_start == other.pc_start
+        && vk.chip_information.len() == other.chip_information.len()
+        && vk.chip_information.iter().zip(&other.chip_information).all(
+            |((_, domain, _), (_, other_domain, _))| {
+                domain.log_n == other_domain.log_n && domain.shift == other_domain.shift
+            },
+        )
+}
+
+/// The number of proofs in each layer of the compress tree with `num_first_layer_inputs` leaves,
+/// from the first layer to the root.
+///
+/// Each layer reduces the proofs of the previous one in pairs, and an odd proof left at the end of
+/// a layer is carried over to the next one.
+fn compress_layer_sizes(num_first_layer_inputs: usize) -> Vec<usize> {
+    let mut layer_sizes = vec![num_first_layer_inputs];
+    while let Some(&size @ 2..) = layer_sizes.last() {
+        layer_sizes.push(size.div_ceil(REDUCE_BATCH_SIZE));
+    }
+    layer_sizes
+}
+
 #[cfg(any(test, feature = "export-tests"))]
 pub mod tests {
 
+    #[cfg(test)]
+    use std::borrow::BorrowMut;
     use std::{
-        collections::BTreeSet,
         fs::File,
         io::{Read, Write},
     };
@@ -1277,13 +2134,20 @@ pub mod tests {
     use build::{build_constraints_and_witness, try_build_groth16_bn254_artifacts_dev};
     use p3_field::PrimeField32;
 
-    use shapes::SP1ProofShape;
+    #[cfg(test)]
+    use sp1_core_executor::{
+        subproof::SubproofVerifier, syscalls::SyscallCode, VersionedProofError,
+    };
+    #[cfg(test)]
+    use sp1_recursion_circuit::stark::dummy_vk_and_shard_proof;
     use sp1_recursion_core::air::RecursionPublicValues;
 
     #[cfg(test)]
     use serial_test::serial;
     #[cfg(test)]
     use sp1_core_machine::utils::setup_logger;
+    #[cfg(test)]
+    use sp1_stark::CpuProver;
     use utils::sp1_vkey_digest_babybear;
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -1335,11 +2199,9 @@ pub mod tests {
         let public_values = core_proof.public_values.clone();
 
         if env::var("COLLECT_SHAPES").is_ok() {
-            let mut shapes = BTreeSet::new();
-            for proof in core_proof.proof.0.iter() {
-                let shape = SP1ProofShape::Recursion(proof.shape());
+            let shapes = prover.collect_shapes(&core_proof);
+            for shape in &shapes {
                 tracing::info!("shape: {:?}", shape);
-                shapes.insert(shape);
             }
 
             let mut file = File::create("../shapes.bin").unwrap();
@@ -1383,7 +2245,7 @@ pub mod tests {
 
         tracing::info!("wrap bn254");
         let wrapped_bn254_proof = prover.wrap_bn254(shrink_proof, opts)?;
-        let bytes = bincode::serialize(&wrapped_bn254_proof).unwrap();
+        let bytes = wrapped_bn254_proof.to_versioned_bytes();
 
         // Save the proof.
         let mut file = File::create("proof-with-pis.bin").unwrap();
@@ -1394,7 +2256,7 @@ pub mod tests {
         let mut bytes = Vec::new();
         file.read_to_end(&mut bytes).unwrap();
 
-        let wrapped_bn254_proof = bincode::deserialize(&bytes).unwrap();
+        let wrapped_bn254_proof = SP1ReduceProof::<OuterSC>::from_versioned_bytes(&bytes)?;
 
         if verify {
             tracing::info!("verify wrap bn254");
@@ -1429,11 +2291,20 @@ pub mod tests {
             &wrapped_bn254_proof.proof,
         );
         let plonk_bn254_proof =
-            prover.wrap_plonk_bn254(wrapped_bn254_proof.clone(), &artifacts_dir);
+            prover.wrap_plonk_bn254(wrapped_bn254_proof.clone(), &artifacts_dir)?;
         println!("{:?}", plonk_bn254_proof);
 
         prover.verify_plonk_bn254(&plonk_bn254_proof, &vk, &public_values, &artifacts_dir)?;
 
+        tracing::info!("verify plonk bn254 proof with only the verifying key");
+        let plonk_vk_bytes = std::fs::read(artifacts_dir.join("plonk_vk.bin"))?;
+        prover.verify_plonk_bn254_with_vk(
+            &plonk_bn254_proof,
+            &vk,
+            &public_values,
+            &plonk_vk_bytes,
+        )?;
+
         tracing::info!("generate groth16 bn254 proof");
         let artifacts_dir = try_build_groth16_bn254_artifacts_dev(
             &wrapped_bn254_proof.vk,
@@ -1491,11 +2362,13 @@ pub mod tests {
 
         // Generate recursive proof of first subproof.
         tracing::info!("compress subproof 1");
-        let deferred_reduce_1 = prover.compress(&keccak_vk, deferred_proof_1, vec![], opts)?;
+        let deferred_reduce_1 =
+            prover.compress(&keccak_vk, deferred_proof_1, vec![], opts)?.into_inner();
 
         // Generate recursive proof of second subproof.
         tracing::info!("compress subproof 2");
-        let deferred_reduce_2 = prover.compress(&keccak_vk, deferred_proof_2, vec![], opts)?;
+        let deferred_reduce_2 =
+            prover.compress(&keccak_vk, deferred_proof_2, vec![], opts)?.into_inner();
 
         // Run verify program with keccak vkey, subproofs, and their committed values.
         let mut stdin = SP1Stdin::new();
@@ -1526,8 +2399,7 @@ pub mod tests {
         )?;
         let reduce_pv: &RecursionPublicValues<_> =
             verify_reduce.proof.public_values.as_slice().borrow();
-        println!("deferred_hash: {:?}", reduce_pv.deferred_proofs_digest);
-        println!("complete: {:?}", reduce_pv.is_complete);
+        println!("{}", reduce_pv.describe());
 
         tracing::info!("verify verify program");
         prover.verify_compressed(&verify_reduce, &verify_vk)?;
@@ -1575,6 +2447,1152 @@ pub mod tests {
         test_e2e_with_deferred_proofs_prover::<CpuProverComponents>(SP1ProverOpts::default())
     }
 
+    /// Checks that the parallel digest extraction in [SP1Prover::hash_deferred_proofs] keeps the
+    /// order of 64 deferred proofs with distinct digests, and that the digest matches a sequential
+    /// fold.
+    #[test]
+    fn test_hash_deferred_proofs_parallel_extraction() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let shape = prover
+            .recursion_shape_config
+            .as_ref()
+            .unwrap()
+            .get_all_shape_combinations(1)
+            .next()
+            .unwrap()
+            .pop()
+            .unwrap();
+        let (vk, proof) = dummy_vk_and_shard_proof(prover.compress_prover.machine(), &shape);
+
+        let deferred_proofs = (0..64u32)
+            .map(|i| {
+                let mut proof = proof.clone();
+                let pv: &mut RecursionPublicValues<BabyBear> =
+                    proof.public_values.as_mut_slice().borrow_mut();
+                pv.sp1_vk_digest = [BabyBear::from_canonical_u32(i); DIGEST_SIZE];
+                for (j, word) in pv.committed_value_digest.iter_mut().enumerate() {
+                    *word = Word::from(i * 8 + j as u32);
+                }
+                SP1ReduceProof { vk: vk.clone(), proof }
+            })
+            .collect::<Vec<_>>();
+
+        let sequential = deferred_proofs
+            .iter()
+            .map(|proof| {
+                let pv: &RecursionPublicValues<BabyBear> =
+                    proof.proof.public_values.as_slice().borrow();
+                let committed_values_digest: [BabyBear; 32] =
+                    words_to_bytes(&pv.committed_value_digest).try_into().unwrap();
+                (pv.sp1_vk_digest, committed_values_digest)
+            })
+            .collect::<Vec<_>>();
+        let parallel = SP1Prover::<CpuProverComponents>::deferred_proof_digests(&deferred_proofs);
+        assert_eq!(sequential, parallel);
+        for (i, (vk_digest, _)) in parallel.iter().enumerate() {
+            assert_eq!(*vk_digest, [BabyBear::from_canonical_usize(i); DIGEST_SIZE]);
+        }
+
+        let expected = sequential.iter().fold(
+            [BabyBear::zero(); DIGEST_SIZE],
+            |digest, (vk_digest, committed_values_digest)| {
+                hash_deferred_proof(&digest, vk_digest, committed_values_digest)
+            },
+        );
+        let digest = SP1Prover::<CpuProverComponents>::hash_deferred_proofs(
+            [BabyBear::zero(); DIGEST_SIZE],
+            &deferred_proofs,
+        );
+        assert_eq!(digest, expected);
+    }
+
+    /// Checks that [SP1Prover::verify_deferred_digest_chain] accepts deferred proofs in the order
+    /// their digest was computed and rejects them in any other order.
+    #[test]
+    fn test_verify_deferred_digest_chain() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let shape = prover
+            .recursion_shape_config
+            .as_ref()
+            .unwrap()
+            .get_all_shape_combinations(1)
+            .next()
+            .unwrap()
+            .pop()
+            .unwrap();
+        let (vk, proof) = dummy_vk_and_shard_proof(prover.compress_prover.machine(), &shape);
+
+        let mut deferred_proofs = (0..3u32)
+            .map(|i| {
+                let mut proof = proof.clone();
+                let pv: &mut RecursionPublicValues<BabyBear> =
+                    proof.public_values.as_mut_slice().borrow_mut();
+                pv.sp1_vk_digest = [BabyBear::from_canonical_u32(i); DIGEST_SIZE];
+                SP1ReduceProof { vk: vk.clone(), proof }
+            })
+            .collect::<Vec<_>>();
+        let expected = SP1Prover::<CpuProverComponents>::hash_deferred_proofs(
+            [BabyBear::zero(); DIGEST_SIZE],
+            &deferred_proofs,
+        );
+        assert!(SP1Prover::<CpuProverComponents>::verify_deferred_digest_chain(
+            &deferred_proofs,
+            expected
+        ));
+
+        deferred_proofs.swap(0, 2);
+        assert!(!SP1Prover::<CpuProverComponents>::verify_deferred_digest_chain(
+            &deferred_proofs,
+            expected
+        ));
+        assert!(!SP1Prover::<CpuProverComponents>::verify_deferred_digest_chain(
+            &deferred_proofs[..2],
+            expected
+        ));
+    }
+
+    /// Checks that [SubproofVerifier::verify_batch] accepts a batch of valid deferred proofs,
+    /// and rejects a batch with one invalid entry.
+    #[test]
+    #[serial]
+    fn test_verify_batch_deferred_proofs() -> Result<()> {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (keccak_pk, keccak_vk) = prover.setup(test_artifacts::KECCAK256_ELF);
+
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&1usize);
+        stdin.write(&vec![0u8, 0, 0]);
+        let core_proof = prover.prove_core(&keccak_pk, &stdin, opts, Default::default())?;
+        let reduce_proof = prover.compress(&keccak_vk, core_proof, vec![], opts)?;
+        let pv: &RecursionPublicValues<BabyBear> =
+            reduce_proof.proof.public_values.as_slice().borrow();
+        let committed_value_digest = pv.committed_value_digest.map(|word| word.to_u32());
+        let vk_hash = keccak_vk.vk.hash_u32();
+
+        let verifier = &prover;
+        let batch = vec![(&reduce_proof, &keccak_vk.vk, vk_hash, committed_value_digest); 4];
+
+        verifier.verify_batch(&batch)?;
+
+        // A single entry with the wrong committed value digest fails the whole batch.
+        let mut invalid_batch = batch.clone();
+        invalid_batch[2].3 = [0; 8];
+        assert!(verifier.verify_batch(&invalid_batch).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_merkle_proofs_with_indices() {
+        let mut prover = SP1Prover::<CpuProverComponents>::new();
+        prover.vk_verification = false;
+        let shapes = prover
+            .recursion_shape_config
+            .as_ref()
+            .unwrap()
+            .get_all_shape_combinations(1)
+            .take(2)
+            .map(|mut shapes| shapes.pop().unwrap())
+            .collect::<Vec<_>>();
+        let vks_and_proofs = shapes
+            .iter()
+            .map(|shape| dummy_vk_and_shard_proof(prover.compress_prover.machine(), shape))
+            .collect::<Vec<_>>();
+        let digests = vks_and_proofs.iter().map(|(vk, _)| vk.hash_babybear()).collect::<Vec<_>>();
+        assert_ne!(digests[0], digests[1]);
+
+        // Pin the two vks to specific dummy leaves.
+        let index_overrides = BTreeMap::from([(digests[0], 3), (digests[1], 7)]);
+        let input = SP1CompressWitnessValues { vks_and_proofs, is_complete: false };
+        let witness = prover.make_merkle_proofs_with_indices(input, &index_overrides);
+        assert_eq!(
+            witness.merkle_val.values,
+            vec![[BabyBear::from_canonical_usize(3); 8], [BabyBear::from_canonical_usize(7); 8]]
+        );
+        assert_eq!(witness.merkle_val.vk_merkle_proofs[0].index, 3);
+        assert_eq!(witness.merkle_val.vk_merkle_proofs[1].index, 7);
+    }
+
+    #[test]
+    fn test_make_merkle_proofs_hashes_each_vk_once() {
+        use crate::types::VK_HASHES;
+
+        let mut prover = SP1Prover::<CpuProverComponents>::new();
+        prover.vk_verification = false;
+        let shapes = prover
+            .recursion_shape_config
+            .as_ref()
+            .unwrap()
+            .get_all_shape_combinations(1)
+            .take(2)
+            .map(|mut shapes| shapes.pop().unwrap())
+            .collect::<Vec<_>>();
+        let (vk, proof) = dummy_vk_and_shard_proof(prover.compress_prover.machine(), &shapes[0]);
+        let other = dummy_vk_and_shard_proof(prover.compress_prover.machine(), &shapes[1]);
+        let digests = [vk.hash_babybear(), other.0.hash_babybear()];
+
+        let vks_and_proofs = vec![(vk.clone(), proof.clone()), (vk, proof), other];
+        let input = SP1CompressWitnessValues { vks_and_proofs, is_complete: false };
+        let hashes_before = VK_HASHES.with(|hashes| hashes.get());
+        let witness = prover.make_merkle_proofs(input);
+        assert_eq!(VK_HASHES.with(|hashes| hashes.get()), hashes_before + 2);
+
+        let index = |digest: [BabyBear; DIGEST_SIZE]| {
+            [BabyBear::from_canonical_usize(
+                digest[0].as_canonical_u32() as usize % prover.allowed_vk_map.len(),
+            ); 8]
+        };
+        assert_eq!(
+            witness.merkle_val.values,
+            vec![index(digests[0]), index(digests[0]), index(digests[1])]
+        );
+    }
+
+    #[test]
+    fn test_versioned_reduce_proof_mismatch() {
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let shape = prover
+            .recursion_shape_config
+            .as_ref()
+            .unwrap()
+            .get_all_shape_combinations(1)
+            .next()
+            .unwrap()
+            .pop()
+            .unwrap();
+        let (vk, proof) = dummy_vk_and_shard_proof(prover.compress_prover.machine(), &shape);
+        let proof = SP1ReduceProof { vk, proof };
+
+        let bytes = proof.to_versioned_bytes_with_version("v0.0.0-old");
+        let err =
+            SP1ReduceProof::<InnerSC>::from_versioned_bytes_with_version(&bytes, "v0.0.0-new")
+                .unwrap_err();
+        match err {
+            VersionedProofError::VersionMismatch { found, expected } => {
+                assert_eq!(found, "v0.0.0-old");
+                assert_eq!(expected, "v0.0.0-new");
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+
+        let bytes = proof.to_versioned_bytes();
+        let decoded = SP1ReduceProof::<InnerSC>::from_versioned_bytes(&bytes).unwrap();
+        assert_eq!(decoded.proof.public_values, proof.proof.public_values);
+
+        let err = SP1ReduceProof::<InnerSC>::from_versioned_bytes(&bytes[1..]).unwrap_err();
+        assert!(matches!(err, VersionedProofError::InvalidHeader));
+    }
+
+    #[test]
+    fn test_try_write_proof_vk_mismatch() {
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let shape = prover
+            .recursion_shape_config
+            .as_ref()
+            .unwrap()
+            .get_all_shape_combinations(1)
+            .next()
+            .unwrap()
+            .pop()
+            .unwrap();
+        let (compress_vk, mut proof) =
+            dummy_vk_and_shard_proof(prover.compress_prover.machine(), &shape);
+
+        let (_, fibonacci_vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let (_, keccak_vk) = prover.setup(test_artifacts::KECCAK256_ELF);
+        let pv: &mut RecursionPublicValues<BabyBear> =
+            proof.public_values.as_mut_slice().borrow_mut();
+        pv.sp1_vk_digest = fibonacci_vk.hash_babybear();
+        let proof = SP1ReduceProof { vk: compress_vk, proof };
+
+        let mut stdin = SP1Stdin::new();
+        let err = stdin.try_write_proof(proof.clone(), keccak_vk.vk.clone()).unwrap_err();
+        assert!(err.to_string().contains("does not match the digest of the provided vk"));
+        assert!(stdin.proofs.is_empty());
+
+        stdin.try_write_proof(proof, fibonacci_vk.vk).unwrap();
+        assert_eq!(stdin.proofs.len(), 1);
+    }
+
+    /// Checks that compressing with a deferred proof whose public values were never set is
+    /// rejected before proving anything.
+    #[test]
+    #[serial]
+    fn test_compress_rejects_uninitialized_deferred_proof() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let core_proof =
+            prover.prove_core(&pk, &SP1Stdin::default(), opts, SP1Context::default()).unwrap();
+
+        let shape = prover
+            .recursion_shape_config
+            .as_ref()
+            .unwrap()
+            .get_all_shape_combinations(1)
+            .next()
+            .unwrap()
+            .pop()
+            .unwrap();
+        let (compress_vk, mut proof) =
+            dummy_vk_and_shard_proof(prover.compress_prover.machine(), &shape);
+        let deferred_proof = SP1ReduceProof { vk: compress_vk.clone(), proof: proof.clone() };
+        let err = prover.compress(&vk, core_proof.clone(), vec![deferred_proof], opts).unwrap_err();
+        assert!(matches!(
+            err,
+            SP1RecursionProverError::UninitializedDeferredProof {
+                index: 0,
+                field: "committed_value_digest"
+            }
+        ));
+
+        let pv: &mut RecursionPublicValues<BabyBear> =
+            proof.public_values.as_mut_slice().borrow_mut();
+        pv.committed_value_digest[0].0[0] = BabyBear::one();
+        let deferred_proof = SP1ReduceProof { vk: compress_vk, proof };
+        let err = prover.compress(&vk, core_proof, vec![deferred_proof], opts).unwrap_err();
+        assert!(matches!(
+            err,
+            SP1RecursionProverError::UninitializedDeferredProof {
+                index: 0,
+                field: "sp1_vk_digest"
+            }
+        ));
+    }
+
+    /// Checks that a compress cancelled once its first proof is done returns
+    /// [SP1RecursionProverError::Cancelled] without completing any further proof.
+    #[test]
+    #[serial]
+    fn test_compress_cancellable() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let mut opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let core_proof =
+            prover.prove_core(&pk, &SP1Stdin::default(), opts, SP1Context::default()).unwrap();
+
+        // With a single prover, no other proof is in flight when the first one is done.
+        opts.recursion_opts.recursion_prove_workers = 1;
+        let cancel = AtomicBool::new(false);
+        let completed_after_cancel = AtomicUsize::new(0);
+        let progress = |_: CompressProgress| {
+            if cancel.swap(true, Ordering::SeqCst) {
+                completed_after_cancel.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+        let result =
+            prover.compress_impl(&vk, core_proof, vec![], opts, &cancel, Some(&progress), None);
+        assert!(matches!(result, Err(SP1RecursionProverError::Cancelled)));
+        assert_eq!(completed_after_cancel.load(Ordering::SeqCst), 0);
+    }
+
+    /// A third-party set of components, reusing the CPU provers.
+    #[cfg(test)]
+    struct ExampleComponents;
+
+    #[cfg(test)]
+    impl SP1ProverComponents for ExampleComponents {
+        type CoreProver = CpuProver<CoreSC, RiscvAir<BabyBear>>;
+        type CompressProver = CpuProver<InnerSC, CompressAir<BabyBear>>;
+        type ShrinkProver = CpuProver<InnerSC, ShrinkAir<BabyBear>>;
+        type WrapProver = CpuProver<OuterSC, WrapAir<<OuterSC as StarkGenericConfig>::Val>>;
+    }
+
+    /// Checks that a proving key round-trips host -> device -> host through the core prover of a
+    /// set of components.
+    #[test]
+    fn test_proving_key_device_round_trip() {
+        setup_logger();
+        let prover = SP1Prover::<ExampleComponents>::new();
+        let (pk, _) = prover.setup(test_artifacts::FIBONACCI_ELF);
+
+        let device_pk = prover.core_prover.pk_to_device(&pk.pk);
+        let host_pk = prover.core_prover.pk_to_host(&device_pk);
+        assert_eq!(bincode::serialize(&host_pk).unwrap(), bincode::serialize(&pk.pk).unwrap());
+    }
+
+    /// Checks that proving two inputs of the same program concurrently with the device proving key
+    /// cache enabled only copies the proving key to the device once.
+    #[test]
+    #[serial]
+    fn test_device_pk_cache() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new()
+            .with_device_pk_cache(NonZeroUsize::new(1).unwrap());
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(test_artifacts::KECCAK256_ELF);
+
+        thread::scope(|s| {
+            for input in [vec![0u8, 1, 2], vec![3u8, 4, 5]] {
+                let (prover, pk, vk) = (&prover, &pk, &vk);
+                s.spawn(move || {
+                    let mut stdin = SP1Stdin::new();
+                    stdin.write(&1usize);
+                    stdin.write(&input);
+                    let core_proof =
+                        prover.prove_core(pk, &stdin, opts, SP1Context::default()).unwrap();
+                    prover.verify(&core_proof.proof, vk).unwrap();
+                });
+            }
+        });
+        assert_eq!(prover.pk_to_device_conversions.load(Ordering::Relaxed), 1);
+    }
+
+    /// Checks that [SP1Prover::try_setup] reports a parse error for bytes that are not an ELF.
+    #[test]
+    fn test_try_setup_malformed_elf() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let elf =
+            (0..1024u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8).collect::<Vec<_>>();
+        let err = prover.try_setup(&elf).unwrap_err();
+        assert!(matches!(err, ProgramError::Parse(_)));
+        assert!(err.to_string().starts_with("Failed to parse ELF: "));
+    }
+
+    /// Checks that compression yields a verifying proof with both fully serial and parallel
+    /// recursion workers, and that zero workers is rejected.
+    #[test]
+    #[serial]
+    fn test_compress_recursion_workers() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let core_proof = prover
+            .prove_core(&pk, &SP1Stdin::default(), SP1ProverOpts::default(), SP1Context::default())
+            .unwrap();
+
+        for workers in [1, 4] {
+            let mut opts = SP1ProverOpts::default();
+            opts.recursion_opts.recursion_trace_workers = workers;
+            opts.recursion_opts.recursion_prove_workers = workers;
+            let compressed = prover.compress(&vk, core_proof.clone(), vec![], opts).unwrap();
+            prover.verify_compressed(&compressed, &vk).unwrap();
+        }
+
+        let mut opts = SP1ProverOpts::default();
+        opts.recursion_opts.recursion_prove_workers = 0;
+        let result = prover.compress(&vk, core_proof, vec![], opts);
+        assert!(matches!(result, Err(SP1RecursionProverError::InvalidOpts(_))));
+    }
+
+    /// Snapshots a challenger between observations and checks that the reconstructed challenger
+    /// samples the same values.
+    #[test]
+    fn test_challenger_snapshot_round_trip() {
+        use p3_challenger::CanSample;
+        use sp1_stark::baby_bear_poseidon2::ChallengerSnapshot;
+
+        let config = CoreSC::default();
+        let mut challenger = config.challenger();
+        challenger.observe_slice(&[BabyBear::from_canonical_u32(7); 11]);
+
+        let snapshot = ChallengerSnapshot::from(&challenger);
+        assert_eq!(snapshot.input_buffer.len(), 11);
+        let bytes = bincode::serialize(&snapshot).unwrap();
+        let decoded: ChallengerSnapshot = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded, snapshot);
+        let mut reconstructed = decoded.reconstruct(&config);
+
+        for challenger in [&mut challenger, &mut reconstructed] {
+            challenger.observe(BabyBear::from_canonical_u32(3));
+        }
+        for _ in 0..20 {
+            let expected: BabyBear = challenger.sample();
+            let sampled: BabyBear = reconstructed.sample();
+            assert_eq!(sampled, expected);
+        }
+    }
+
+    /// Loads a tiny custom vk map from a file and checks that the prover commits to it instead of
+    /// the baked-in map.
+    #[test]
+    fn test_with_allowed_vk_map_path() {
+        let allowed: BTreeMap<[BabyBear; DIGEST_SIZE], usize> =
+            [([BabyBear::from_canonical_u32(42); DIGEST_SIZE], 0)].into_iter().collect();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&bincode::serialize(&allowed).unwrap()).unwrap();
+
+        let default_prover = SP1Prover::<CpuProverComponents>::uninitialized();
+        let prover = SP1Prover::<CpuProverComponents>::uninitialized()
+            .with_allowed_vk_map_path(file.path())
+            .unwrap();
+        assert_eq!(prover.allowed_vk_map, allowed);
+        assert_eq!(prover.vk_root, compute_vk_root(&allowed).0);
+        assert_ne!(prover.vk_root, default_prover.vk_root);
+        assert_eq!(prover.vk_verification, default_prover.vk_verification);
+
+        let missing = file.path().with_extension("missing");
+        assert!(SP1Prover::<CpuProverComponents>::uninitialized()
+            .with_allowed_vk_map_path(missing)
+            .is_err());
+
+        // The leaf indices must be 0..n in key order.
+        let out_of_order: BTreeMap<[BabyBear; DIGEST_SIZE], usize> = [
+            ([BabyBear::from_canonical_u32(1); DIGEST_SIZE], 1),
+            ([BabyBear::from_canonical_u32(2); DIGEST_SIZE], 0),
+        ]
+        .into_iter()
+        .collect();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&bincode::serialize(&out_of_order).unwrap()).unwrap();
+        let err = SP1Prover::<CpuProverComponents>::uninitialized()
+            .with_allowed_vk_map_path(file.path())
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    /// Commits a small set of allowed vks and checks the root against a direct recomputation and
+    /// the openings of each vk.
+    #[test]
+    fn test_compute_vk_root() {
+        // Leaves are committed in key order, so the index of each vk is its position in the map.
+        let mut allowed: BTreeMap<[BabyBear; DIGEST_SIZE], usize> = (0..3u32)
+            .map(|i| (core::array::from_fn(|j| BabyBear::from_canonical_u32(8 * i + j as u32)), 0))
+            .collect();
+        for (index, value) in allowed.values_mut().enumerate() {
+            *value = index;
+        }
+
+        let (root, merkle_tree) = compute_vk_root(&allowed);
+        let (expected, _) =
+            MerkleTree::<BabyBear, InnerSC>::commit(allowed.keys().copied().collect());
+        assert_eq!(root, expected);
+
+        for (digest, index) in &allowed {
+            let (value, proof) = merkle_tree.open(*index);
+            assert_eq!(value, *digest);
+            MerkleTree::<BabyBear, InnerSC>::verify(proof, value, root).unwrap();
+        }
+    }
+
+    /// Checks that execution accepts a schema matching the committed public values and rejects
+    /// schemas with too few or too many fields.
+    #[test]
+    fn test_execute_with_schema() {
+        use schema::{PublicValueType, PublicValuesSchemaError};
+
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let elf = test_artifacts::FIBONACCI_ELF;
+        let execute = |schema| {
+            prover.execute_with_schema(elf, &SP1Stdin::new(), SP1Context::default(), &schema)
+        };
+
+        // Fibonacci commits the last two numbers of the sequence as `i32`s.
+        let schema = PublicValuesSchema::new()
+            .field("a", PublicValueType::I32)
+            .field("b", PublicValueType::I32);
+        execute(schema.clone()).unwrap();
+
+        let short = PublicValuesSchema::new().field("a", PublicValueType::I32);
+        assert!(matches!(
+            execute(short),
+            Err(ExecuteWithSchemaError::Schema(PublicValuesSchemaError::TrailingBytes {
+                count: 4
+            }))
+        ));
+
+        let long = schema.field("c", PublicValueType::U64);
+        assert!(matches!(
+            execute(long),
+            Err(ExecuteWithSchemaError::Schema(PublicValuesSchemaError::Truncated {
+                needed: 8,
+                remaining: 0,
+                ..
+            }))
+        ));
+    }
+
+    /// Executes with checkpointing, then proves from the checkpoints. Checkpoints of another stdin
+    /// or program are rejected, and proving reads the persisted checkpoints instead of executing.
+    #[test]
+    #[serial]
+    fn test_prove_core_from_checkpoints() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(test_artifacts::VEC_OF_SLICES_ELF);
+        let mut stdin = SP1Stdin::new();
+        stdin.write_vec_of_slices(&[b"checkpoint", b"resume"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let context = || SP1Context::builder().with_checkpoint_dir(dir.path()).build();
+        let (public_values, _) =
+            prover.execute(test_artifacts::VEC_OF_SLICES_ELF, &stdin, context()).unwrap();
+        assert!(dir.path().join("manifest.bin").exists());
+
+        let core_proof = prover.prove_core(&pk, &stdin, opts, context()).unwrap();
+        assert_eq!(core_proof.public_values.as_slice(), public_values.as_slice());
+        prover.verify(&core_proof.proof, &vk).unwrap();
+
+        let mut other_stdin = SP1Stdin::new();
+        other_stdin.write_vec_of_slices(&[b"another", b"input"]);
+        let err = prover.prove_core(&pk, &other_stdin, opts, context()).unwrap_err();
+        assert!(matches!(err, SP1CoreProverError::CheckpointMismatch("stdin")));
+
+        let (other_pk, _) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let err = prover.prove_core(&other_pk, &stdin, opts, context()).unwrap_err();
+        assert!(matches!(err, SP1CoreProverError::CheckpointMismatch("program")));
+    }
+
+    /// A context that overrides a precompile is rejected before anything is executed.
+    #[test]
+    #[serial]
+    fn test_prove_core_rejects_precompile_overrides() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let (pk, _) = prover.setup(test_artifacts::KECCAK_PERMUTE_ELF);
+        let context = SP1Context::builder()
+            .with_precompile_override(SyscallCode::KECCAK_PERMUTE, |_| vec![0; 200])
+            .build();
+        let err = prover
+            .prove_core(&pk, &SP1Stdin::new(), SP1ProverOpts::default(), context)
+            .unwrap_err();
+        assert!(matches!(err, SP1CoreProverError::PrecompileOverrides));
+    }
+
+    /// Proving from a checkpoint set with a missing or undecodable checkpoint returns an error
+    /// rather than executing or panicking.
+    #[test]
+    #[serial]
+    fn test_prove_core_from_broken_checkpoints() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, _) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let stdin = SP1Stdin::new();
+
+        let dir = tempfile::tempdir().unwrap();
+        let context = || SP1Context::builder().with_checkpoint_dir(dir.path()).build();
+        prover.execute(test_artifacts::FIBONACCI_ELF, &stdin, context()).unwrap();
+        let checkpoint = dir.path().join("checkpoint-0.bin");
+
+        std::fs::write(&checkpoint, b"not a checkpoint").unwrap();
+        let err = prover.prove_core(&pk, &stdin, opts, context()).unwrap_err();
+        assert!(matches!(err, SP1CoreProverError::SerializationError(_)));
+
+        std::fs::remove_file(&checkpoint).unwrap();
+        let err = prover.prove_core(&pk, &stdin, opts, context()).unwrap_err();
+        assert!(matches!(err, SP1CoreProverError::IoError(_)));
+    }
+
+    /// Verifies a compressed proof with only a compress machine, without going through the prover.
+    #[test]
+    #[serial]
+    fn test_verify_compressed_proof_standalone() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let core_proof =
+            prover.prove_core(&pk, &SP1Stdin::default(), opts, SP1Context::default()).unwrap();
+        let compressed_proof = prover.compress(&vk, core_proof, vec![], opts).unwrap();
+        drop(prover);
+
+        let machine = CompressAir::compress_machine(InnerSC::default());
+        let mut challenger = machine.config().challenger();
+        verify::verify_compressed_proof(&compressed_proof, &vk, None, &machine, &mut challenger)
+            .unwrap();
+
+        // A recursion vk outside the allowed vk map is rejected.
+        let mut challenger = machine.config().challenger();
+        let err = verify::verify_compressed_proof(
+            &compressed_proof,
+            &vk,
+            Some(&BTreeMap::new()),
+            &machine,
+            &mut challenger,
+        )
+        .unwrap_err();
+        assert!(matches!(err, sp1_stark::MachineVerificationError::InvalidVerificationKey));
+    }
+
+    /// Defers proofs of two programs with different vks and compresses them together.
+    #[test]
+    #[serial]
+    fn test_compress_deferred_proofs_with_distinct_vks() -> Result<()> {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+
+        let (fibonacci_pk, fibonacci_vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let fibonacci_proof =
+            prover.prove_core(&fibonacci_pk, &SP1Stdin::new(), opts, Default::default())?;
+        let fibonacci_pv = fibonacci_proof.public_values.to_vec();
+        let fibonacci_reduce =
+            prover.compress(&fibonacci_vk, fibonacci_proof, vec![], opts)?.into_inner();
+
+        let (keccak_pk, keccak_vk) = prover.setup(test_artifacts::KECCAK256_ELF);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&1usize);
+        stdin.write(&vec![0u8, 1, 2]);
+        let keccak_proof = prover.prove_core(&keccak_pk, &stdin, opts, Default::default())?;
+        let keccak_pv = keccak_proof.public_values.to_vec();
+        let keccak_reduce = prover.compress(&keccak_vk, keccak_proof, vec![], opts)?.into_inner();
+
+        let deferred_proofs = vec![fibonacci_reduce, keccak_reduce];
+        let digests = SP1Prover::<CpuProverComponents>::deferred_proof_digests(&deferred_proofs);
+        assert_ne!(digests[0].0, digests[1].0);
+
+        // Verify both proofs, each under its own vkey, in a single program.
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&vec![
+            (fibonacci_vk.hash_u32(), fibonacci_pv),
+            (keccak_vk.hash_u32(), keccak_pv),
+        ]);
+        stdin.write_proof(deferred_proofs[0].clone(), fibonacci_vk.vk.clone());
+        stdin.write_proof(deferred_proofs[1].clone(), keccak_vk.vk.clone());
+
+        let (verify_pk, verify_vk) = prover.setup(test_artifacts::VERIFY_MULTI_VKEY_ELF);
+        let verify_proof = prover.prove_core(&verify_pk, &stdin, opts, Default::default())?;
+        let verify_reduce =
+            prover.compress(&verify_vk, verify_proof, deferred_proofs.clone(), opts)?;
+        prover.verify_compressed(&verify_reduce, &verify_vk)?;
+
+        let reduce_pv: &RecursionPublicValues<_> =
+            verify_reduce.proof.public_values.as_slice().borrow();
+        assert!(SP1Prover::<CpuProverComponents>::verify_deferred_digest_chain(
+            &deferred_proofs,
+            reduce_pv.deferred_proofs_digest
+        ));
+        Ok(())
+    }
+
+    /// Proves a multi-shard program to disk, then verifies and compresses it from the shard proofs
+    /// on disk.
+    #[test]
+    #[serial]
+    fn test_prove_core_to_file() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let lazy_proof = prover
+            .prove_core_to_file(&pk, &SP1Stdin::new(), opts, SP1Context::default(), file.path())
+            .unwrap();
+        // The memory initialization and finalization events are proven in their own shards.
+        assert!(lazy_proof.len() > 1);
+        prover.verify_lazy(&lazy_proof, &vk).unwrap();
+
+        // The file is in the format of `SP1CoreProof::save`.
+        let core_proof = SP1CoreProof::load(file.path()).unwrap();
+        assert_eq!(core_proof.proof.0.len(), lazy_proof.len());
+        assert_eq!(core_proof.cycles, lazy_proof.cycles);
+        let reopened = SP1CoreProof::open_lazy(file.path()).unwrap();
+        assert_eq!(reopened.len(), lazy_proof.len());
+
+        let compressed_proof = prover.compress_lazy(&vk, &lazy_proof, vec![], opts).unwrap();
+        prover.verify_compressed(&compressed_proof, &vk).unwrap();
+    }
+
+    /// Checks that a shard proof that can no longer be read fails compression with an error rather
+    /// than a panic.
+    #[test]
+    #[serial]
+    fn test_compress_lazy_read_error() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let lazy_proof = prover
+            .prove_core_to_file(&pk, &SP1Stdin::new(), opts, SP1Context::default(), file.path())
+            .unwrap();
+        std::fs::remove_file(file.path()).unwrap();
+
+        let result = prover.compress_lazy(&vk, &lazy_proof, vec![], opts);
+        assert!(matches!(result, Err(SP1RecursionProverError::ReadShardProof(_))));
+    }
+
+    /// Checks that a core proof saved with [SP1CoreProof::save] and opened with
+    /// [SP1CoreProof::open_lazy] decodes to the saved proof and verifies one shard at a time.
+    #[test]
+    fn test_open_lazy_core_proof() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let core_proof = prover
+            .prove_core(&pk, &SP1Stdin::default(), SP1ProverOpts::default(), SP1Context::default())
+            .unwrap();
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        core_proof.save(file.path()).unwrap();
+
+        let lazy_proof = SP1CoreProof::open_lazy(file.path()).unwrap();
+        assert_eq!(lazy_proof.len(), core_proof.proof.0.len());
+        assert_eq!(lazy_proof.cycles, core_proof.cycles);
+        assert_eq!(lazy_proof.public_values.as_slice(), core_proof.public_values.as_slice());
+        assert!(lazy_proof.shard(lazy_proof.len()).is_err());
+        for (shard, expected) in lazy_proof.shards().zip(core_proof.proof.0.iter()) {
+            assert_eq!(
+                bincode::serialize(&shard.unwrap()).unwrap(),
+                bincode::serialize(expected).unwrap()
+            );
+        }
+        prover.verify_lazy(&lazy_proof, &vk).unwrap();
+    }
+
+    /// Checks that an execution past the `warn_cycles` threshold but below `max_cycles` logs the
+    /// high cycle count warning once and still completes.
+    #[test]
+    fn test_execute_warn_cycles() {
+        #[derive(Clone, Default)]
+        struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for LogBuffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let logs = LogBuffer::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .finish();
+        let context = SP1Context::builder().warn_cycles(100).max_cycles(1_000_000).build();
+        let (_, report) = tracing::subscriber::with_default(subscriber, || {
+            prover.execute(test_artifacts::FIBONACCI_ELF, &SP1Stdin::default(), context)
+        })
+        .unwrap();
+        assert!(report.total_instruction_count() > 100);
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(logs.matches("high cycle count").count(), 1);
+    }
+
+    #[test]
+    fn test_compress_layer_sizes() {
+        assert_eq!(compress_layer_sizes(1), [1]);
+        assert_eq!(compress_layer_sizes(2), [2, 1]);
+        assert_eq!(compress_layer_sizes(3), [3, 2, 1]);
+        assert_eq!(compress_layer_sizes(4), [4, 2, 1]);
+        assert_eq!(compress_layer_sizes(5), [5, 3, 2, 1]);
+    }
+
+    /// Checks that [SP1Prover::compress_with_progress] reports every proof of every layer of the
+    /// recursion tree, in order, ending with the root.
+    #[test]
+    #[serial]
+    fn test_compress_with_progress() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let core_proof =
+            prover.prove_core(&pk, &SP1Stdin::default(), opts, SP1Context::default()).unwrap();
+        let num_shards = core_proof.proof.0.len();
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let progress: CompressProgressFn = {
+            let reports = Arc::clone(&reports);
+            Arc::new(move |report| reports.lock().unwrap().push(report))
+        };
+        let compressed_proof =
+            prover.compress_with_progress(&vk, core_proof, vec![], opts, Some(progress)).unwrap();
+        prover.verify_compressed(&compressed_proof, &vk).unwrap();
+
+        let reports = reports.lock().unwrap();
+        let layer_sizes = compress_layer_sizes(num_shards);
+        let expected = layer_sizes
+            .iter()
+            .enumerate()
+            .flat_map(|(layer, &total_in_layer)| {
+                (1..=total_in_layer).map(move |completed_in_layer| CompressProgress {
+                    layer,
+                    completed_in_layer,
+                    total_in_layer,
+                })
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(reports.len(), layer_sizes.iter().sum::<usize>());
+        assert_eq!(*reports, expected);
+    }
+
+    /// Proves the verify program over `num_deferred` copies of a compressed fibonacci proof, so
+    /// that compressing it has a wide first layer. Returns the core proof, its vk and the deferred
+    /// proofs.
+    fn prove_verify_program_with_deferred_proofs(
+        prover: &SP1Prover<CpuProverComponents>,
+        opts: SP1ProverOpts,
+        num_deferred: usize,
+    ) -> (SP1CoreProof, SP1VerifyingKey, Vec<SP1ReduceProof<InnerSC>>) {
+        let (fibonacci_pk, fibonacci_vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let fibonacci_proof = prover
+            .prove_core(&fibonacci_pk, &SP1Stdin::default(), opts, SP1Context::default())
+            .unwrap();
+        let fibonacci_pv = fibonacci_proof.public_values.as_slice().to_vec();
+        let deferred_proof =
+            prover.compress(&fibonacci_vk, fibonacci_proof, vec![], opts).unwrap().into_inner();
+
+        let (verify_pk, verify_vk) = prover.setup(test_artifacts::VERIFY_PROOF_ELF);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&fibonacci_vk.hash_u32());
+        stdin.write(&vec![fibonacci_pv; num_deferred]);
+        for _ in 0..num_deferred {
+            stdin.write_proof(deferred_proof.clone(), fibonacci_vk.vk.clone());
+        }
+        let core_proof =
+            prover.prove_core(&verify_pk, &stdin, opts, SP1Context::default()).unwrap();
+        (core_proof, verify_vk, vec![deferred_proof; num_deferred])
+    }
+
+    /// Returns the maximum of each depth reported to the returned callback.
+    fn max_queue_depths() -> (Arc<Mutex<CompressQueueDepths>>, CompressQueueDepthsFn) {
+        let max_depths = Arc::new(Mutex::new(CompressQueueDepths::default()));
+        let queue_depths: CompressQueueDepthsFn = {
+            let max_depths = Arc::clone(&max_depths);
+            Arc::new(move |depths: CompressQueueDepths| {
+                let mut max_depths = max_depths.lock().unwrap();
+                max_depths.inputs = max_depths.inputs.max(depths.inputs);
+                max_depths.records_and_traces =
+                    max_depths.records_and_traces.max(depths.records_and_traces);
+                max_depths.proofs = max_depths.proofs.max(depths.proofs);
+                max_depths.layers_in_flight =
+                    max_depths.layers_in_flight.max(depths.layers_in_flight);
+            })
+        };
+        (max_depths, queue_depths)
+    }
+
+    /// Checks that [SP1Prover::compress_with_queue_depths] reports a backed up records and traces
+    /// channel when the proving pool is much smaller than the trace generation pool.
+    #[test]
+    #[serial]
+    fn test_compress_queue_depths() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+
+        // Defer a few proofs, so that the first layer has enough inputs for the trace workers to
+        // outpace the single prover.
+        let (core_proof, verify_vk, deferred_proofs) =
+            prove_verify_program_with_deferred_proofs(&prover, opts, 4);
+
+        let mut undersized = opts;
+        undersized.recursion_opts.records_and_traces_channel_capacity = 1;
+        undersized.recursion_opts.recursion_trace_workers = 4;
+        undersized.recursion_opts.recursion_prove_workers = 1;
+
+        let (max_depths, queue_depths) = max_queue_depths();
+        let compressed_proof = prover
+            .compress_with_queue_depths(
+                &verify_vk,
+                core_proof,
+                deferred_proofs,
+                undersized,
+                Some(queue_depths),
+            )
+            .unwrap();
+        prover.verify_compressed(&compressed_proof, &verify_vk).unwrap();
+
+        // Trace workers blocked on the full channel count towards its depth.
+        let max_depths = *max_depths.lock().unwrap();
+        assert!(
+            max_depths.records_and_traces
+                > undersized.recursion_opts.records_and_traces_channel_capacity,
+            "{max_depths:?}"
+        );
+    }
+
+    /// Checks that [CompressSchedule::BreadthFirst] keeps the records of a single layer in flight,
+    /// where the pipelined schedule starts the next layer while the previous one is still being
+    /// proven, and that [CompressSchedule::Unrecognized] is rejected.
+    #[test]
+    #[serial]
+    fn test_compress_breadth_first() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let mut opts = SP1ProverOpts::default();
+        let (core_proof, verify_vk, deferred_proofs) =
+            prove_verify_program_with_deferred_proofs(&prover, opts, 4);
+
+        // With a single prover, the trace workers get to the next layer's inputs long before the
+        // previous layer is proven.
+        opts.recursion_opts.recursion_trace_workers = 4;
+        opts.recursion_opts.recursion_prove_workers = 1;
+        let mut layers_in_flight = Vec::new();
+        for schedule in [CompressSchedule::Pipelined, CompressSchedule::BreadthFirst] {
+            opts.recursion_opts.compress_schedule = schedule;
+            let (max_depths, queue_depths) = max_queue_depths();
+            let compressed_proof = prover
+                .compress_with_queue_depths(
+                    &verify_vk,
+                    core_proof.clone(),
+                    deferred_proofs.clone(),
+                    opts,
+                    Some(queue_depths),
+                )
+                .unwrap();
+            prover.verify_compressed(&compressed_proof, &verify_vk).unwrap();
+            layers_in_flight.push(max_depths.lock().unwrap().layers_in_flight);
+        }
+        assert!(layers_in_flight[0] > 1, "{layers_in_flight:?}");
+        assert_eq!(layers_in_flight[1], 1);
+
+        opts.recursion_opts.compress_schedule = CompressSchedule::Unrecognized;
+        let result = prover.compress(&verify_vk, core_proof, deferred_proofs, opts);
+        assert!(matches!(result, Err(SP1RecursionProverError::InvalidOpts(_))));
+    }
+
+    /// Passing a compressed proof to `wrap_bn254` without shrinking it first is rejected up front.
+    #[test]
+    #[serial]
+    fn test_wrap_bn254_rejects_unshrunk_proof() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let core_proof =
+            prover.prove_core(&pk, &SP1Stdin::default(), opts, SP1Context::default()).unwrap();
+        let compressed_proof = prover.compress(&vk, core_proof, vec![], opts).unwrap();
+
+        // Only a mislabeled proof gets past the types to the shape check.
+        let mislabeled = ShrunkProof(compressed_proof.clone().into_inner());
+        let err = prover.wrap_bn254(mislabeled, opts).unwrap_err();
+        assert!(matches!(err, SP1RecursionProverError::InvalidShape(_)));
+
+        let shrink_proof = prover.shrink(compressed_proof, opts).unwrap();
+        shrink_proof.assert_shrink_shape().unwrap();
+    }
+
+    /// The wrap vk computed by a fresh prover is the vk of an actual wrapped proof.
+    #[test]
+    #[serial]
+    fn test_wrap_verifying_key() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let wrap_vk = prover.wrap_verifying_key().clone();
+
+        let fresh_prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = fresh_prover.setup(test_artifacts::FIBONACCI_ELF);
+        let core_proof = fresh_prover
+            .prove_core(&pk, &SP1Stdin::default(), opts, SP1Context::default())
+            .unwrap();
+        let compressed_proof = fresh_prover.compress(&vk, core_proof, vec![], opts).unwrap();
+        let shrink_proof = fresh_prover.shrink(compressed_proof, opts).unwrap();
+        let wrapped_proof = fresh_prover.wrap_bn254(shrink_proof, opts).unwrap();
+
+        assert_eq!(
+            bincode::serialize(&wrap_vk).unwrap(),
+            bincode::serialize(&wrapped_proof.vk).unwrap()
+        );
+        assert_eq!(
+            bincode::serialize(fresh_prover.wrap_verifying_key()).unwrap(),
+            bincode::serialize(&wrapped_proof.vk).unwrap()
+        );
+    }
+
+    /// Checks that [SP1Prover::wrap_plonk_bn254] rejects a proof whose vk is not the wrap vk
+    /// before running the PLONK prover.
+    #[test]
+    #[serial]
+    fn test_wrap_plonk_bn254_rejects_wrong_vk() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let core_proof =
+            prover.prove_core(&pk, &SP1Stdin::default(), opts, SP1Context::default()).unwrap();
+        let compressed_proof = prover.compress(&vk, core_proof, vec![], opts).unwrap();
+        let shrink_proof = prover.shrink(compressed_proof, opts).unwrap();
+        let mut wrapped_proof = prover.wrap_bn254(shrink_proof, opts).unwrap();
+
+        let mut commit: [_; 1] = wrapped_proof.vk.commit.into();
+        commit[0] += p3_bn254_fr::Bn254Fr::one();
+        wrapped_proof.vk.commit = commit.into();
+
+        let build_dir = tempfile::tempdir().unwrap();
+        let err = prover.wrap_plonk_bn254(wrapped_proof, build_dir.path()).unwrap_err();
+        assert!(matches!(err, SP1RecursionProverError::WrapVkMismatch));
+    }
+
+    /// Checks that one [LoadedPlonkArtifacts] handle can prove the witnesses of two different
+    /// programs, and that both proofs verify.
+    #[test]
+    #[serial]
+    fn test_wrap_plonk_bn254_with_loaded() -> Result<()> {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+
+        let mut keccak_stdin = SP1Stdin::new();
+        keccak_stdin.write(&1usize);
+        keccak_stdin.write(&vec![0u8, 1, 2]);
+
+        let mut wrapped = Vec::new();
+        for (elf, stdin) in [
+            (test_artifacts::FIBONACCI_ELF, SP1Stdin::default()),
+            (test_artifacts::KECCAK256_ELF, keccak_stdin),
+        ] {
+            let (pk, vk) = prover.setup(elf);
+            let core_proof = prover.prove_core(&pk, &stdin, opts, SP1Context::default())?;
+            let public_values = core_proof.public_values.clone();
+            let compressed_proof = prover.compress(&vk, core_proof, vec![], opts)?;
+            let shrink_proof = prover.shrink(compressed_proof, opts)?;
+            let wrapped_proof = prover.wrap_bn254(shrink_proof, opts)?;
+            wrapped.push((vk, public_values, wrapped_proof));
+        }
+
+        let (_, _, template) = &wrapped[0];
+        let artifacts_dir = try_build_plonk_bn254_artifacts_dev(&template.vk, &template.proof);
+        let artifacts = LoadedPlonkArtifacts::load(&artifacts_dir);
+
+        for (vk, public_values, wrapped_proof) in wrapped {
+            let plonk_proof = prover.wrap_plonk_bn254_with_loaded(wrapped_proof, &artifacts)?;
+            prover.verify_plonk_bn254(&plonk_proof, &vk, &public_values, &artifacts_dir)?;
+        }
+
+        Ok(())
+    }
+
+    /// Proves and compresses with a single FRI query and checks that the prover accepts its own
+    /// proofs.
+    #[test]
+    #[serial]
+    fn test_with_fri_queries() -> Result<()> {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::with_fri_queries(1);
+        assert_eq!(prover.core_prover.machine().config().pcs().fri_config().num_queries, 1);
+        assert_eq!(prover.compress_prover.machine().config().pcs().fri_config().num_queries, 1);
+        assert_eq!(prover.shrink_prover.machine().config().pcs().fri_config().num_queries, 1);
+        assert_eq!(prover.wrap_prover.machine().config().pcs().fri_config().num_queries, 1);
+
+        // The override must survive the config being cloned.
+        let config = prover.core_prover.machine().config().clone();
+        assert_eq!(config.pcs().fri_config().num_queries, 1);
+
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let core_proof =
+            prover.prove_core(&pk, &SP1Stdin::default(), opts, SP1Context::default())?;
+        prover.verify(&core_proof.proof, &vk)?;
+
+        let compressed_proof = prover.compress(&vk, core_proof, vec![], opts)?;
+        prover.verify_compressed(&compressed_proof, &vk)?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_deterministic_setup() {
         setup_logger();
@@ -1584,4 +3602,23 @@ pub mod tests {
         let pk2 = prover.setup(program).0;
         assert_eq!(pk.pk.commit, pk2.pk.commit);
     }
+
+    #[test]
+    fn test_vk_semantically_eq() {
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let (_, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+
+        // A key with an extra chip ordering entry verifies the same proofs.
+        let mut incidental = vk.clone();
+        incidental.vk.chip_ordering.insert("Unused".to_string(), usize::MAX);
+        assert!(vk.semantically_eq(&incidental));
+        assert!(incidental.semantically_eq(&vk));
+
+        let mut other_pc = vk.clone();
+        other_pc.vk.pc_start += BabyBear::one();
+        assert!(!vk.semantically_eq(&other_pc));
+
+        let (_, other_program) = prover.setup(test_artifacts::KECCAK256_ELF);
+        assert!(!vk.semantically_eq(&other_program));
+    }
 }
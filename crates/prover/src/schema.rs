@@ -0,0 +1,111 @@
+//! Schemas describing the layout of the public values committed by a program.
+
+use thiserror::Error;
+
+/// The type of a single field committed with `sp1_zkvm::io::commit` or `commit_slice`.
+///
+/// Integers use the fixed-width little-endian encoding of bincode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublicValueType {
+    /// A `bool`, encoded as a single byte that is 0 or 1.
+    Bool,
+    /// A `u8`.
+    U8,
+    /// A `u16`.
+    U16,
+    /// A `u32`.
+    U32,
+    /// A `u64`.
+    U64,
+    /// A `u128`.
+    U128,
+    /// An `i32`.
+    I32,
+    /// An `i64`.
+    I64,
+    /// A fixed number of raw bytes, such as a `[u8; N]` or a slice committed with `commit_slice`.
+    FixedBytes(usize),
+    /// A bincode-encoded `Vec<u8>`: a little-endian `u64` length followed by that many bytes.
+    Bytes,
+}
+
+impl PublicValueType {
+    /// The encoded size of the type, or `None` if it is length-prefixed.
+    const fn fixed_size(self) -> Option<usize> {
+        match self {
+            PublicValueType::Bool | PublicValueType::U8 => Some(1),
+            PublicValueType::U16 => Some(2),
+            PublicValueType::U32 | PublicValueType::I32 => Some(4),
+            PublicValueType::U64 | PublicValueType::I64 => Some(8),
+            PublicValueType::U128 => Some(16),
+            PublicValueType::FixedBytes(len) => Some(len),
+            PublicValueType::Bytes => None,
+        }
+    }
+}
+
+/// An error returned when committed public values do not match a [`PublicValuesSchema`].
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PublicValuesSchemaError {
+    #[error("field `{field}` needs {needed} bytes but only {remaining} remain")]
+    Truncated { field: String, needed: usize, remaining: usize },
+    #[error("field `{field}` is a bool but was committed as {value}")]
+    InvalidBool { field: String, value: u8 },
+    #[error("{count} bytes were committed after the last field of the schema")]
+    TrailingBytes { count: usize },
+}
+
+/// The expected layout of a program's public values, as an ordered list of named fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PublicValuesSchema {
+    fields: Vec<(String, PublicValueType)>,
+}
+
+impl PublicValuesSchema {
+    /// Create an empty schema, which only matches empty public values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a field to the schema.
+    pub fn field(mut self, name: impl Into<String>, ty: PublicValueType) -> Self {
+        self.fields.push((name.into(), ty));
+        self
+    }
+
+    /// Check that `public_values` consists of exactly the fields of the schema, in order.
+    pub fn validate(&self, public_values: &[u8]) -> Result<(), PublicValuesSchemaError> {
+        let mut rest = public_values;
+        for (name, ty) in &self.fields {
+            let truncated = |needed| PublicValuesSchemaError::Truncated {
+                field: name.clone(),
+                needed,
+                remaining: rest.len(),
+            };
+            let size = match ty.fixed_size() {
+                Some(size) => size,
+                None => {
+                    let (len, _) = rest.split_first_chunk::<8>().ok_or_else(|| truncated(8))?;
+                    usize::try_from(u64::from_le_bytes(*len))
+                        .ok()
+                        .and_then(|len| len.checked_add(8))
+                        .unwrap_or(usize::MAX)
+                }
+            };
+            if size > rest.len() {
+                return Err(truncated(size));
+            }
+            if *ty == PublicValueType::Bool && rest[0] > 1 {
+                return Err(PublicValuesSchemaError::InvalidBool {
+                    field: name.clone(),
+                    value: rest[0],
+                });
+            }
+            rest = &rest[size..];
+        }
+        if !rest.is_empty() {
+            return Err(PublicValuesSchemaError::TrailingBytes { count: rest.len() });
+        }
+        Ok(())
+    }
+}
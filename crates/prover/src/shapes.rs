@@ -1,5 +1,6 @@
 use std::{
     collections::{BTreeMap, BTreeSet, HashSet},
+    fmt,
     fs::File,
     hash::{DefaultHasher, Hash, Hasher},
     panic::{catch_unwind, AssertUnwindSafe},
@@ -18,19 +19,44 @@ use sp1_recursion_circuit::machine::{
     SP1CompressWithVKeyWitnessValues, SP1CompressWithVkeyShape, SP1DeferredShape,
     SP1DeferredWitnessValues, SP1RecursionShape, SP1RecursionWitnessValues,
 };
-use sp1_recursion_core::{shape::RecursionShapeConfig, RecursionProgram};
+use sp1_recursion_core::{
+    shape::{RecursionShapeConfig, ShapeTooLarge},
+    RecursionProgram,
+};
 use sp1_stark::{MachineProver, ProofShape, DIGEST_SIZE};
 
-use crate::{components::SP1ProverComponents, CompressAir, HashableKey, SP1Prover};
+use crate::{components::SP1ProverComponents, CompressAir, HashableKey, SP1CoreProof, SP1Prover};
 
+/// The shape of a proof verified by one of the recursion programs, which determines the program
+/// and therefore its verifying key.
+///
+/// The shapes hit by a workload can be inventoried with [`SP1Prover::collect_shapes`].
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum SP1ProofShape {
+    /// A core shard proof, verified by the recursion program.
     Recursion(ProofShape),
+    /// The batch of compress proofs verified by a compress program.
     Compress(Vec<ProofShape>),
+    /// A deferred compress proof, verified by the deferred program.
     Deferred(ProofShape),
+    /// The compress proof verified by the shrink program.
     Shrink(ProofShape),
 }
 
+impl fmt::Display for SP1ProofShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SP1ProofShape::Recursion(shape) => write!(f, "Recursion {shape}"),
+            SP1ProofShape::Compress(shapes) => {
+                writeln!(f, "Compress of {} proofs:", shapes.len())?;
+                shapes.iter().try_for_each(|shape| write!(f, "{shape}"))
+            }
+            SP1ProofShape::Deferred(shape) => write!(f, "Deferred {shape}"),
+            SP1ProofShape::Shrink(shape) => write!(f, "Shrink {shape}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Hash)]
 pub enum SP1CompressProgramShape {
     Recursion(SP1RecursionShape),
@@ -115,7 +141,16 @@ pub fn build_vk_map<C: SP1ProverComponents>(
                         }));
                         let is_shrink = matches!(shape, SP1CompressProgramShape::Shrink(_));
                         match program {
-                            Ok(program) => program_tx.send((i, program, is_shrink)).unwrap(),
+                            Ok(Ok(program)) => program_tx.send((i, program, is_shrink)).unwrap(),
+                            Ok(Err(e)) => {
+                                tracing::warn!(
+                                    "Program generation failed for shape {} {:?}, with error: {}",
+                                    i,
+                                    shape,
+                                    e
+                                );
+                                panic_tx.send(i).unwrap();
+                            }
                             Err(e) => {
                                 tracing::warn!(
                                     "Program generation failed for shape {} {:?}, with error: {:?}",
@@ -285,7 +320,7 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
     pub fn program_from_shape(
         &self,
         shape: SP1CompressProgramShape,
-    ) -> Arc<RecursionProgram<BabyBear>> {
+    ) -> Result<Arc<RecursionProgram<BabyBear>>, ShapeTooLarge> {
         match shape {
             SP1CompressProgramShape::Recursion(shape) => {
                 let input = SP1RecursionWitnessValues::dummy(self.core_prover.machine(), &shape);
@@ -303,15 +338,60 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             SP1CompressProgramShape::Shrink(shape) => {
                 let input =
                     SP1CompressWithVKeyWitnessValues::dummy(self.compress_prover.machine(), &shape);
-                self.shrink_program(&input)
+                Ok(self.shrink_program(&input))
             }
         }
     }
+
+    /// The shapes of the shard proofs of `core_proof`, as verified by the recursion programs.
+    ///
+    /// The shapes only depend on the trace heights of the shards, so proving the same input again
+    /// yields the same set.
+    pub fn collect_shapes(&self, core_proof: &SP1CoreProof) -> BTreeSet<SP1ProofShape> {
+        core_proof.proof.0.iter().map(|proof| SP1ProofShape::Recursion(proof.shape())).collect()
+    }
+
+    /// Compile the recursion programs for the given shapes ahead of time and insert them into the
+    /// recursion program cache.
+    ///
+    /// The cache holds at most `PROVER_CORE_CACHE_SIZE` programs, so warming more shapes than that
+    /// evicts the least recently used ones. Stops at the first shape whose program does not fit any
+    /// allowed recursion shape.
+    pub fn warm_programs(&self, shapes: &[SP1RecursionShape]) -> Result<(), ShapeTooLarge> {
+        for shape in shapes {
+            self.program_from_shape(SP1CompressProgramShape::Recursion(shape.clone()))?;
+        }
+        Ok(())
+    }
+
+    /// Compile the compress programs for the given shapes ahead of time and insert them into the
+    /// compress program cache.
+    ///
+    /// The cache holds at most `PROVER_COMPRESS_CACHE_SIZE` programs, so warming more shapes than
+    /// that evicts the least recently used ones. Stops at the first shape whose program does not
+    /// fit any allowed recursion shape.
+    pub fn warm_compress_programs(
+        &self,
+        shapes: &[SP1CompressWithVkeyShape],
+    ) -> Result<(), ShapeTooLarge> {
+        for shape in shapes {
+            self.program_from_shape(SP1CompressProgramShape::Compress(shape.clone()))?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::Ordering;
+
+    use p3_challenger::CanObserve;
+    use sp1_core_machine::io::SP1Stdin;
+    use sp1_core_machine::utils::setup_logger;
+    use sp1_stark::{SP1ProverOpts, StarkGenericConfig};
+
     use super::*;
+    use crate::{components::CpuProverComponents, COMPRESS_DEGREE, REDUCE_BATCH_SIZE};
 
     #[test]
     #[ignore]
@@ -325,4 +405,141 @@ mod tests {
 
         println!("Number of compress shapes: {}", all_shapes.len());
     }
+
+    #[test]
+    fn test_warm_programs() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let core_proof =
+            prover.prove_core(&pk, &SP1Stdin::default(), opts, Default::default()).unwrap();
+
+        // Build the first recursion input the same way `compress` does.
+        let mut leaf_challenger = prover.core_prover.config().challenger();
+        vk.vk.observe_into(&mut leaf_challenger);
+        let shard_proofs = &core_proof.proof.0;
+        for proof in shard_proofs {
+            leaf_challenger.observe(proof.commitment.global_main_commit);
+            leaf_challenger
+                .observe_slice(&proof.public_values[0..prover.core_prover.num_pv_elts()]);
+        }
+        let inputs = prover.get_recursion_core_inputs(
+            &vk.vk,
+            &leaf_challenger,
+            shard_proofs,
+            1,
+            shard_proofs.len() == 1,
+        );
+        let input = inputs.first().unwrap();
+
+        prover.warm_programs(&[input.shape()]).unwrap();
+        let misses = prover.recursion_cache_misses.load(Ordering::Relaxed);
+        prover.recursion_program(input).unwrap();
+        assert_eq!(prover.recursion_cache_misses.load(Ordering::Relaxed), misses);
+    }
+
+    /// Computes the trace sizes of a real compress program and checks them against the shape the
+    /// program is fixed to.
+    #[test]
+    fn test_compress_program_constraint_stats() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let recursion_shape_config = prover.recursion_shape_config.as_ref().unwrap();
+        let compress_shape =
+            recursion_shape_config.get_all_shape_combinations(REDUCE_BATCH_SIZE).next().unwrap();
+        let height = prover.allowed_vk_map.len().next_power_of_two().ilog2() as usize;
+        let program = prover
+            .program_from_shape(SP1CompressProgramShape::from_proof_shape(
+                SP1ProofShape::Compress(compress_shape),
+                height,
+            ))
+            .unwrap();
+
+        let stats = program.constraint_stats::<COMPRESS_DEGREE>();
+        let shape = program.shape.as_ref().unwrap();
+        for (name, stats) in &stats {
+            tracing::info!("{name}: {stats:?}");
+            assert!(stats.rows <= stats.padded_rows, "{name} does not fit its padded trace");
+            if let Some(log_height) = shape.inner.get(name) {
+                assert_eq!(stats.padded_rows, 1 << log_height, "{name} is not padded to the shape");
+            }
+        }
+
+        // Verifying the proofs is dominated by hashing and FRI, and the public values trace always
+        // has its fixed height.
+        let rows = |name: &str| stats[name].rows;
+        assert!(rows(&format!("Poseidon2WideDeg{COMPRESS_DEGREE}")) > 0);
+        assert!(rows("BatchFRI") > 0);
+        assert!(rows("BaseAlu") > 0);
+        assert_eq!(rows("PublicValues"), 16);
+    }
+
+    #[test]
+    fn test_collect_shapes() {
+        setup_logger();
+        let prover = SP1Prover::<CpuProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, _) = prover.setup(test_artifacts::FIBONACCI_ELF);
+        let prove =
+            || prover.prove_core(&pk, &SP1Stdin::default(), opts, Default::default()).unwrap();
+
+        let shapes = prover.collect_shapes(&prove());
+        assert!(!shapes.is_empty());
+        assert!(shapes.iter().all(|shape| matches!(shape, SP1ProofShape::Recursion(_))));
+        for shape in &shapes {
+            tracing::info!("{shape}");
+        }
+        assert_eq!(prover.collect_shapes(&prove()), shapes);
+    }
+
+    #[test]
+    fn test_program_cache_dir() {
+        setup_logger();
+        let dir = tempfile::tempdir().unwrap();
+        let core_shape_config = CoreShapeConfig::default();
+        let recursion_shape_config = RecursionShapeConfig::default();
+        let recursion_shape =
+            SP1ProofShape::generate(&core_shape_config, &recursion_shape_config, 1).next().unwrap();
+        let compress_shape =
+            SP1ProofShape::generate_compress_shapes(&recursion_shape_config, 1).next().unwrap();
+
+        let compile_all = |prover: &SP1Prover<CpuProverComponents>| {
+            [recursion_shape.clone(), compress_shape.clone()]
+                .map(|shape| {
+                    let shape = SP1CompressProgramShape::from_proof_shape(
+                        shape,
+                        prover.vk_merkle_tree.height,
+                    );
+                    bincode::serialize(&*prover.program_from_shape(shape).unwrap()).unwrap()
+                })
+                .to_vec()
+        };
+
+        let prover = SP1Prover::<CpuProverComponents>::new().with_program_cache_dir(dir.path());
+        let compiled = compile_all(&prover);
+        assert_eq!(prover.program_cache_disk_hits.load(Ordering::Relaxed), 0);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+        drop(prover);
+
+        let prover = SP1Prover::<CpuProverComponents>::new().with_program_cache_dir(dir.path());
+        let loaded = compile_all(&prover);
+        assert_eq!(prover.program_cache_disk_hits.load(Ordering::Relaxed), 2);
+        assert_eq!(loaded, compiled);
+        drop(prover);
+
+        // Provers whose programs differ for the same shapes do not share the cached programs.
+        let mut prover = SP1Prover::<CpuProverComponents>::new().with_program_cache_dir(dir.path());
+        prover.vk_verification = !prover.vk_verification;
+        compile_all(&prover);
+        assert_eq!(prover.program_cache_disk_hits.load(Ordering::Relaxed), 0);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 4);
+        drop(prover);
+
+        let prover = SP1Prover::<CpuProverComponents>::with_fri_queries(1)
+            .with_program_cache_dir(dir.path());
+        compile_all(&prover);
+        assert_eq!(prover.program_cache_disk_hits.load(Ordering::Relaxed), 0);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 6);
+    }
 }
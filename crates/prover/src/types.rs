@@ -1,4 +1,11 @@
-use std::{fs::File, path::Path};
+use std::{
+    borrow::Borrow,
+    fs::File,
+    io::{BufReader, Seek, SeekFrom},
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use anyhow::Result;
 use clap::ValueEnum;
@@ -7,21 +14,26 @@ use p3_bn254_fr::Bn254Fr;
 use p3_commit::{Pcs, TwoAdicMultiplicativeCoset};
 use p3_field::{AbstractField, PrimeField, PrimeField32, TwoAdicField};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use sp1_core_machine::{io::SP1Stdin, reduce::SP1ReduceProof};
+use sp1_core_executor::ExecutionError;
+use sp1_core_machine::{io::SP1Stdin, reduce::SP1ReduceProof, riscv::CoreShapeError};
 use sp1_primitives::{io::SP1PublicValues, poseidon2_hash};
 
 use sp1_recursion_circuit::machine::{
     SP1CompressWitnessValues, SP1DeferredWitnessValues, SP1RecursionWitnessValues,
 };
 
+use sp1_recursion_core::{air::RecursionPublicValues, shape::ShapeTooLarge};
 use sp1_recursion_gnark_ffi::proof::{Groth16Bn254Proof, PlonkBn254Proof};
 
-use sp1_stark::{ShardProof, StarkGenericConfig, StarkProvingKey, StarkVerifyingKey, DIGEST_SIZE};
+use sp1_stark::{
+    ProofShape, ShardProof, StarkGenericConfig, StarkProvingKey, StarkVerifyingKey, DIGEST_SIZE,
+};
 use thiserror::Error;
 
 use crate::{
-    utils::{babybears_to_bn254, words_to_bytes_be},
-    CoreSC, InnerSC,
+    schema::PublicValuesSchemaError,
+    utils::{self, babybears_to_bn254, words_to_bytes_be},
+    CoreSC, InnerSC, OuterSC, ShrinkAir,
 };
 
 /// The information necessary to generate a proof for a given RISC-V program.
@@ -39,6 +51,34 @@ pub struct SP1VerifyingKey {
     pub vk: StarkVerifyingKey<CoreSC>,
 }
 
+impl SP1VerifyingKey {
+    /// Whether both keys verify the same proofs: they have the same preprocessed commitment, start
+    /// pc and preprocessed chips, with the same domains and trace dimensions.
+    ///
+    /// The chip ordering map, which is derived from the chips, is ignored.
+    pub fn semantically_eq(&self, other: &Self) -> bool {
+        let (vk, other) = (&self.vk, &other.vk);
+        vk.commit == other.commit
+            && vk.pc_start == other.pc_start
+            && vk.chip_information.len() == other.chip_information.len()
+            && vk.chip_information.iter().zip(&other.chip_information).all(
+                |((name, domain, dims), (other_name, other_domain, other_dims))| {
+                    name == other_name
+                        && domain.log_n == other_domain.log_n
+                        && domain.shift == other_domain.shift
+                        && dims.width == other_dims.width
+                        && dims.height == other_dims.height
+                },
+            )
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    /// The number of times a verifying key digest was computed on this thread.
+    pub(crate) static VK_HASHES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
 /// A trait for keys that can be hashed into a digest.
 pub trait HashableKey {
     /// Hash the key into a digest of BabyBear elements.
@@ -107,6 +147,8 @@ where
             inputs.push(g);
         }
 
+        #[cfg(test)]
+        VK_HASHES.with(|hashes| hashes.set(hashes.get() + 1));
         poseidon2_hash(inputs)
     }
 
@@ -165,9 +207,98 @@ pub type SP1Groth16Bn254Proof = SP1ProofWithMetadata<SP1Groth16Bn254ProofData>;
 /// An SP1 proof that has been wrapped into a single proof and can be verified onchain.
 pub type SP1Proof = SP1ProofWithMetadata<SP1Bn254ProofData>;
 
+impl SP1CoreProof {
+    /// Opens a core proof written by [SP1ProofWithMetadata::save] without loading all of its shard
+    /// proofs.
+    ///
+    /// The file is scanned once to index the shard proofs, decoding and dropping them one at a
+    /// time, so at most one shard proof is held in memory. The metadata that follows the shard
+    /// proofs is decoded eagerly.
+    pub fn open_lazy(path: impl AsRef<Path>) -> bincode::Result<LazySP1CoreProof> {
+        let path = path.as_ref().to_path_buf();
+        let mut reader = BufReader::new(File::open(&path)?);
+        let num_shards: u64 = bincode::deserialize_from(&mut reader)?;
+        let mut offsets = Vec::new();
+        for _ in 0..num_shards {
+            offsets.push(reader.stream_position()?);
+            let _: ShardProof<CoreSC> = bincode::deserialize_from(&mut reader)?;
+        }
+        let stdin = bincode::deserialize_from(&mut reader)?;
+        let public_values = bincode::deserialize_from(&mut reader)?;
+        let cycles = bincode::deserialize_from(&mut reader)?;
+        Ok(LazySP1CoreProof { path, offsets, stdin, public_values, cycles })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SP1CoreProofData(pub Vec<ShardProof<CoreSC>>);
 
+/// A saved [SP1CoreProof] whose shard proofs are decoded on access.
+#[derive(Clone)]
+pub struct LazySP1CoreProof {
+    path: PathBuf,
+    /// The position of each shard proof in the file.
+    offsets: Vec<u64>,
+    pub stdin: SP1Stdin,
+    pub public_values: SP1PublicValues,
+    pub cycles: u64,
+}
+
+impl LazySP1CoreProof {
+    pub(crate) fn new(
+        path: PathBuf,
+        offsets: Vec<u64>,
+        stdin: SP1Stdin,
+        public_values: SP1PublicValues,
+        cycles: u64,
+    ) -> Self {
+        Self { path, offsets, stdin, public_values, cycles }
+    }
+
+    /// The path of the saved proof.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The number of shard proofs.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Whether there are no shard proofs.
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Decodes the shard proof at `index`, or returns an error if there is no such shard.
+    pub fn shard(&self, index: usize) -> bincode::Result<ShardProof<CoreSC>> {
+        let offset = *self.offsets.get(index).ok_or_else(|| {
+            bincode::ErrorKind::Custom(format!(
+                "shard index {index} out of range for a proof with {} shards",
+                self.len()
+            ))
+        })?;
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        reader.seek(SeekFrom::Start(offset))?;
+        bincode::deserialize_from(reader)
+    }
+
+    /// Decodes the shard proofs in order, one at a time.
+    pub fn shards(&self) -> impl Iterator<Item = bincode::Result<ShardProof<CoreSC>>> + '_ {
+        (0..self.len()).map(|index| self.shard(index))
+    }
+
+    /// Decodes all the shard proofs into an in-memory [SP1CoreProof].
+    pub fn load(&self) -> bincode::Result<SP1CoreProof> {
+        Ok(SP1CoreProof {
+            proof: self.shards().collect::<bincode::Result<_>>().map(SP1CoreProofData)?,
+            stdin: self.stdin.clone(),
+            public_values: self.public_values.clone(),
+            cycles: self.cycles,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SP1ReducedProofData(pub ShardProof<InnerSC>);
 
@@ -231,10 +362,249 @@ pub enum SP1ReduceProofWrapper {
     Recursive(SP1ReduceProof<InnerSC>),
 }
 
+/// The progress of [`crate::SP1Prover::compress_with_progress`], reported each time a proof of the
+/// recursion tree is done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressProgress {
+    /// The layer of the finished proof, where layer 0 proves the core and deferred proofs and the
+    /// last layer is the root.
+    pub layer: usize,
+    /// The number of finished proofs in this layer, including this one.
+    pub completed_in_layer: usize,
+    /// The total number of proofs in this layer.
+    pub total_in_layer: usize,
+}
+
+/// A callback receiving [`CompressProgress`] updates.
+pub type CompressProgressFn = Arc<dyn Fn(CompressProgress) + Send + Sync>;
+
+/// The number of items queued on each channel of the recursion pipeline of
+/// [`crate::SP1Prover::compress_with_queue_depths`], reported each time one of them changes.
+///
+/// An item is counted from the moment a worker starts sending it, so a depth above the capacity of
+/// its channel means that workers are blocked on it and that the pool draining it is too small.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompressQueueDepths {
+    /// The inputs waiting for a worker to generate their records and traces.
+    pub inputs: usize,
+    /// The records and traces waiting for a worker to prove them.
+    pub records_and_traces: usize,
+    /// The proofs waiting to be batched into the inputs of the next layer.
+    pub proofs: usize,
+    /// The layers of the recursion tree with records and traces that were generated but not
+    /// proven yet, wherever they are queued.
+    pub layers_in_flight: usize,
+}
+
+/// A callback receiving [`CompressQueueDepths`] updates.
+pub type CompressQueueDepthsFn = Arc<dyn Fn(CompressQueueDepths) + Send + Sync>;
+
 #[derive(Error, Debug)]
 pub enum SP1RecursionProverError {
     #[error("Runtime error: {0}")]
     RuntimeError(String),
+    #[error("Compression was cancelled")]
+    Cancelled,
+    #[error("Invalid recursion options: {0}")]
+    InvalidOpts(&'static str),
+    #[error("Invalid proof shape: {0}")]
+    InvalidShape(#[from] ShapeError),
+    #[error("The proof's vk does not match the wrap vk, so it was not produced by wrap_bn254")]
+    WrapVkMismatch,
+    #[error("Failed to read a shard proof: {0}")]
+    ReadShardProof(bincode::Error),
+    #[error("A recursion program does not fit any allowed shape: {0}")]
+    ShapeTooLarge(#[from] ShapeTooLarge),
+    #[error("Deferred proof {index} has an all-zero {field}, so its public values were never set")]
+    UninitializedDeferredProof { index: usize, field: &'static str },
+}
+
+/// A proof whose shape differs from the one expected by the prover stage it was passed to.
+#[derive(Error, Debug)]
+#[error("expected a proof of shape {expected}, found {found}")]
+pub struct ShapeError {
+    pub expected: ProofShape,
+    pub found: ProofShape,
+}
+
+/// An error preparing an ELF for proving with [`crate::SP1Prover::try_setup`].
+#[derive(Error, Debug)]
+pub enum ProgramError {
+    #[error("Failed to parse ELF: {0}")]
+    Parse(eyre::Report),
+    #[error("Failed to fix program shape: {0}")]
+    Shape(#[from] CoreShapeError),
+}
+
+/// An error executing a program with [`crate::SP1Prover::execute_with_schema`].
+#[derive(Error, Debug)]
+pub enum ExecuteWithSchemaError {
+    #[error("Execution failed: {0}")]
+    Execution(#[from] ExecutionError),
+    #[error("Public values do not match the schema: {0}")]
+    Schema(#[from] PublicValuesSchemaError),
+}
+
+#[derive(Error, Debug)]
+pub enum SP1StdinError {
+    #[error(
+        "the proof's vk digest {found:?} does not match the digest of the provided vk {expected:?}"
+    )]
+    VkDigestMismatch { found: [u32; DIGEST_SIZE], expected: [u32; DIGEST_SIZE] },
+}
+
+/// Checked writes of deferred proofs to an [SP1Stdin].
+pub trait SP1StdinProofExt {
+    /// Write a proof and its verifying key, checking that the proof was generated for `vk`.
+    ///
+    /// Unlike [SP1Stdin::write_proof], a mismatched verifying key is reported immediately rather
+    /// than during proving of the recursion program.
+    fn try_write_proof(
+        &mut self,
+        proof: SP1ReduceProof<InnerSC>,
+        vk: StarkVerifyingKey<CoreSC>,
+    ) -> Result<(), SP1StdinError>;
+}
+
+impl SP1StdinProofExt for SP1Stdin {
+    fn try_write_proof(
+        &mut self,
+        proof: SP1ReduceProof<InnerSC>,
+        vk: StarkVerifyingKey<CoreSC>,
+    ) -> Result<(), SP1StdinError> {
+        let pv: &RecursionPublicValues<BabyBear> = proof.proof.public_values.as_slice().borrow();
+        let found = pv.sp1_vk_digest.map(|x| x.as_canonical_u32());
+        let expected = vk.hash_u32();
+        if found != expected {
+            return Err(SP1StdinError::VkDigestMismatch { found, expected });
+        }
+        self.write_proof(proof, vk);
+        Ok(())
+    }
+}
+
+/// The digests a wrapped proof exposes as the public inputs of the PLONK and Groth16 circuits.
+///
+/// The bn254 digests are the field elements expected by the on-chain verifiers, in the order
+/// `[vkey digest, committed values digest]`.
+///
+/// ```rust,no_run
+/// use p3_field::PrimeField;
+/// use sp1_core_machine::io::SP1Stdin;
+/// use sp1_prover::{components::CpuProverComponents, SP1Prover, SP1WrappedProofExt};
+/// use sp1_stark::SP1ProverOpts;
+///
+/// let prover = SP1Prover::<CpuProverComponents>::new();
+/// let (pk, vk) = prover.setup(test_artifacts::FIBONACCI_ELF);
+/// let opts = SP1ProverOpts::default();
+/// let core_proof = prover.prove_core(&pk, &SP1Stdin::new(), opts, Default::default()).unwrap();
+/// let compressed_proof = prover.compress(&vk, core_proof, vec![], opts).unwrap();
+/// let shrink_proof = prover.shrink(compressed_proof, opts).unwrap();
+/// let wrapped_proof = prover.wrap_bn254(shrink_proof, opts).unwrap();
+///
+/// println!("vkey digest: {}", wrapped_proof.sp1_vkey_digest_bn254().as_canonical_biguint());
+/// println!(
+///     "committed values digest: {}",
+///     wrapped_proof.sp1_committed_values_digest_bn254().as_canonical_biguint()
+/// );
+/// ```
+pub trait SP1WrappedProofExt {
+    /// The digest of the SP1 program's verifying key.
+    fn sp1_vkey_digest_babybear(&self) -> [BabyBear; DIGEST_SIZE];
+
+    /// The digest of the SP1 program's verifying key, as the first circuit public input.
+    fn sp1_vkey_digest_bn254(&self) -> Bn254Fr;
+
+    /// The bytes of the digest of the SP1 program's committed values.
+    fn sp1_committed_values_digest_babybear(&self) -> [BabyBear; 32];
+
+    /// The digest of the SP1 program's committed values, as the second circuit public input.
+    fn sp1_committed_values_digest_bn254(&self) -> Bn254Fr;
+}
+
+impl SP1WrappedProofExt for SP1ReduceProof<OuterSC> {
+    fn sp1_vkey_digest_babybear(&self) -> [BabyBear; DIGEST_SIZE] {
+        utils::sp1_vkey_digest_babybear(self)
+    }
+
+    fn sp1_vkey_digest_bn254(&self) -> Bn254Fr {
+        utils::sp1_vkey_digest_bn254(self)
+    }
+
+    fn sp1_committed_values_digest_babybear(&self) -> [BabyBear; 32] {
+        utils::sp1_committed_values_digest_babybear(self)
+    }
+
+    fn sp1_committed_values_digest_bn254(&self) -> Bn254Fr {
+        utils::sp1_committed_values_digest_bn254(self)
+    }
+}
+
+/// Shape checks on compressed proofs before they are passed to the next prover stage.
+pub trait SP1ReduceProofShapeExt {
+    /// Check that the proof has the fixed shape produced by [`crate::SP1Prover::shrink`].
+    ///
+    /// [`crate::SP1Prover::wrap_bn254`] only accepts shrunk proofs; a compressed proof that has not
+    /// been through `shrink` fails this check.
+    fn assert_shrink_shape(&self) -> Result<(), ShapeError>;
+}
+
+impl SP1ReduceProofShapeExt for SP1ReduceProof<InnerSC> {
+    fn assert_shrink_shape(&self) -> Result<(), ShapeError> {
+        // Both shapes are collected to get the same canonical chip order.
+        let expected: ProofShape = ShrinkAir::<BabyBear>::shrink_shape().into();
+        let found: ProofShape = self.proof.shape().into_iter().collect();
+        if found != expected {
+            return Err(ShapeError { expected, found });
+        }
+        Ok(())
+    }
+}
+
+/// A proof returned by [`crate::SP1Prover::compress`], which can be shrunk with
+/// [`crate::SP1Prover::shrink`].
+///
+/// Compressed and shrunk proofs are both [`SP1ReduceProof`]s over [`InnerSC`]; the newtypes keep
+/// them apart so that a proof cannot be passed to the wrong stage. Both encode like the proof they
+/// wrap.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(transparent)]
+pub struct CompressedProof(pub SP1ReduceProof<InnerSC>);
+
+/// A proof returned by [`crate::SP1Prover::shrink`], which can be wrapped with
+/// [`crate::SP1Prover::wrap_bn254`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(transparent)]
+pub struct ShrunkProof(pub SP1ReduceProof<InnerSC>);
+
+impl CompressedProof {
+    /// Unwrap the underlying reduce proof, e.g. to defer it in another program's stdin.
+    pub fn into_inner(self) -> SP1ReduceProof<InnerSC> {
+        self.0
+    }
+}
+
+impl ShrunkProof {
+    /// Unwrap the underlying reduce proof.
+    pub fn into_inner(self) -> SP1ReduceProof<InnerSC> {
+        self.0
+    }
+}
+
+impl Deref for CompressedProof {
+    type Target = SP1ReduceProof<InnerSC>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Deref for ShrunkProof {
+    type Target = SP1ReduceProof<InnerSC>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
 }
 
 #[allow(clippy::large_enum_variant)]
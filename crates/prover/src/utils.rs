@@ -1,5 +1,6 @@
 use std::{
     borrow::Borrow,
+    collections::BTreeMap,
     fs::{self, File},
     io::Read,
     iter::{Skip, Take},
@@ -12,10 +13,11 @@ use p3_field::{AbstractField, PrimeField32};
 use p3_symmetric::CryptographicHasher;
 use sp1_core_executor::{Executor, Program};
 use sp1_core_machine::{io::SP1Stdin, reduce::SP1ReduceProof};
-use sp1_recursion_circuit::machine::RootPublicValues;
+use sp1_recursion_circuit::{machine::RootPublicValues, merkle_tree::MerkleTree};
 use sp1_recursion_core::{
     air::{RecursionPublicValues, NUM_PV_ELMS_TO_HASH},
     stark::BabyBearPoseidon2Outer,
+    DIGEST_SIZE,
 };
 use sp1_stark::{baby_bear_poseidon2::MyHash as InnerHash, SP1CoreOpts, Word};
 
@@ -33,6 +35,16 @@ pub fn sp1_vkey_digest_bn254(proof: &SP1ReduceProof<BabyBearPoseidon2Outer>) ->
     babybears_to_bn254(&sp1_vkey_digest_babybear(proof))
 }
 
+/// Commit to a set of allowed recursion vk digests, returning the `vk_root` and the Merkle tree used
+/// to open it.
+///
+/// The digests are committed in key order, as [`crate::SP1Prover`] does for its embedded vk map.
+pub fn compute_vk_root(
+    allowed: &BTreeMap<[BabyBear; DIGEST_SIZE], usize>,
+) -> ([BabyBear; DIGEST_SIZE], MerkleTree<BabyBear, InnerSC>) {
+    MerkleTree::commit(allowed.keys().copied().collect())
+}
+
 /// Compute the digest of the public values.
 pub fn recursion_public_values_digest(
     config: &InnerSC,
@@ -80,15 +92,20 @@ pub fn assert_recursion_public_values_valid(
     }
 }
 
+/// Get the committed values digest bytes, as BabyBear elements, this reduce proof is representing.
+pub fn sp1_committed_values_digest_babybear(
+    proof: &SP1ReduceProof<BabyBearPoseidon2Outer>,
+) -> [BabyBear; 32] {
+    let proof = &proof.proof;
+    let pv: &RecursionPublicValues<BabyBear> = proof.public_values.as_slice().borrow();
+    words_to_bytes(&pv.committed_value_digest).try_into().unwrap()
+}
+
 /// Get the committed values Bn Poseidon2 digest this reduce proof is representing.
 pub fn sp1_committed_values_digest_bn254(
     proof: &SP1ReduceProof<BabyBearPoseidon2Outer>,
 ) -> Bn254Fr {
-    let proof = &proof.proof;
-    let pv: &RecursionPublicValues<BabyBear> = proof.public_values.as_slice().borrow();
-    let committed_values_digest_bytes: [BabyBear; 32] =
-        words_to_bytes(&pv.committed_value_digest).try_into().unwrap();
-    babybear_bytes_to_bn254(&committed_values_digest_bytes)
+    babybear_bytes_to_bn254(&sp1_committed_values_digest_babybear(proof))
 }
 
 impl SP1CoreProofData {
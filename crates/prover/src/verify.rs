@@ -1,10 +1,14 @@
-use std::{borrow::Borrow, path::Path, str::FromStr};
+use std::{borrow::Borrow, collections::BTreeMap, path::Path, str::FromStr};
 
 use anyhow::Result;
 use num_bigint::BigUint;
 use p3_baby_bear::BabyBear;
 use p3_field::{AbstractField, PrimeField};
-use sp1_core_executor::{subproof::SubproofVerifier, SP1ReduceProof};
+use p3_maybe_rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use sp1_core_executor::{
+    subproof::{DeferredProofClaim, SubproofVerifier},
+    SP1ReduceProof,
+};
 use sp1_core_machine::cpu::MAX_CPU_LOG_DEGREE;
 use sp1_primitives::{consts::WORD_SIZE, io::SP1PublicValues};
 
@@ -16,14 +20,16 @@ use sp1_recursion_gnark_ffi::{
 use sp1_stark::{
     air::{PublicValues, POSEIDON_NUM_WORDS, PV_DIGEST_NUM_WORDS},
     baby_bear_poseidon2::BabyBearPoseidon2,
-    MachineProof, MachineProver, MachineVerificationError, StarkGenericConfig, Word,
+    MachineProof, MachineProver, MachineVerificationError, ShardProof, StarkGenericConfig,
+    StarkMachine, Word, DIGEST_SIZE,
 };
 use thiserror::Error;
 
 use crate::{
     components::SP1ProverComponents,
     utils::{assert_recursion_public_values_valid, assert_root_public_values_valid},
-    CoreSC, HashableKey, OuterSC, SP1CoreProofData, SP1Prover, SP1VerifyingKey,
+    CompressAir, CoreSC, HashableKey, InnerSC, LazySP1CoreProof, OuterSC, SP1CoreProofData,
+    SP1Prover, SP1VerifyingKey, ShrinkAir,
 };
 
 #[derive(Error, Debug)]
@@ -50,6 +56,37 @@ pub enum Groth16VerificationError {
     InvalidPublicValues,
 }
 
+#[derive(Error, Debug)]
+pub enum LazyCoreProofVerificationError {
+    #[error("failed to read a shard proof: {0}")]
+    Read(#[from] bincode::Error),
+    #[error("invalid core proof: {0}")]
+    Invalid(#[from] MachineVerificationError<CoreSC>),
+}
+
+/// The parts of a core shard proof that the checks across shards look at.
+struct ShardSummary {
+    public_values: PublicValues<Word<BabyBear>, BabyBear>,
+    contains_cpu: bool,
+    log_degree_cpu: Option<usize>,
+    contains_global_memory_init: bool,
+    contains_global_memory_finalize: bool,
+}
+
+impl ShardSummary {
+    fn new(shard_proof: &ShardProof<CoreSC>) -> Self {
+        let public_values: &PublicValues<Word<_>, _> =
+            shard_proof.public_values.as_slice().borrow();
+        Self {
+            public_values: *public_values,
+            contains_cpu: shard_proof.contains_cpu(),
+            log_degree_cpu: shard_proof.contains_cpu().then(|| shard_proof.log_degree_cpu()),
+            contains_global_memory_init: shard_proof.contains_global_memory_init(),
+            contains_global_memory_finalize: shard_proof.contains_global_memory_finalize(),
+        }
+    }
+}
+
 impl<C: SP1ProverComponents> SP1Prover<C> {
     /// Verify a core proof by verifying the shards, verifying lookup bus, verifying that the
     /// shards are contiguous and complete.
@@ -57,12 +94,54 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         &self,
         proof: &SP1CoreProofData,
         vk: &SP1VerifyingKey,
+    ) -> Result<(), MachineVerificationError<CoreSC>> {
+        let shards = proof.0.iter().map(ShardSummary::new).collect::<Vec<_>>();
+        Self::verify_shard_summaries(&shards, vk)?;
+
+        // Verify the shard proof.
+        let mut challenger = self.core_prover.config().challenger();
+        self.core_prover.machine().verify_lazy(
+            &vk.vk,
+            proof.0.len(),
+            |i| Ok(&proof.0[i]),
+            &mut challenger,
+        )
+    }
+
+    /// Verify a saved core proof opened with [crate::SP1CoreProof::open_lazy] like [Self::verify].
+    ///
+    /// The shard proofs are decoded one at a time, each one once to check the public values across
+    /// shards and twice more to verify the shard proofs, so at most one is held in memory.
+    pub fn verify_lazy(
+        &self,
+        proof: &LazySP1CoreProof,
+        vk: &SP1VerifyingKey,
+    ) -> Result<(), LazyCoreProofVerificationError> {
+        let shards = proof
+            .shards()
+            .map(|shard_proof| shard_proof.map(|shard_proof| ShardSummary::new(&shard_proof)))
+            .collect::<bincode::Result<Vec<_>>>()?;
+        Self::verify_shard_summaries(&shards, vk)?;
+
+        let mut challenger = self.core_prover.config().challenger();
+        self.core_prover.machine().verify_lazy(
+            &vk.vk,
+            proof.len(),
+            |i| proof.shard(i).map_err(Into::into),
+            &mut challenger,
+        )
+    }
+
+    /// Checks that the shards of a core proof are contiguous and complete.
+    fn verify_shard_summaries(
+        shards: &[ShardSummary],
+        vk: &SP1VerifyingKey,
     ) -> Result<(), MachineVerificationError<CoreSC>> {
         // First shard has a "CPU" constraint.
         //
         // Assert that the first shard has a "CPU".
-        let first_shard = proof.0.first().unwrap();
-        if !first_shard.contains_cpu() {
+        let first_shard = shards.first().ok_or(MachineVerificationError::EmptyProof)?;
+        if !first_shard.contains_cpu {
             return Err(MachineVerificationError::MissingCpuInFirstShard);
         }
 
@@ -70,9 +149,8 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         //
         // Assert that the CPU log degree does not exceed `MAX_CPU_LOG_DEGREE`. This is to ensure
         // that the lookup argument's multiplicities do not overflow.
-        for shard_proof in proof.0.iter() {
-            if shard_proof.contains_cpu() {
-                let log_degree_cpu = shard_proof.log_degree_cpu();
+        for shard_proof in shards.iter() {
+            if let Some(log_degree_cpu) = shard_proof.log_degree_cpu {
                 if log_degree_cpu > MAX_CPU_LOG_DEGREE {
                     return Err(MachineVerificationError::CpuLogDegreeTooLarge(log_degree_cpu));
                 }
@@ -87,9 +165,8 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         // Transition:
         // - Shard should increment by one for each shard.
         let mut current_shard = BabyBear::zero();
-        for shard_proof in proof.0.iter() {
-            let public_values: &PublicValues<Word<_>, _> =
-                shard_proof.public_values.as_slice().borrow();
+        for shard_proof in shards.iter() {
+            let public_values = &shard_proof.public_values;
             current_shard += BabyBear::one();
             if public_values.shard != current_shard {
                 return Err(MachineVerificationError::InvalidPublicValues(
@@ -108,10 +185,9 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         // - Execution shard should stay the same for non-CPU shards.
         // - For the other shards, execution shard does not matter.
         let mut current_execution_shard = BabyBear::zero();
-        for shard_proof in proof.0.iter() {
-            let public_values: &PublicValues<Word<_>, _> =
-                shard_proof.public_values.as_slice().borrow();
-            if shard_proof.contains_cpu() {
+        for shard_proof in shards.iter() {
+            let public_values = &shard_proof.public_values;
+            if shard_proof.contains_cpu {
                 current_execution_shard += BabyBear::one();
                 if public_values.execution_shard != current_execution_shard {
                     return Err(MachineVerificationError::InvalidPublicValues(
@@ -134,9 +210,8 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         // Finalization:
         // - `next_pc` should equal zero.
         let mut prev_next_pc = BabyBear::zero();
-        for (i, shard_proof) in proof.0.iter().enumerate() {
-            let public_values: &PublicValues<Word<_>, _> =
-                shard_proof.public_values.as_slice().borrow();
+        for (i, shard_proof) in shards.iter().enumerate() {
+            let public_values = &shard_proof.public_values;
             if i == 0 && public_values.start_pc != vk.vk.pc_start {
                 return Err(MachineVerificationError::InvalidPublicValues(
                     "start_pc != vk.start_pc: program counter should start at vk.start_pc",
@@ -145,16 +220,15 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                 return Err(MachineVerificationError::InvalidPublicValues(
                     "start_pc != next_pc_prev: start_pc should equal next_pc_prev for all shards",
                 ));
-            } else if !shard_proof.contains_cpu() && public_values.start_pc != public_values.next_pc
-            {
+            } else if !shard_proof.contains_cpu && public_values.start_pc != public_values.next_pc {
                 return Err(MachineVerificationError::InvalidPublicValues(
                     "start_pc != next_pc: start_pc should equal next_pc for non-cpu shards",
                 ));
-            } else if shard_proof.contains_cpu() && public_values.start_pc == BabyBear::zero() {
+            } else if shard_proof.contains_cpu && public_values.start_pc == BabyBear::zero() {
                 return Err(MachineVerificationError::InvalidPublicValues(
                     "start_pc == 0: execution should never start at halted state",
                 ));
-            } else if i == proof.0.len() - 1 && public_values.next_pc != BabyBear::zero() {
+            } else if i == shards.len() - 1 && public_values.next_pc != BabyBear::zero() {
                 return Err(MachineVerificationError::InvalidPublicValues(
                     "next_pc != 0: execution should have halted",
                 ));
@@ -165,9 +239,8 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         // Exit code constraints.
         //
         // - In every shard, the exit code should be zero.
-        for shard_proof in proof.0.iter() {
-            let public_values: &PublicValues<Word<_>, _> =
-                shard_proof.public_values.as_slice().borrow();
+        for shard_proof in shards.iter() {
+            let public_values = &shard_proof.public_values;
             if public_values.exit_code != BabyBear::zero() {
                 return Err(MachineVerificationError::InvalidPublicValues(
                     "exit_code != 0: exit code should be zero for all shards",
@@ -192,9 +265,8 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         //   `last_finalize_addr_bits`.
         let mut last_init_addr_bits_prev = [BabyBear::zero(); 32];
         let mut last_finalize_addr_bits_prev = [BabyBear::zero(); 32];
-        for shard_proof in proof.0.iter() {
-            let public_values: &PublicValues<Word<_>, _> =
-                shard_proof.public_values.as_slice().borrow();
+        for shard_proof in shards.iter() {
+            let public_values = &shard_proof.public_values;
             if public_values.previous_init_addr_bits != last_init_addr_bits_prev {
                 return Err(MachineVerificationError::InvalidPublicValues(
                     "previous_init_addr_bits != last_init_addr_bits_prev",
@@ -203,13 +275,13 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                 return Err(MachineVerificationError::InvalidPublicValues(
                     "last_init_addr_bits != last_finalize_addr_bits_prev",
                 ));
-            } else if !shard_proof.contains_global_memory_init()
+            } else if !shard_proof.contains_global_memory_init
                 && public_values.previous_init_addr_bits != public_values.last_init_addr_bits
             {
                 return Err(MachineVerificationError::InvalidPublicValues(
                     "previous_init_addr_bits != last_init_addr_bits",
                 ));
-            } else if !shard_proof.contains_global_memory_finalize()
+            } else if !shard_proof.contains_global_memory_finalize
                 && public_values.previous_finalize_addr_bits
                     != public_values.last_finalize_addr_bits
             {
@@ -243,9 +315,8 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         let zero_deferred_proofs_digest = [BabyBear::zero(); POSEIDON_NUM_WORDS];
         let mut committed_value_digest_prev = zero_committed_value_digest;
         let mut deferred_proofs_digest_prev = zero_deferred_proofs_digest;
-        for shard_proof in proof.0.iter() {
-            let public_values: &PublicValues<Word<_>, _> =
-                shard_proof.public_values.as_slice().borrow();
+        for shard_proof in shards.iter() {
+            let public_values = &shard_proof.public_values;
             if committed_value_digest_prev != zero_committed_value_digest
                 && public_values.committed_value_digest != committed_value_digest_prev
             {
@@ -258,13 +329,13 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                 return Err(MachineVerificationError::InvalidPublicValues(
                     "deferred_proofs_digest != deferred_proofs_digest_prev",
                 ));
-            } else if !shard_proof.contains_cpu()
+            } else if !shard_proof.contains_cpu
                 && public_values.committed_value_digest != committed_value_digest_prev
             {
                 return Err(MachineVerificationError::InvalidPublicValues(
                     "committed_value_digest != committed_value_digest_prev",
                 ));
-            } else if !shard_proof.contains_cpu()
+            } else if !shard_proof.contains_cpu
                 && public_values.deferred_proofs_digest != deferred_proofs_digest_prev
             {
                 return Err(MachineVerificationError::InvalidPublicValues(
@@ -276,15 +347,10 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         }
 
         // Verify that the number of shards is not too large.
-        if proof.0.len() > 1 << 16 {
+        if shards.len() > 1 << 16 {
             return Err(MachineVerificationError::TooManyShards);
         }
 
-        // Verify the shard proof.
-        let mut challenger = self.core_prover.config().challenger();
-        let machine_proof = MachineProof { shard_proofs: proof.0.to_vec() };
-        self.core_prover.machine().verify(&vk.vk, &machine_proof, &mut challenger)?;
-
         Ok(())
     }
 
@@ -294,35 +360,14 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         proof: &SP1ReduceProof<BabyBearPoseidon2>,
         vk: &SP1VerifyingKey,
     ) -> Result<(), MachineVerificationError<CoreSC>> {
-        let SP1ReduceProof { vk: compress_vk, proof } = proof;
         let mut challenger = self.compress_prover.config().challenger();
-        let machine_proof = MachineProof { shard_proofs: vec![proof.clone()] };
-        self.compress_prover.machine().verify(compress_vk, &machine_proof, &mut challenger)?;
-
-        // Validate public values
-        let public_values: &RecursionPublicValues<_> = proof.public_values.as_slice().borrow();
-        assert_recursion_public_values_valid(
-            self.compress_prover.machine().config(),
-            public_values,
-        );
-
-        if self.vk_verification && !self.allowed_vk_map.contains_key(&compress_vk.hash_babybear()) {
-            return Err(MachineVerificationError::InvalidVerificationKey);
-        }
-
-        // `is_complete` should be 1. In the reduce program, this ensures that the proof is fully
-        // reduced.
-        if public_values.is_complete != BabyBear::one() {
-            return Err(MachineVerificationError::InvalidPublicValues("is_complete is not 1"));
-        }
-
-        // Verify that the proof is for the sp1 vkey we are expecting.
-        let vkey_hash = vk.hash_babybear();
-        if public_values.sp1_vk_digest != vkey_hash {
-            return Err(MachineVerificationError::InvalidPublicValues("sp1 vk hash mismatch"));
-        }
-
-        Ok(())
+        verify_compressed_proof(
+            proof,
+            vk,
+            self.vk_verification.then_some(&self.allowed_vk_map),
+            self.compress_prover.machine(),
+            &mut challenger,
+        )
     }
 
     /// Verify a shrink proof.
@@ -332,28 +377,13 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         vk: &SP1VerifyingKey,
     ) -> Result<(), MachineVerificationError<CoreSC>> {
         let mut challenger = self.shrink_prover.config().challenger();
-        let machine_proof = MachineProof { shard_proofs: vec![proof.proof.clone()] };
-        self.shrink_prover.machine().verify(&proof.vk, &machine_proof, &mut challenger)?;
-
-        // Validate public values
-        let public_values: &RecursionPublicValues<_> =
-            proof.proof.public_values.as_slice().borrow();
-        assert_recursion_public_values_valid(
-            self.compress_prover.machine().config(),
-            public_values,
-        );
-
-        if self.vk_verification && !self.allowed_vk_map.contains_key(&proof.vk.hash_babybear()) {
-            return Err(MachineVerificationError::InvalidVerificationKey);
-        }
-
-        // Verify that the proof is for the sp1 vkey we are expecting.
-        let vkey_hash = vk.hash_babybear();
-        if public_values.sp1_vk_digest != vkey_hash {
-            return Err(MachineVerificationError::InvalidPublicValues("sp1 vk hash mismatch"));
-        }
-
-        Ok(())
+        verify_shrink_proof(
+            proof,
+            vk,
+            self.vk_verification.then_some(&self.allowed_vk_map),
+            self.shrink_prover.machine(),
+            &mut challenger,
+        )
     }
 
     /// Verify a wrap bn254 proof.
@@ -402,6 +432,27 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         Ok(())
     }
 
+    /// Verifies a PLONK proof using only the contents of the `plonk_vk.bin` circuit artifact.
+    pub fn verify_plonk_bn254_with_vk(
+        &self,
+        proof: &PlonkBn254Proof,
+        vk: &SP1VerifyingKey,
+        public_values: &SP1PublicValues,
+        plonk_vk_bytes: &[u8],
+    ) -> Result<()> {
+        let prover = PlonkBn254Prover::new();
+
+        let vkey_hash = BigUint::from_str(&proof.public_inputs[0])?;
+        let committed_values_digest = BigUint::from_str(&proof.public_inputs[1])?;
+
+        // Verify the proof with the corresponding public inputs.
+        prover.verify_with_vk(proof, plonk_vk_bytes, &vkey_hash, &committed_values_digest);
+
+        verify_plonk_bn254_public_inputs(vk, public_values, &proof.public_inputs)?;
+
+        Ok(())
+    }
+
     /// Verifies a Groth16 proof using the circuit artifacts in the build directory.
     pub fn verify_groth16_bn254(
         &self,
@@ -424,6 +475,86 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
     }
 }
 
+/// Verify a compressed proof using only the compress machine.
+///
+/// This performs the same checks as [`SP1Prover::verify_compressed`], so a verifier does not need
+/// to construct a full [`SP1Prover`]. The recursion vk of the proof must be a key of
+/// `allowed_vk_map`, which should be the prover's [`SP1Prover::allowed_vk_map`]. Passing `None`
+/// skips that check, like a prover with vk verification disabled.
+pub fn verify_compressed_proof(
+    proof: &SP1ReduceProof<InnerSC>,
+    vk: &SP1VerifyingKey,
+    allowed_vk_map: Option<&BTreeMap<[BabyBear; DIGEST_SIZE], usize>>,
+    machine: &StarkMachine<InnerSC, CompressAir<BabyBear>>,
+    challenger: &mut <InnerSC as StarkGenericConfig>::Challenger,
+) -> Result<(), MachineVerificationError<CoreSC>> {
+    check_allowed_vk(proof, allowed_vk_map)?;
+
+    let SP1ReduceProof { vk: compress_vk, proof } = proof;
+    let machine_proof = MachineProof { shard_proofs: vec![proof.clone()] };
+    machine.verify(compress_vk, &machine_proof, challenger)?;
+
+    // Validate public values
+    let public_values: &RecursionPublicValues<_> = proof.public_values.as_slice().borrow();
+    assert_recursion_public_values_valid(machine.config(), public_values);
+
+    // `is_complete` should be 1. In the reduce program, this ensures that the proof is fully
+    // reduced.
+    if public_values.is_complete != BabyBear::one() {
+        return Err(MachineVerificationError::InvalidPublicValues("is_complete is not 1"));
+    }
+
+    // Verify that the proof is for the sp1 vkey we are expecting.
+    let vkey_hash = vk.hash_babybear();
+    if public_values.sp1_vk_digest != vkey_hash {
+        return Err(MachineVerificationError::InvalidPublicValues("sp1 vk hash mismatch"));
+    }
+
+    Ok(())
+}
+
+/// Verify a shrink proof using only the shrink machine.
+///
+/// This performs the same checks as [`SP1Prover::verify_shrink`], with `allowed_vk_map` used as in
+/// [`verify_compressed_proof`].
+pub fn verify_shrink_proof(
+    proof: &SP1ReduceProof<InnerSC>,
+    vk: &SP1VerifyingKey,
+    allowed_vk_map: Option<&BTreeMap<[BabyBear; DIGEST_SIZE], usize>>,
+    machine: &StarkMachine<InnerSC, ShrinkAir<BabyBear>>,
+    challenger: &mut <InnerSC as StarkGenericConfig>::Challenger,
+) -> Result<(), MachineVerificationError<CoreSC>> {
+    check_allowed_vk(proof, allowed_vk_map)?;
+
+    let machine_proof = MachineProof { shard_proofs: vec![proof.proof.clone()] };
+    machine.verify(&proof.vk, &machine_proof, challenger)?;
+
+    // Validate public values
+    let public_values: &RecursionPublicValues<_> = proof.proof.public_values.as_slice().borrow();
+    assert_recursion_public_values_valid(machine.config(), public_values);
+
+    // Verify that the proof is for the sp1 vkey we are expecting.
+    let vkey_hash = vk.hash_babybear();
+    if public_values.sp1_vk_digest != vkey_hash {
+        return Err(MachineVerificationError::InvalidPublicValues("sp1 vk hash mismatch"));
+    }
+
+    Ok(())
+}
+
+/// Check that the recursion vk of a proof is in the allowed vk map, if one is given.
+fn check_allowed_vk(
+    proof: &SP1ReduceProof<InnerSC>,
+    allowed_vk_map: Option<&BTreeMap<[BabyBear; DIGEST_SIZE], usize>>,
+) -> Result<(), MachineVerificationError<CoreSC>> {
+    match allowed_vk_map {
+        Some(map) if !map.contains_key(&proof.vk.hash_babybear()) => {
+            Err(MachineVerificationError::InvalidVerificationKey)
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Verify the vk_hash and public_values_hash in the public inputs of the PlonkBn254Proof match the
 /// expected values.
 pub fn verify_plonk_bn254_public_inputs(
@@ -501,4 +632,14 @@ impl<C: SP1ProverComponents> SubproofVerifier for &SP1Prover<C> {
         }
         Ok(())
     }
+
+    /// Verify the deferred proofs in parallel.
+    fn verify_batch(
+        &self,
+        proofs: &[DeferredProofClaim<'_>],
+    ) -> Result<(), MachineVerificationError<BabyBearPoseidon2>> {
+        proofs.par_iter().try_for_each(|&(proof, vk, vk_hash, committed_value_digest)| {
+            self.verify_deferred_proof(proof, vk, vk_hash, committed_value_digest)
+        })
+    }
 }
@@ -0,0 +1,127 @@
+//! Conversions between BabyBear values and Bn254 field elements, both natively and in-circuit.
+//!
+//! These are the encodings used to expose the recursion public values (the vkey digest and the
+//! committed values digest) as public inputs of the Groth16 and PLONK wrappers. Each in-circuit
+//! function has a native counterpart producing the same value, e.g.:
+//!
+//! ```
+//! use p3_baby_bear::BabyBear;
+//! use p3_field::AbstractField;
+//! use sp1_recursion_circuit::conversions::{babybears_to_bn254, felts_to_bn254_var};
+//! use sp1_recursion_compiler::{
+//!     config::OuterConfig,
+//!     ir::{Builder, Felt, Var},
+//! };
+//! use sp1_recursion_core::{air::RecursionPublicValues, DIGEST_SIZE};
+//!
+//! let public_values = RecursionPublicValues::<BabyBear> {
+//!     sp1_vk_digest: core::array::from_fn(|i| BabyBear::from_canonical_usize(i + 1)),
+//!     ..Default::default()
+//! };
+//! let expected = babybears_to_bn254(&public_values.sp1_vk_digest);
+//!
+//! let mut builder = Builder::<OuterConfig>::default();
+//! let sp1_vk_digest: [Felt<_>; DIGEST_SIZE] =
+//!     public_values.sp1_vk_digest.map(|word| builder.eval(word));
+//! let vkey_hash = felts_to_bn254_var(&mut builder, &sp1_vk_digest);
+//! let expected: Var<_> = builder.eval(expected);
+//! builder.assert_var_eq(vkey_hash, expected);
+//! ```
+
+use p3_baby_bear::BabyBear;
+use p3_bn254_fr::Bn254Fr;
+use p3_field::{AbstractField, PrimeField32};
+
+use sp1_recursion_compiler::ir::{Builder, Config, Felt, Var};
+use sp1_recursion_core::DIGEST_SIZE;
+
+use sp1_stark::Word;
+
+/// Convert 8 BabyBear words into a Bn254Fr field element by shifting by 31 bits each time. The last
+/// word becomes the least significant bits.
+pub fn babybears_to_bn254(digest: &[BabyBear; 8]) -> Bn254Fr {
+    let mut result = Bn254Fr::zero();
+    for word in digest.iter() {
+        // Since BabyBear prime is less than 2^31, we can shift by 31 bits each time and still be
+        // within the Bn254Fr field, so we don't have to truncate the top 3 bits.
+        result *= Bn254Fr::from_canonical_u64(1 << 31);
+        result += Bn254Fr::from_canonical_u32(word.as_canonical_u32());
+    }
+    result
+}
+
+/// Convert 32 BabyBear bytes into a Bn254Fr field element. The first byte's most significant 3 bits
+/// (which would become the 3 most significant bits) are truncated.
+pub fn babybear_bytes_to_bn254(bytes: &[BabyBear; 32]) -> Bn254Fr {
+    let mut result = Bn254Fr::zero();
+    for (i, byte) in bytes.iter().enumerate() {
+        debug_assert!(byte < &BabyBear::from_canonical_u32(256));
+        if i == 0 {
+            // 32 bytes is more than Bn254 prime, so we need to truncate the top 3 bits.
+            result = Bn254Fr::from_canonical_u32(byte.as_canonical_u32() & 0x1f);
+        } else {
+            result *= Bn254Fr::from_canonical_u32(256);
+            result += Bn254Fr::from_canonical_u32(byte.as_canonical_u32());
+        }
+    }
+    result
+}
+
+/// The in-circuit version of [`babybears_to_bn254`].
+///
+/// The `DIGEST_SIZE` felts are packed big-endian, 31 bits each: the first felt ends up in the most
+/// significant bits and the last felt in the least significant bits.
+pub fn felts_to_bn254_var<C: Config>(
+    builder: &mut Builder<C>,
+    digest: &[Felt<C::F>; DIGEST_SIZE],
+) -> Var<C::N> {
+    let var_2_31: Var<_> = builder.constant(C::N::from_canonical_u32(1 << 31));
+    let result = builder.constant(C::N::zero());
+    for (i, word) in digest.iter().enumerate() {
+        let word_var = builder.felt2var_circuit(*word);
+        if i == 0 {
+            builder.assign(result, word_var);
+        } else {
+            builder.assign(result, result * var_2_31 + word_var);
+        }
+    }
+    result
+}
+
+/// The in-circuit version of [`babybear_bytes_to_bn254`].
+///
+/// The 32 felts are interpreted as big-endian bytes, and each of them must already be constrained
+/// to be less than 256. The 3 most significant bits of the first byte are dropped so that the
+/// result fits in a Bn254 element.
+pub fn felt_bytes_to_bn254_var<C: Config>(
+    builder: &mut Builder<C>,
+    bytes: &[Felt<C::F>; 32],
+) -> Var<C::N> {
+    let var_256: Var<_> = builder.constant(C::N::from_canonical_u32(256));
+    let zero_var: Var<_> = builder.constant(C::N::zero());
+    let result = builder.constant(C::N::zero());
+    for (i, byte) in bytes.iter().enumerate() {
+        let byte_bits = builder.num2bits_f_circuit(*byte);
+        if i == 0 {
+            // Since 32 bytes doesn't fit into Bn254, we need to truncate the top 3 bits.
+            // For first byte, zero out 3 most significant bits.
+            for i in 0..3 {
+                builder.assign(byte_bits[8 - i - 1], zero_var);
+            }
+            let byte_var = builder.bits2num_v_circuit(&byte_bits);
+            builder.assign(result, byte_var);
+        } else {
+            let byte_var = builder.bits2num_v_circuit(&byte_bits);
+            builder.assign(result, result * var_256 + byte_var);
+        }
+    }
+    result
+}
+
+/// Flattens words into their bytes, keeping the little-endian byte order within each word.
+///
+/// Applied to the 8 words of a committed values digest, this yields the 32 bytes expected by
+/// [`felt_bytes_to_bn254_var`] and [`babybear_bytes_to_bn254`].
+pub fn words_to_bytes<T: Copy>(words: &[Word<T>]) -> Vec<T> {
+    words.iter().flat_map(|w| w.0).collect::<Vec<_>>()
+}
@@ -20,6 +20,7 @@ mod types;
 
 pub mod challenger;
 pub mod constraints;
+pub mod conversions;
 pub mod domain;
 pub mod fri;
 pub mod hash;
@@ -45,8 +46,9 @@ use sp1_recursion_core::{
     D,
 };
 
+use conversions::{felt_bytes_to_bn254_var, felts_to_bn254_var, words_to_bytes};
 use p3_baby_bear::BabyBear;
-use utils::{felt_bytes_to_bn254_var, felts_to_bn254_var, words_to_bytes};
+use utils::fold_domain_into_bn254_var;
 
 type EF = <BabyBearPoseidon2 as StarkGenericConfig>::Challenge;
 
@@ -105,6 +107,21 @@ pub trait BabyBearFriConfigVariable<C: CircuitConfig<F = BabyBear>>:
     );
 }
 
+/// The public value commitments that only exist in the outer circuit, whose public inputs are
+/// Bn254 elements.
+pub trait BabyBearFriConfigOuterVariable<C: CircuitConfig<F = BabyBear, N = Bn254Fr>>:
+    BabyBearFriConfigVariable<C>
+{
+    /// Commits the recursion public values together with an application-specific domain tag, so
+    /// that proofs from different applications sharing one verifier cannot be replayed across
+    /// them.
+    fn commit_recursion_public_values_with_domain(
+        builder: &mut Builder<C>,
+        public_values: RecursionPublicValues<Felt<C::F>>,
+        domain: [Felt<C::F>; 4],
+    );
+}
+
 pub trait CircuitConfig: Config {
     type Bit: Copy + Variable<Self>;
 
@@ -177,6 +194,29 @@ pub trait CircuitConfig: Config {
             Self::assert_bit_zero(builder, bit);
         }
     }
+
+    /// Asserts that a little-endian bit decomposition represents a value below the BabyBear
+    /// modulus, so that a prover cannot substitute a non-canonical decomposition.
+    fn assert_canonical_bits(builder: &mut Builder<Self>, bits: &[Self::Bit]) {
+        for bit in bits.iter().skip(31) {
+            Self::assert_bit_zero(builder, *bit);
+        }
+
+        // If there are less than 31 bits, there is nothing else to check.
+        if bits.len() < 31 {
+            return;
+        }
+
+        // Since the BabyBear modulus is 2^31 - 2^27 + 1, a 31-bit value is canonical if any of the
+        // top `4` bits are zero, or otherwise if all the bottom `27` bits are zero.
+        let bits: Vec<Felt<_>> =
+            bits[..31].iter().map(|bit| Self::bits2num(builder, [*bit])).collect();
+        let are_all_top_bits_one: Felt<_> = builder
+            .eval(bits[27..].iter().copied().map(SymbolicFelt::from).product::<SymbolicFelt<_>>());
+        for bit in bits[..27].iter().copied() {
+            builder.assert_felt_eq(bit * are_all_top_bits_one, Self::F::zero());
+        }
+    }
 }
 
 impl CircuitConfig for InnerConfig {
@@ -630,3 +670,87 @@ impl<C: CircuitConfig<F = BabyBear, N = Bn254Fr, Bit = Var<Bn254Fr>>> BabyBearFr
         builder.commit_vkey_hash_circuit(vkey_hash);
     }
 }
+
+impl<C: CircuitConfig<F = BabyBear, N = Bn254Fr, Bit = Var<Bn254Fr>>>
+    BabyBearFriConfigOuterVariable<C> for BabyBearPoseidon2Outer
+{
+    fn commit_recursion_public_values_with_domain(
+        builder: &mut Builder<C>,
+        public_values: RecursionPublicValues<Felt<<C>::F>>,
+        domain: [Felt<<C>::F>; 4],
+    ) {
+        let committed_values_digest_bytes_felts: [Felt<_>; 32] =
+            words_to_bytes(&public_values.committed_value_digest).try_into().unwrap();
+        let committed_values_digest_bytes: Var<_> =
+            felt_bytes_to_bn254_var(builder, &committed_values_digest_bytes_felts);
+        let committed_values_digest =
+            fold_domain_into_bn254_var(builder, committed_values_digest_bytes, &domain);
+        builder.commit_committed_values_digest_circuit(committed_values_digest);
+
+        let vkey_hash = felts_to_bn254_var(builder, &public_values.sp1_vk_digest);
+        builder.commit_vkey_hash_circuit(vkey_hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use p3_field::{AbstractField, PrimeField32};
+    use sp1_recursion_compiler::{
+        circuit::AsmBuilder,
+        config::InnerConfig,
+        ir::{Config, Felt},
+    };
+
+    use crate::{utils::tests::run_test_recursion, CircuitConfig};
+
+    type F = <InnerConfig as Config>::F;
+    type EF = <InnerConfig as Config>::EF;
+
+    fn run_assert_canonical_bits(value: u32) {
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let bits: Vec<Felt<_>> =
+            (0..31).map(|i| builder.eval(F::from_canonical_u32((value >> i) & 1))).collect();
+        InnerConfig::assert_canonical_bits(&mut builder, &bits);
+        run_test_recursion(builder.into_operations(), None);
+    }
+
+    #[test]
+    fn test_assert_canonical_bits() {
+        run_assert_canonical_bits(F::ORDER_U32 - 1);
+        run_assert_canonical_bits((1 << 27) - 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_canonical_bits_non_canonical() {
+        // `p + 1` still fits in 31 bits but is not a canonical BabyBear element.
+        run_assert_canonical_bits(F::ORDER_U32 + 1);
+    }
+
+    fn run_select_chain_f(should_swap: bool) {
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let first: Vec<Felt<_>> = (1..=2).map(|i| builder.eval(F::from_canonical_u32(i))).collect();
+        let second: Vec<Felt<_>> =
+            (3..=5).map(|i| builder.eval(F::from_canonical_u32(i))).collect();
+        let bit: Felt<_> = builder.eval(F::from_bool(should_swap));
+
+        let selected =
+            InnerConfig::select_chain_f(&mut builder, bit, first.clone(), second.clone());
+        let expected = if should_swap {
+            second.into_iter().chain(first)
+        } else {
+            first.into_iter().chain(second)
+        };
+        for (selected, expected) in selected.into_iter().zip_eq(expected) {
+            builder.assert_felt_eq(selected, expected);
+        }
+        run_test_recursion(builder.into_operations(), None);
+    }
+
+    #[test]
+    fn test_select_chain_f() {
+        run_select_chain_f(false);
+        run_select_chain_f(true);
+    }
+}
@@ -2,12 +2,12 @@ use std::mem::MaybeUninit;
 
 use p3_baby_bear::BabyBear;
 use p3_bn254_fr::Bn254Fr;
-use p3_field::{AbstractField, PrimeField32};
+use p3_field::AbstractField;
 
 use sp1_recursion_compiler::ir::{Builder, Config, Felt, Var};
-use sp1_recursion_core::{air::ChallengerPublicValues, DIGEST_SIZE};
+use sp1_recursion_core::{air::ChallengerPublicValues, stark::BabyBearPoseidon2Outer, DIGEST_SIZE};
 
-use sp1_stark::Word;
+use crate::{conversions::felts_to_bn254_var, hash::FieldHasherVariable, CircuitConfig};
 
 pub(crate) unsafe fn uninit_challenger_pv<C: Config>(
     _builder: &mut Builder<C>,
@@ -15,93 +15,39 @@ pub(crate) unsafe fn uninit_challenger_pv<C: Config>(
     unsafe { MaybeUninit::zeroed().assume_init() }
 }
 
-/// Convert 8 BabyBear words into a Bn254Fr field element by shifting by 31 bits each time. The last
-/// word becomes the least significant bits.
-#[allow(dead_code)]
-pub fn babybears_to_bn254(digest: &[BabyBear; 8]) -> Bn254Fr {
-    let mut result = Bn254Fr::zero();
-    for word in digest.iter() {
-        // Since BabyBear prime is less than 2^31, we can shift by 31 bits each time and still be
-        // within the Bn254Fr field, so we don't have to truncate the top 3 bits.
-        result *= Bn254Fr::from_canonical_u64(1 << 31);
-        result += Bn254Fr::from_canonical_u32(word.as_canonical_u32());
-    }
-    result
-}
-
-/// Convert 32 BabyBear bytes into a Bn254Fr field element. The first byte's most significant 3 bits
-/// (which would become the 3 most significant bits) are truncated.
-#[allow(dead_code)]
-pub fn babybear_bytes_to_bn254(bytes: &[BabyBear; 32]) -> Bn254Fr {
-    let mut result = Bn254Fr::zero();
-    for (i, byte) in bytes.iter().enumerate() {
-        debug_assert!(byte < &BabyBear::from_canonical_u32(256));
-        if i == 0 {
-            // 32 bytes is more than Bn254 prime, so we need to truncate the top 3 bits.
-            result = Bn254Fr::from_canonical_u32(byte.as_canonical_u32() & 0x1f);
-        } else {
-            result *= Bn254Fr::from_canonical_u32(256);
-            result += Bn254Fr::from_canonical_u32(byte.as_canonical_u32());
-        }
-    }
-    result
-}
-
-#[allow(dead_code)]
-pub fn felts_to_bn254_var<C: Config>(
-    builder: &mut Builder<C>,
-    digest: &[Felt<C::F>; DIGEST_SIZE],
-) -> Var<C::N> {
-    let var_2_31: Var<_> = builder.constant(C::N::from_canonical_u32(1 << 31));
-    let result = builder.constant(C::N::zero());
-    for (i, word) in digest.iter().enumerate() {
-        let word_var = builder.felt2var_circuit(*word);
-        if i == 0 {
-            builder.assign(result, word_var);
-        } else {
-            builder.assign(result, result * var_2_31 + word_var);
-        }
-    }
-    result
-}
-
-#[allow(dead_code)]
-pub fn felt_bytes_to_bn254_var<C: Config>(
+/// Folds a 4-felt domain tag into a Bn254 digest by compressing the two with Poseidon2. The domain
+/// occupies the low words of its Bn254 encoding, see [`crate::conversions::babybears_to_bn254`].
+pub fn fold_domain_into_bn254_var<
+    C: CircuitConfig<F = BabyBear, N = Bn254Fr, Bit = Var<Bn254Fr>>,
+>(
     builder: &mut Builder<C>,
-    bytes: &[Felt<C::F>; 32],
+    digest: Var<C::N>,
+    domain: &[Felt<C::F>; 4],
 ) -> Var<C::N> {
-    let var_256: Var<_> = builder.constant(C::N::from_canonical_u32(256));
-    let zero_var: Var<_> = builder.constant(C::N::zero());
-    let result = builder.constant(C::N::zero());
-    for (i, byte) in bytes.iter().enumerate() {
-        let byte_bits = builder.num2bits_f_circuit(*byte);
-        if i == 0 {
-            // Since 32 bytes doesn't fit into Bn254, we need to truncate the top 3 bits.
-            // For first byte, zero out 3 most significant bits.
-            for i in 0..3 {
-                builder.assign(byte_bits[8 - i - 1], zero_var);
-            }
-            let byte_var = builder.bits2num_v_circuit(&byte_bits);
-            builder.assign(result, byte_var);
-        } else {
-            let byte_var = builder.bits2num_v_circuit(&byte_bits);
-            builder.assign(result, result * var_256 + byte_var);
-        }
-    }
-    result
-}
-
-#[allow(dead_code)]
-pub fn words_to_bytes<T: Copy>(words: &[Word<T>]) -> Vec<T> {
-    words.iter().flat_map(|w| w.0).collect::<Vec<_>>()
+    let zero: Felt<_> = builder.eval(C::F::zero());
+    let mut padded_domain = [zero; DIGEST_SIZE];
+    padded_domain[DIGEST_SIZE - domain.len()..].copy_from_slice(domain);
+    let domain = felts_to_bn254_var(builder, &padded_domain);
+    let [folded] = BabyBearPoseidon2Outer::compress(builder, [[digest], [domain]]);
+    folded
 }
 
 #[cfg(any(test, feature = "export-tests"))]
 pub(crate) mod tests {
     use std::sync::Arc;
 
+    use p3_baby_bear::BabyBear;
+    use p3_bn254_fr::Bn254Fr;
+    use p3_field::AbstractField;
     use sp1_core_machine::utils::{run_test_machine_with_prover, setup_logger};
-    use sp1_recursion_compiler::{circuit::AsmCompiler, circuit::AsmConfig, ir::DslIr};
+    use sp1_recursion_compiler::{
+        circuit::AsmCompiler,
+        circuit::AsmConfig,
+        config::OuterConfig,
+        constraints::ConstraintCompiler,
+        ir::{Builder, DslIr, Felt, Var},
+    };
+    use sp1_recursion_gnark_ffi::PlonkBn254Prover;
 
     use sp1_recursion_compiler::ir::TracedVec;
     use sp1_recursion_core::{machine::RecursionAir, Runtime};
@@ -109,7 +55,14 @@ pub(crate) mod tests {
         baby_bear_poseidon2::BabyBearPoseidon2, CpuProver, InnerChallenge, InnerVal, MachineProver,
     };
 
-    use crate::witness::WitnessBlock;
+    use sp1_recursion_core::{stark::BabyBearPoseidon2Outer, DIGEST_SIZE};
+
+    use super::fold_domain_into_bn254_var;
+    use crate::{
+        conversions::babybears_to_bn254,
+        hash::FieldHasher,
+        witness::{OuterWitness, WitnessBlock},
+    };
 
     type SC = BabyBearPoseidon2;
     type F = InnerVal;
@@ -161,4 +114,35 @@ pub(crate) mod tests {
     ) {
         run_test_recursion_with_prover::<CpuProver<_, _>>(operations, witness_stream)
     }
+
+    #[test]
+    fn test_fold_domain_into_bn254_var() {
+        type C = OuterConfig;
+
+        let digest = Bn254Fr::from_canonical_u32(0x1234_5678);
+        let domains = [[1, 2, 3, 4], [5, 6, 7, 8]].map(|d| d.map(BabyBear::from_canonical_u32));
+        let expected = domains.map(|domain| {
+            let mut padded_domain = [BabyBear::zero(); DIGEST_SIZE];
+            padded_domain[DIGEST_SIZE - 4..].copy_from_slice(&domain);
+            let [folded] = BabyBearPoseidon2Outer::constant_compress([
+                [digest],
+                [babybears_to_bn254(&padded_domain)],
+            ]);
+            folded
+        });
+        assert_ne!(expected[0], expected[1]);
+
+        let mut builder = Builder::<C>::default();
+        let digest_var: Var<_> = builder.eval(digest);
+        for (domain, expected) in domains.into_iter().zip(expected) {
+            let domain: [Felt<_>; 4] = domain.map(|x| builder.eval(x));
+            let folded = fold_domain_into_bn254_var(&mut builder, digest_var, &domain);
+            let expected: Var<_> = builder.eval(expected);
+            builder.assert_var_eq(folded, expected);
+        }
+
+        let mut backend = ConstraintCompiler::<C>::default();
+        let constraints = backend.emit(builder.into_operations());
+        PlonkBn254Prover::test::<C>(constraints, OuterWitness::default());
+    }
 }
@@ -4,7 +4,10 @@ use serde::{Deserialize, Serialize};
 use sp1_derive::AlignedBorrow;
 use sp1_stark::air::{BinomialExtension, ExtensionAirBuilder, SP1AirBuilder};
 
-use std::ops::{Index, IndexMut};
+use std::{
+    fmt::{Display, Formatter},
+    ops::{Index, IndexMut},
+};
 
 use crate::runtime::D;
 
@@ -46,6 +49,13 @@ impl<T> Block<T> {
     }
 }
 
+impl<T: Display> Display for Block<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let [a, b, c, d] = &self.0;
+        write!(f, "[{a}, {b}, {c}, {d}]")
+    }
+}
+
 impl<T: Clone> Block<T> {
     pub fn as_extension<AB: ExtensionAirBuilder<Var = T>>(&self) -> BinomialExtension<AB::Expr> {
         let arr: [AB::Expr; 4] = self.0.clone().map(|x| AB::Expr::zero() + x);
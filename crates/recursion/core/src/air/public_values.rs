@@ -11,6 +11,7 @@ use sp1_stark::{air::POSEIDON_NUM_WORDS, Word, PROOF_MAX_NUM_PVS};
 use static_assertions::const_assert_eq;
 use std::{
     borrow::BorrowMut,
+    fmt::{self, Write},
     mem::{size_of, transmute, MaybeUninit},
 };
 
@@ -165,6 +166,88 @@ impl<F: Copy> RecursionPublicValues<F> {
     }
 }
 
+/// A field whose value differs between two [`RecursionPublicValues`], as reported by
+/// [`RecursionPublicValues::diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicValuesFieldDiff {
+    /// The name of the field.
+    pub field: &'static str,
+    /// The value of the field in `self`.
+    pub left: String,
+    /// The value of the field in `other`.
+    pub right: String,
+}
+
+impl fmt::Display for PublicValuesFieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} != {}", self.field, self.left, self.right)
+    }
+}
+
+impl<T: Debug> RecursionPublicValues<T> {
+    /// The name and value of every field, in declaration order.
+    fn named_fields(&self) -> Vec<(&'static str, &dyn Debug)> {
+        // Destructure `self` so that a new field can't be left out.
+        macro_rules! named_fields {
+            ($($field:ident),* $(,)?) => {{
+                let Self { $($field),* } = self;
+                vec![$((stringify!($field), $field as &dyn Debug)),*]
+            }};
+        }
+        named_fields!(
+            committed_value_digest,
+            deferred_proofs_digest,
+            start_pc,
+            next_pc,
+            start_shard,
+            next_shard,
+            start_execution_shard,
+            next_execution_shard,
+            previous_init_addr_bits,
+            last_init_addr_bits,
+            previous_finalize_addr_bits,
+            last_finalize_addr_bits,
+            start_reconstruct_challenger,
+            end_reconstruct_challenger,
+            start_reconstruct_deferred_digest,
+            end_reconstruct_deferred_digest,
+            sp1_vk_digest,
+            vk_root,
+            leaf_challenger,
+            cumulative_sum,
+            is_complete,
+            contains_execution_shard,
+            exit_code,
+            digest,
+        )
+    }
+
+    /// Render the public values with one `name: value` line per field.
+    pub fn describe(&self) -> String {
+        let mut description = String::new();
+        for (field, value) in self.named_fields() {
+            writeln!(description, "{field}: {value:?}").unwrap();
+        }
+        description
+    }
+
+    /// The fields whose values differ between `self` and `other`, in declaration order.
+    ///
+    /// Values are compared through their [`Debug`] representation.
+    pub fn diff(&self, other: &Self) -> Vec<PublicValuesFieldDiff> {
+        self.named_fields()
+            .into_iter()
+            .zip(other.named_fields())
+            .map(|((field, left), (_, right))| PublicValuesFieldDiff {
+                field,
+                left: format!("{left:?}"),
+                right: format!("{right:?}"),
+            })
+            .filter(|diff| diff.left != diff.right)
+            .collect()
+    }
+}
+
 impl<T: Copy> IntoIterator for RecursionPublicValues<T> {
     type Item = T;
     type IntoIter = std::array::IntoIter<T, RECURSIVE_PROOF_NUM_PV_ELTS>;
@@ -182,3 +265,27 @@ impl<T: Copy> IntoIterator for ChallengerPublicValues<T> {
         self.as_array().into_iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_reports_only_differing_field() {
+        let expected = RecursionPublicValues::<u32>::default();
+        let mut actual = expected;
+        actual.is_complete = 1;
+
+        assert!(expected.diff(&expected).is_empty());
+        assert_eq!(
+            expected.diff(&actual),
+            vec![PublicValuesFieldDiff {
+                field: "is_complete",
+                left: "0".to_string(),
+                right: "1".to_string(),
+            }]
+        );
+        assert!(expected.describe().contains("is_complete: 0\n"));
+        assert!(actual.describe().contains("is_complete: 1\n"));
+    }
+}
@@ -85,11 +85,11 @@ impl<F: PrimeField32, const DEGREE: usize> MachineAir<F> for BatchFRIChip<DEGREE
                 }
             })
             .for_each(|instruction| {
-                let BatchFRIInstr { base_vec_addrs, ext_single_addrs, ext_vec_addrs, acc_mult } =
+                instruction.validate().expect("invalid batch fri instruction");
+                let BatchFRIInstr { base_vec_addrs, ext_single_addrs, ext_vec_addrs, .. } =
                     instruction.as_ref();
                 let len = ext_vec_addrs.p_at_z.len();
                 let mut row_add = vec![[F::zero(); NUM_BATCH_FRI_PREPROCESSED_COLS]; len];
-                debug_assert_eq!(*acc_mult, F::one());
 
                 row_add.iter_mut().enumerate().for_each(|(i, row)| {
                     let row: &mut BatchFRIPreprocessedCols<F> = row.as_mut_slice().borrow_mut();
@@ -113,6 +113,7 @@ impl<F: PrimeField32, const DEGREE: usize> MachineAir<F> for FriFoldChip<DEGREE>
                 }
             })
             .for_each(|instruction| {
+                instruction.validate().expect("invalid fri fold instruction");
                 let FriFoldInstr {
                     base_single_addrs,
                     ext_single_addrs,
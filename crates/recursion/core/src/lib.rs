@@ -1,6 +1,9 @@
-use p3_field::PrimeField64;
+use std::fmt::{Display, Formatter};
+
+use p3_field::{Field, PrimeField64};
 use serde::{Deserialize, Serialize};
 use sp1_derive::AlignedBorrow;
+use thiserror::Error;
 
 use crate::air::{Block, RecursionPublicValues};
 
@@ -32,6 +35,12 @@ impl<F: PrimeField64> Address<F> {
     }
 }
 
+impl<F: Display> Display for Address<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "@{}", self.0)
+    }
+}
+
 // -------------------------------------------------------------------------------------------------
 
 /// The inputs and outputs to an operation of the base field ALU.
@@ -45,6 +54,13 @@ pub struct BaseAluIo<V> {
 
 pub type BaseAluEvent<F> = BaseAluIo<F>;
 
+/// Formats as `out: {out}, in1: {in1}, in2: {in2}`, for both events and instruction addresses.
+impl<V: Display> Display for BaseAluIo<V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "out: {}, in1: {}, in2: {}", self.out, self.in1, self.in2)
+    }
+}
+
 /// An instruction invoking the extension field ALU.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BaseAluInstr<F> {
@@ -66,6 +82,13 @@ pub struct ExtAluIo<V> {
 
 pub type ExtAluEvent<F> = ExtAluIo<Block<F>>;
 
+/// Formats as `out: {out}, in1: {in1}, in2: {in2}`, for both events and instruction addresses.
+impl<V: Display> Display for ExtAluIo<V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "out: {}, in1: {}, in2: {}", self.out, self.in1, self.in2)
+    }
+}
+
 /// An instruction invoking the extension field ALU.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExtAluInstr<F> {
@@ -207,6 +230,37 @@ pub struct FriFoldInstr<F> {
     pub ro_mults: Vec<F>,
 }
 
+/// An error returned when the vectors of a [`FriFoldInstr`] have inconsistent lengths.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FriFoldShapeError {
+    #[error("fri fold instruction has {len} `{field}` entries, but {expected} `ps_at_z` entries")]
+    LengthMismatch { field: &'static str, len: usize, expected: usize },
+}
+
+impl<F> FriFoldInstr<F> {
+    /// Checks that every vector of the instruction has one entry per iteration, i.e. the same
+    /// length as `ps_at_z`.
+    pub fn validate(&self) -> Result<(), FriFoldShapeError> {
+        let ext_vec_addrs = &self.ext_vec_addrs;
+        let expected = ext_vec_addrs.ps_at_z.len();
+        let lens = [
+            ("mat_opening", ext_vec_addrs.mat_opening.len()),
+            ("alpha_pow_input", ext_vec_addrs.alpha_pow_input.len()),
+            ("ro_input", ext_vec_addrs.ro_input.len()),
+            ("alpha_pow_output", ext_vec_addrs.alpha_pow_output.len()),
+            ("ro_output", ext_vec_addrs.ro_output.len()),
+            ("alpha_pow_mults", self.alpha_pow_mults.len()),
+            ("ro_mults", self.ro_mults.len()),
+        ];
+        for (field, len) in lens {
+            if len != expected {
+                return Err(FriFoldShapeError::LengthMismatch { field, len, expected });
+            }
+        }
+        Ok(())
+    }
+}
+
 /// The event encoding the data of a single iteration within the FRI fold operation.
 /// For any given event, we are accessing a single element of the `Vec` inputs, so that the event
 /// is not a type alias for `FriFoldIo` like many of the other events.
@@ -253,6 +307,41 @@ pub struct BatchFRIInstr<F> {
     pub acc_mult: F,
 }
 
+/// An error returned when a [`BatchFRIInstr`] does not have the shape the batch FRI chip assumes.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BatchFRIShapeError {
+    #[error("batch fri instruction has no `p_at_z` entries")]
+    Empty,
+    #[error("batch fri instruction has {len} `{field}` entries, but {expected} `p_at_z` entries")]
+    LengthMismatch { field: &'static str, len: usize, expected: usize },
+    #[error("batch fri instruction has an accumulator multiplicity other than one")]
+    AccMult,
+}
+
+impl<F: Field> BatchFRIInstr<F> {
+    /// Checks that the instruction has at least one iteration, that every vector has one entry per
+    /// iteration, i.e. the same length as `p_at_z`, and that the accumulator is written once.
+    pub fn validate(&self) -> Result<(), BatchFRIShapeError> {
+        let expected = self.ext_vec_addrs.p_at_z.len();
+        if expected == 0 {
+            return Err(BatchFRIShapeError::Empty);
+        }
+        let lens = [
+            ("alpha_pow", self.ext_vec_addrs.alpha_pow.len()),
+            ("p_at_x", self.base_vec_addrs.p_at_x.len()),
+        ];
+        for (field, len) in lens {
+            if len != expected {
+                return Err(BatchFRIShapeError::LengthMismatch { field, len, expected });
+            }
+        }
+        if self.acc_mult != F::one() {
+            return Err(BatchFRIShapeError::AccMult);
+        }
+        Ok(())
+    }
+}
+
 /// The event encoding the data of a single iteration within the batch FRI operation.
 /// For any given event, we are accessing a single element of the `Vec` inputs, so that the event
 /// is not a type alias for `BatchFRIIo` like many of the other events.
@@ -275,3 +364,74 @@ pub struct CommitPublicValuesInstr<F> {
 pub struct CommitPublicValuesEvent<F> {
     pub public_values: RecursionPublicValues<F>,
 }
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+
+    use super::*;
+
+    #[test]
+    fn test_alu_io_display() {
+        let block = |x: u32| Block([x, x + 1, x + 2, x + 3].map(BabyBear::from_canonical_u32));
+        let event: ExtAluEvent<BabyBear> = ExtAluIo { out: block(1), in1: block(5), in2: block(9) };
+        assert_eq!(event.to_string(), "out: [1, 2, 3, 4], in1: [5, 6, 7, 8], in2: [9, 10, 11, 12]");
+
+        let addr = |x: u32| Address(BabyBear::from_canonical_u32(x));
+        let addrs = BaseAluIo { out: addr(3), in1: addr(1), in2: addr(2) };
+        assert_eq!(addrs.to_string(), "out: @3, in1: @1, in2: @2");
+    }
+
+    #[test]
+    fn test_fri_fold_instr_validate() {
+        let addrs = |n: usize| vec![Address(BabyBear::zero()); n];
+        let mut instr = FriFoldInstr {
+            base_single_addrs: FriFoldBaseIo { x: Address(BabyBear::zero()) },
+            ext_single_addrs: FriFoldExtSingleIo {
+                z: Address(BabyBear::zero()),
+                alpha: Address(BabyBear::zero()),
+            },
+            ext_vec_addrs: FriFoldExtVecIo {
+                mat_opening: addrs(3),
+                ps_at_z: addrs(3),
+                alpha_pow_input: addrs(3),
+                ro_input: addrs(3),
+                alpha_pow_output: addrs(3),
+                ro_output: addrs(3),
+            },
+            alpha_pow_mults: vec![BabyBear::one(); 3],
+            ro_mults: vec![BabyBear::one(); 3],
+        };
+        assert_eq!(instr.validate(), Ok(()));
+
+        instr.ext_vec_addrs.ro_output.pop();
+        let err = instr.validate().unwrap_err();
+        assert_eq!(
+            err,
+            FriFoldShapeError::LengthMismatch { field: "ro_output", len: 2, expected: 3 }
+        );
+        assert_eq!(
+            err.to_string(),
+            "fri fold instruction has 2 `ro_output` entries, but 3 `ps_at_z` entries"
+        );
+    }
+
+    #[test]
+    fn test_batch_fri_instr_validate() {
+        let addrs = |n: usize| vec![Address(BabyBear::zero()); n];
+        let mut instr = BatchFRIInstr {
+            base_vec_addrs: BatchFRIBaseVecIo { p_at_x: addrs(4) },
+            ext_single_addrs: BatchFRIExtSingleIo { acc: Address(BabyBear::zero()) },
+            ext_vec_addrs: BatchFRIExtVecIo { p_at_z: addrs(4), alpha_pow: addrs(4) },
+            acc_mult: BabyBear::one(),
+        };
+        assert_eq!(instr.validate(), Ok(()));
+
+        instr.ext_vec_addrs.alpha_pow.push(Address(BabyBear::zero()));
+        assert_eq!(
+            instr.validate(),
+            Err(BatchFRIShapeError::LengthMismatch { field: "alpha_pow", len: 5, expected: 4 })
+        );
+    }
+}
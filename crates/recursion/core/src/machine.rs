@@ -1,7 +1,12 @@
-use std::ops::{Add, AddAssign};
+use std::{
+    collections::BTreeMap,
+    ops::{Add, AddAssign},
+};
 
 use hashbrown::HashMap;
+use p3_air::BaseAir;
 use p3_field::{extension::BinomiallyExtendable, PrimeField32};
+use sp1_core_machine::utils::next_power_of_two;
 use sp1_stark::{
     air::{InteractionScope, MachineAir},
     Chip, ProofShape, StarkGenericConfig, StarkMachine, PROOF_MAX_NUM_PVS,
@@ -170,6 +175,35 @@ impl<F: PrimeField32 + BinomiallyExtendable<D>, const DEGREE: usize> RecursionAi
     }
 
     pub fn heights(program: &RecursionProgram<F>) -> Vec<(String, usize)> {
+        Self::chip_heights(program).map(|(chip, height)| (chip.name(), height)).to_vec()
+    }
+
+    /// The number of rows and columns of each chip needed to prove `program`.
+    ///
+    /// The rows are padded like the traces are: to the fixed shape of the program if it has one,
+    /// and to the next power of two (at least 16) otherwise.
+    pub fn chip_stats(program: &RecursionProgram<F>) -> BTreeMap<String, ChipStats> {
+        Self::chip_heights(program)
+            .into_iter()
+            .map(|(chip, rows)| {
+                // The public values trace has a fixed height, whose log is what `chip_heights`
+                // reports for it.
+                let rows = match chip {
+                    Self::PublicValues(_) => 1 << PUB_VALUES_LOG_HEIGHT,
+                    _ => rows,
+                };
+                let stats = ChipStats {
+                    rows,
+                    padded_rows: next_power_of_two(rows, program.fixed_log2_rows(&chip)),
+                    width: chip.width(),
+                    preprocessed_width: chip.preprocessed_width(),
+                };
+                (chip.name(), stats)
+            })
+            .collect()
+    }
+
+    fn chip_heights(program: &RecursionProgram<F>) -> [(Self, usize); 9] {
         let heights = program
             .instructions
             .iter()
@@ -201,8 +235,27 @@ impl<F: PrimeField32 + BinomiallyExtendable<D>, const DEGREE: usize> RecursionAi
             ),
             (Self::PublicValues(PublicValuesChip), PUB_VALUES_LOG_HEIGHT),
         ]
-        .map(|(chip, log_height)| (chip.name(), log_height))
-        .to_vec()
+    }
+}
+
+/// The size of the trace of a chip for a recursion program, as reported by
+/// [`RecursionProgram::constraint_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChipStats {
+    /// The number of rows filled by the events of the program.
+    pub rows: usize,
+    /// The number of rows of the padded trace.
+    pub padded_rows: usize,
+    /// The number of main trace columns.
+    pub width: usize,
+    /// The number of preprocessed trace columns.
+    pub preprocessed_width: usize,
+}
+
+impl ChipStats {
+    /// The number of cells of the padded main and preprocessed traces.
+    pub fn cells(&self) -> usize {
+        self.padded_rows * (self.width + self.preprocessed_width)
     }
 }
 
@@ -1,4 +1,7 @@
-use std::borrow::Borrow;
+use std::{
+    borrow::Borrow,
+    fmt::{Display, Formatter},
+};
 
 use p3_field::{AbstractExtensionField, AbstractField};
 use serde::{Deserialize, Serialize};
@@ -22,6 +25,107 @@ pub enum Instruction<F> {
     Hint(HintInstr<F>),
 }
 
+/// Formats the instruction as a single line of the listing produced by
+/// [`RecursionProgram::disassemble`]: the opcode followed by its operand addresses and
+/// multiplicities.
+impl<F: Display + Copy> Display for Instruction<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::BaseAlu(BaseAluInstr { opcode, mult, addrs }) => {
+                write!(f, "{opcode:?} {addrs}, mult: {mult}")
+            }
+            Instruction::ExtAlu(ExtAluInstr { opcode, mult, addrs }) => {
+                write!(f, "{opcode:?} {addrs}, mult: {mult}")
+            }
+            Instruction::Mem(MemInstr { addrs, vals, mult, kind }) => {
+                write!(
+                    f,
+                    "Mem {kind:?} addr: {}, val: [{}], mult: {mult}",
+                    addrs.inner,
+                    list(&vals.inner.0)
+                )
+            }
+            Instruction::Poseidon2(instr) => write!(
+                f,
+                "Poseidon2 output: [{}], input: [{}], mults: [{}]",
+                list(&instr.addrs.output),
+                list(&instr.addrs.input),
+                list(&instr.mults)
+            ),
+            Instruction::Select(SelectInstr { addrs, mult1, mult2 }) => write!(
+                f,
+                "Select bit: {}, out1: {}, out2: {}, in1: {}, in2: {}, mult1: {mult1}, \
+                 mult2: {mult2}",
+                addrs.bit, addrs.out1, addrs.out2, addrs.in1, addrs.in2
+            ),
+            Instruction::ExpReverseBitsLen(ExpReverseBitsInstr { addrs, mult }) => write!(
+                f,
+                "ExpReverseBitsLen result: {}, base: {}, exp: [{}], mult: {mult}",
+                addrs.result,
+                addrs.base,
+                list(&addrs.exp)
+            ),
+            Instruction::HintBits(HintBitsInstr { output_addrs_mults, input_addr }) => write!(
+                f,
+                "HintBits outputs: [{}], input: {input_addr}",
+                list_with_mults(output_addrs_mults)
+            ),
+            Instruction::FriFold(instr) => write!(
+                f,
+                "FriFold x: {}, z: {}, alpha: {}, mat_opening: [{}], ps_at_z: [{}], \
+                 alpha_pow_input: [{}], ro_input: [{}], alpha_pow_output: [{}], \
+                 ro_output: [{}], alpha_pow_mults: [{}], ro_mults: [{}]",
+                instr.base_single_addrs.x,
+                instr.ext_single_addrs.z,
+                instr.ext_single_addrs.alpha,
+                list(&instr.ext_vec_addrs.mat_opening),
+                list(&instr.ext_vec_addrs.ps_at_z),
+                list(&instr.ext_vec_addrs.alpha_pow_input),
+                list(&instr.ext_vec_addrs.ro_input),
+                list(&instr.ext_vec_addrs.alpha_pow_output),
+                list(&instr.ext_vec_addrs.ro_output),
+                list(&instr.alpha_pow_mults),
+                list(&instr.ro_mults)
+            ),
+            Instruction::BatchFRI(instr) => write!(
+                f,
+                "BatchFRI acc: {}, p_at_z: [{}], alpha_pow: [{}], p_at_x: [{}], acc_mult: {}",
+                instr.ext_single_addrs.acc,
+                list(&instr.ext_vec_addrs.p_at_z),
+                list(&instr.ext_vec_addrs.alpha_pow),
+                list(&instr.base_vec_addrs.p_at_x),
+                instr.acc_mult
+            ),
+            Instruction::Print(PrintInstr { field_elt_type, addr }) => {
+                write!(f, "Print {field_elt_type:?} addr: {addr}")
+            }
+            Instruction::HintExt2Felts(HintExt2FeltsInstr { output_addrs_mults, input_addr }) => {
+                write!(
+                    f,
+                    "HintExt2Felts outputs: [{}], input: {input_addr}",
+                    list_with_mults(output_addrs_mults)
+                )
+            }
+            Instruction::CommitPublicValues(instr) => {
+                write!(f, "CommitPublicValues pv_addrs: [{}]", list(&instr.pv_addrs.as_array()))
+            }
+            Instruction::Hint(HintInstr { output_addrs_mults }) => {
+                write!(f, "Hint outputs: [{}]", list_with_mults(output_addrs_mults))
+            }
+        }
+    }
+}
+
+/// Joins the items with `, `, for use inside the brackets of a listed operand.
+fn list<T: Display>(items: &[T]) -> String {
+    items.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+/// Lists `(address, multiplicity)` pairs as `addr x mult`.
+fn list_with_mults<F: Display>(items: &[(Address<F>, F)]) -> String {
+    items.iter().map(|(addr, mult)| format!("{addr} x {mult}")).collect::<Vec<_>>().join(", ")
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct HintBitsInstr<F> {
     /// Addresses and mults of the output bits.
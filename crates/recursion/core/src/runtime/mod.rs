@@ -163,6 +163,10 @@ pub enum RuntimeError<F: Debug, EF: Debug> {
     DebugPrint(#[from] std::io::Error),
     #[error("attempted to read from empty witness stream")]
     EmptyWitnessStream,
+    #[error("invalid fri fold instruction at pc {pc:?}: {source}")]
+    FriFoldShape { source: FriFoldShapeError, pc: usize },
+    #[error("invalid batch fri instruction at pc {pc:?}: {source}")]
+    BatchFRIShape { source: BatchFRIShapeError, pc: usize },
 }
 
 impl<'a, F: PrimeField32, EF: ExtensionField<F>, Diffusion> Runtime<'a, F, EF, Diffusion>
@@ -417,6 +421,10 @@ where
                 }
 
                 Instruction::FriFold(instr) => {
+                    instr.validate().map_err(|source| RuntimeError::FriFoldShape {
+                        source,
+                        pc: self.pc.as_canonical_u32() as usize,
+                    })?;
                     let FriFoldInstr {
                         base_single_addrs,
                         ext_single_addrs,
@@ -491,6 +499,10 @@ where
                     }
                 }
                 Instruction::BatchFRI(instr) => {
+                    instr.validate().map_err(|source| RuntimeError::BatchFRIShape {
+                        source,
+                        pc: self.pc.as_canonical_u32() as usize,
+                    })?;
                     let BatchFRIInstr { base_vec_addrs, ext_single_addrs, ext_vec_addrs, acc_mult } =
                         *instr;
 
@@ -1,10 +1,15 @@
+use std::collections::BTreeMap;
+
 use backtrace::Backtrace;
-use p3_field::Field;
+use p3_field::{extension::BinomiallyExtendable, Field, PrimeField32};
 use serde::{Deserialize, Serialize};
 use shape::RecursionShape;
 use sp1_stark::air::{MachineAir, MachineProgram};
 
-use crate::*;
+use crate::{
+    machine::{ChipStats, RecursionAir},
+    *,
+};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RecursionProgram<F> {
@@ -34,4 +39,98 @@ impl<F: Field> RecursionProgram<F> {
             })
             .copied()
     }
+
+    /// Lists the compiled instructions in order, one per line, prefixed with their index.
+    ///
+    /// Each line gives the opcode with its operand addresses and multiplicities. This is meant for
+    /// debugging the output of the recursion compiler; the format is not stable.
+    pub fn disassemble(&self) -> String {
+        let width = self.instructions.len().saturating_sub(1).to_string().len();
+        self.instructions
+            .iter()
+            .enumerate()
+            .map(|(i, instruction)| format!("{i:>width$}: {instruction}\n"))
+            .collect()
+    }
+}
+
+impl<F: PrimeField32 + BinomiallyExtendable<D>> RecursionProgram<F> {
+    /// Reports the number of rows and columns each chip needs to prove this program on a recursion
+    /// machine of constraint degree `DEGREE`, keyed by chip name.
+    ///
+    /// See [`RecursionAir::chip_stats`] for how the rows are padded.
+    pub fn constraint_stats<const DEGREE: usize>(&self) -> BTreeMap<String, ChipStats> {
+        RecursionAir::<F, DEGREE>::chip_stats(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+
+    use super::*;
+    use crate::runtime::instruction as instr;
+
+    #[test]
+    fn test_disassemble() {
+        let program = RecursionProgram::<BabyBear> {
+            instructions: vec![
+                instr::mem(MemAccessKind::Write, 2, 0, 3),
+                instr::mem(MemAccessKind::Write, 1, 1, 4),
+                instr::base_alu(BaseAluOpcode::MulF, 1, 2, 0, 1),
+                instr::ext_alu(ExtAluOpcode::AddE, 0, 3, 0, 0),
+                instr::mem(MemAccessKind::Read, 1, 2, 12),
+            ],
+            ..Default::default()
+        };
+
+        let listing = program.disassemble();
+        let lines = listing.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "0: Mem Write addr: @0, val: [3, 0, 0, 0], mult: 2",
+                "1: Mem Write addr: @1, val: [4, 0, 0, 0], mult: 1",
+                "2: MulF out: @2, in1: @0, in2: @1, mult: 1",
+                "3: AddE out: @3, in1: @0, in2: @0, mult: 0",
+                "4: Mem Read addr: @2, val: [12, 0, 0, 0], mult: 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_constraint_stats() {
+        let mut program = RecursionProgram::<BabyBear> {
+            instructions: vec![
+                instr::mem(MemAccessKind::Write, 2, 0, 3),
+                instr::mem(MemAccessKind::Write, 1, 1, 4),
+                instr::base_alu(BaseAluOpcode::MulF, 1, 2, 0, 1),
+                instr::ext_alu(ExtAluOpcode::AddE, 0, 3, 0, 0),
+                instr::mem(MemAccessKind::Read, 1, 2, 12),
+            ],
+            ..Default::default()
+        };
+
+        let stats = program.constraint_stats::<3>();
+        let rows = stats
+            .iter()
+            .filter(|(_, stats)| stats.rows > 0)
+            .map(|(name, stats)| (name.as_str(), stats.rows))
+            .collect::<Vec<_>>();
+        assert_eq!(rows, [("BaseAlu", 1), ("ExtAlu", 1), ("MemoryConst", 2), ("PublicValues", 16)]);
+        // Without a fixed shape, every trace is padded to the minimum of 16 rows.
+        assert_eq!(stats.len(), 9);
+        assert_eq!(stats.values().map(|stats| stats.padded_rows).sum::<usize>(), 9 * 16);
+        for stats in stats.values() {
+            assert!(stats.width > 0);
+            assert_eq!(stats.cells(), 16 * (stats.width + stats.preprocessed_width));
+        }
+
+        // With a fixed shape, the traces are padded to it.
+        let shape = RecursionAir::<BabyBear, 3>::shrink_shape();
+        program.shape = Some(shape.clone());
+        for (name, stats) in program.constraint_stats::<3>() {
+            assert_eq!(stats.padded_rows, 1 << shape.inner[&name]);
+        }
+    }
 }
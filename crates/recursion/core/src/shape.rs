@@ -1,4 +1,8 @@
-use std::marker::PhantomData;
+use std::{
+    cmp::Reverse,
+    fmt::{Display, Formatter},
+    marker::PhantomData,
+};
 
 use hashbrown::HashMap;
 
@@ -6,6 +10,7 @@ use itertools::Itertools;
 use p3_field::{extension::BinomiallyExtendable, PrimeField32};
 use serde::{Deserialize, Serialize};
 use sp1_stark::{air::MachineAir, ProofShape};
+use thiserror::Error;
 
 use crate::{
     chips::{
@@ -27,6 +32,37 @@ pub struct RecursionShape {
     pub(crate) inner: HashMap<String, usize>,
 }
 
+/// A chip whose height exceeds its allowed height in a shape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverBudgetChip {
+    /// The name of the chip.
+    pub name: String,
+    /// The number of rows the program needs in this chip.
+    pub height: usize,
+    /// The log height allowed for the chip by the closest shape.
+    pub allowed_log_height: usize,
+}
+
+impl Display for OverBudgetChip {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} needs {} rows but at most 2^{} are allowed",
+            self.name, self.height, self.allowed_log_height
+        )
+    }
+}
+
+/// An error returned when a program does not fit any of the allowed recursion shapes.
+///
+/// The chips listed are the over-budget ones of the allowed shape that the program misses by the
+/// fewest chips, preferring the shape that allows them the most rows.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("no allowed recursion shape fits the program: {}", over_budget.iter().join("; "))]
+pub struct ShapeTooLarge {
+    pub over_budget: Vec<OverBudgetChip>,
+}
+
 pub struct RecursionShapeConfig<F, A> {
     allowed_shapes: Vec<HashMap<String, usize>>,
     _marker: PhantomData<(F, A)>,
@@ -35,39 +71,84 @@ pub struct RecursionShapeConfig<F, A> {
 impl<F: PrimeField32 + BinomiallyExtendable<D>, const DEGREE: usize>
     RecursionShapeConfig<F, RecursionAir<F, DEGREE>>
 {
+    /// Set the shape of the program to an allowed shape that fits it.
+    ///
+    /// Panics if the program does not fit any allowed shape; see [`Self::try_fix_shape`].
     pub fn fix_shape(&self, program: &mut RecursionProgram<F>) {
+        if let Err(err) = self.try_fix_shape(program) {
+            panic!("{err}");
+        }
+    }
+
+    /// Set the shape of the program to an allowed shape that fits it, or report the chips that
+    /// are too large if there is no such shape.
+    pub fn try_fix_shape(&self, program: &mut RecursionProgram<F>) -> Result<(), ShapeTooLarge> {
         let heights = RecursionAir::<F, DEGREE>::heights(program);
         // Get the allowed shape with a minimal hamming distance from the current shape.
         let mut min_distance = usize::MAX;
         let mut closest_shape = None;
+        // The over-budget chips of the invalid shape with the fewest of them.
+        let mut min_over_budget: Option<Vec<OverBudgetChip>> = None;
         for shape in self.allowed_shapes.iter() {
             let mut distance = 0;
-            let mut is_valid = true;
+            let mut over_budget = Vec::new();
             for (name, height) in heights.iter() {
                 let next_power_of_two = height.next_power_of_two();
-                let allowed_log_height = shape.get(name).unwrap();
+                let allowed_log_height = *shape.get(name).unwrap();
                 let allowed_height = 1 << allowed_log_height;
                 if next_power_of_two != allowed_height {
                     distance += 1;
                 }
                 if next_power_of_two > allowed_height {
-                    is_valid = false;
+                    over_budget.push(OverBudgetChip {
+                        name: name.clone(),
+                        height: *height,
+                        allowed_log_height,
+                    });
                 }
             }
-            if is_valid && distance < min_distance {
-                min_distance = distance;
-                closest_shape = Some(shape.clone());
+            if over_budget.is_empty() {
+                if distance < min_distance {
+                    min_distance = distance;
+                    closest_shape = Some(shape.clone());
+                }
+            } else if min_over_budget
+                .as_ref()
+                .map_or(true, |min| Self::budget_key(&over_budget) < Self::budget_key(min))
+            {
+                min_over_budget = Some(over_budget);
             }
         }
 
-        if let Some(shape) = closest_shape {
-            let shape = RecursionShape { inner: shape };
-            program.shape = Some(shape);
-        } else {
-            panic!("no shape found for heights: {:?}", heights);
+        match closest_shape {
+            Some(shape) => {
+                program.shape = Some(RecursionShape { inner: shape });
+                Ok(())
+            }
+            None => Err(ShapeTooLarge { over_budget: min_over_budget.unwrap_or_default() }),
         }
     }
 
+    /// Orders the over-budget chips of two shapes: fewer chips first, then the more generous
+    /// allowed heights for them.
+    fn budget_key(over_budget: &[OverBudgetChip]) -> (usize, Reverse<usize>) {
+        let allowed_rows = over_budget.iter().map(|chip| 1usize << chip.allowed_log_height).sum();
+        (over_budget.len(), Reverse(allowed_rows))
+    }
+
+    /// The allowed shape with the most rows in total.
+    pub fn largest_shape(&self) -> RecursionShape {
+        let inner = self
+            .allowed_shapes
+            .iter()
+            .max_by_key(|shape| {
+                shape.values().map(|log_height| 1usize << log_height).sum::<usize>()
+            })
+            .cloned()
+            .unwrap_or_default();
+        RecursionShape { inner }
+    }
+
     pub fn get_all_shape_combinations(
         &self,
         batch_size: usize,
@@ -240,3 +321,39 @@ impl<F: PrimeField32 + BinomiallyExtendable<D>, const DEGREE: usize> Default
         Self { allowed_shapes, _marker: PhantomData }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+
+    use super::*;
+    use crate::runtime::instruction as instr;
+
+    type A = RecursionAir<BabyBear, 3>;
+
+    #[test]
+    fn test_try_fix_shape_too_large() {
+        let shape_config = RecursionShapeConfig::<BabyBear, A>::default();
+        let select_log_height = shape_config.largest_shape().inner["Select"];
+
+        let mut program = RecursionProgram::<BabyBear> {
+            instructions: vec![instr::select(1, 1, 0, 1, 2, 3, 4); (1 << select_log_height) + 1],
+            ..Default::default()
+        };
+        let err = shape_config.try_fix_shape(&mut program).unwrap_err();
+        assert_eq!(
+            err.over_budget,
+            vec![OverBudgetChip {
+                name: "Select".to_string(),
+                height: (1 << select_log_height) + 1,
+                allowed_log_height: select_log_height,
+            }]
+        );
+        assert!(err.to_string().contains("Select needs"));
+        assert!(program.shape.is_none());
+
+        program.instructions.truncate(1 << select_log_height);
+        shape_config.try_fix_shape(&mut program).unwrap();
+        assert!(program.shape.is_some());
+    }
+}
@@ -109,11 +109,15 @@ pub fn outer_fri_config_with_blowup(log_blowup: usize) -> FriConfig<OuterChallen
 pub struct BabyBearPoseidon2Outer {
     pub perm: OuterPerm,
     pub pcs: OuterPcs,
+    fri_queries: Option<usize>,
 }
 
 impl Clone for BabyBearPoseidon2Outer {
     fn clone(&self) -> Self {
-        Self::new()
+        match self.fri_queries {
+            Some(num_queries) => Self::new().with_fri_queries(num_queries),
+            None => Self::new(),
+        }
     }
 }
 
@@ -141,7 +145,7 @@ impl BabyBearPoseidon2Outer {
         let dft = OuterDft {};
         let fri_config = outer_fri_config();
         let pcs = OuterPcs::new(27, dft, val_mmcs, fri_config);
-        Self { pcs, perm }
+        Self { pcs, perm, fri_queries: None }
     }
     pub fn new_with_log_blowup(log_blowup: usize) -> Self {
         let perm = outer_perm();
@@ -151,7 +155,27 @@ impl BabyBearPoseidon2Outer {
         let dft = OuterDft {};
         let fri_config = outer_fri_config_with_blowup(log_blowup);
         let pcs = OuterPcs::new(27, dft, val_mmcs, fri_config);
-        Self { pcs, perm }
+        Self { pcs, perm, fri_queries: None }
+    }
+
+    /// Overrides the number of FRI queries, keeping the rest of the FRI config unchanged.
+    ///
+    /// This is for development only: fewer queries means less soundness, and the resulting proofs
+    /// do not match the circuits built with the default config.
+    pub fn with_fri_queries(self, num_queries: usize) -> Self {
+        let hash = OuterHash::new(self.perm.clone()).unwrap();
+        let compress = OuterCompress::new(self.perm.clone());
+        let val_mmcs = OuterValMmcs::new(hash, compress);
+        let dft = OuterDft {};
+        let fri_config = self.pcs.fri_config();
+        let fri_config = FriConfig {
+            log_blowup: fri_config.log_blowup,
+            num_queries,
+            proof_of_work_bits: fri_config.proof_of_work_bits,
+            mmcs: fri_config.mmcs.clone(),
+        };
+        let pcs = OuterPcs::new(27, dft, val_mmcs, fri_config);
+        Self { pcs, perm: self.perm, fri_queries: Some(num_queries) }
     }
 }
 
@@ -58,6 +58,58 @@ func ProvePlonkBn254(dataDir *C.char, witnessPath *C.char) *C.C_PlonkBn254Proof
 	return structPtr
 }
 
+var plonkArtifactsMutex = &sync.Mutex{}
+var plonkArtifacts = make(map[C.ulonglong]*sp1.PlonkArtifacts)
+var nextPlonkArtifactsHandle C.ulonglong = 1
+
+//export LoadPlonkBn254
+func LoadPlonkBn254(dataDir *C.char) C.ulonglong {
+	dataDirString := C.GoString(dataDir)
+
+	artifacts := sp1.LoadPlonkArtifacts(dataDirString)
+
+	// Go pointers can't be held by C, so the artifacts are referenced through a handle.
+	plonkArtifactsMutex.Lock()
+	defer plonkArtifactsMutex.Unlock()
+	handle := nextPlonkArtifactsHandle
+	nextPlonkArtifactsHandle++
+	plonkArtifacts[handle] = artifacts
+	return handle
+}
+
+//export ProvePlonkBn254WithArtifacts
+func ProvePlonkBn254WithArtifacts(handle C.ulonglong, witnessPath *C.char) *C.C_PlonkBn254Proof {
+	witnessPathString := C.GoString(witnessPath)
+
+	plonkArtifactsMutex.Lock()
+	artifacts, ok := plonkArtifacts[handle]
+	plonkArtifactsMutex.Unlock()
+	if !ok {
+		panic("unknown plonk artifacts handle")
+	}
+
+	sp1PlonkBn254Proof := sp1.ProvePlonkWithArtifacts(artifacts, witnessPathString)
+
+	ms := C.malloc(C.sizeof_C_PlonkBn254Proof)
+	if ms == nil {
+		return nil
+	}
+
+	structPtr := (*C.C_PlonkBn254Proof)(ms)
+	structPtr.PublicInputs[0] = C.CString(sp1PlonkBn254Proof.PublicInputs[0])
+	structPtr.PublicInputs[1] = C.CString(sp1PlonkBn254Proof.PublicInputs[1])
+	structPtr.EncodedProof = C.CString(sp1PlonkBn254Proof.EncodedProof)
+	structPtr.RawProof = C.CString(sp1PlonkBn254Proof.RawProof)
+	return structPtr
+}
+
+//export FreePlonkBn254Artifacts
+func FreePlonkBn254Artifacts(handle C.ulonglong) {
+	plonkArtifactsMutex.Lock()
+	delete(plonkArtifacts, handle)
+	plonkArtifactsMutex.Unlock()
+}
+
 //export FreePlonkBn254Proof
 func FreePlonkBn254Proof(proof *C.C_PlonkBn254Proof) {
 	C.free(unsafe.Pointer(proof.EncodedProof))
@@ -21,12 +21,24 @@ var globalR1csInitialized = false
 var globalPk groth16.ProvingKey = groth16.NewProvingKey(ecc.BN254)
 var globalPkInitialized = false
 
+// PlonkArtifacts holds the PLONK circuit and keys read from a build directory, so that several
+// witnesses can be proven without reading them again.
+type PlonkArtifacts struct {
+	DataDir string
+	Scs     constraint.ConstraintSystem
+	Pk      plonk.ProvingKey
+	Vk      plonk.VerifyingKey
+}
+
 func ProvePlonk(dataDir string, witnessPath string) Proof {
+	return ProvePlonkWithArtifacts(LoadPlonkArtifacts(dataDir), witnessPath)
+}
+
+func LoadPlonkArtifacts(dataDir string) *PlonkArtifacts {
 	// Sanity check the required arguments have been provided.
 	if dataDir == "" {
 		panic("dataDirStr is required")
 	}
-	os.Setenv("CONSTRAINTS_JSON", dataDir+"/"+constraintsJsonFile)
 
 	// Read the R1CS.
 	scsFile, err := os.Open(dataDir + "/" + plonkCircuitPath)
@@ -56,6 +68,12 @@ func ProvePlonk(dataDir string, witnessPath string) Proof {
 	vk.ReadFrom(vkFile)
 	defer vkFile.Close()
 
+	return &PlonkArtifacts{DataDir: dataDir, Scs: scs, Pk: pk, Vk: vk}
+}
+
+func ProvePlonkWithArtifacts(artifacts *PlonkArtifacts, witnessPath string) Proof {
+	os.Setenv("CONSTRAINTS_JSON", artifacts.DataDir+"/"+constraintsJsonFile)
+
 	// Read the file.
 	data, err := os.ReadFile(witnessPath)
 	if err != nil {
@@ -81,13 +99,13 @@ func ProvePlonk(dataDir string, witnessPath string) Proof {
 	}
 
 	// Generate the proof.
-	proof, err := plonk.Prove(scs, pk, witness)
+	proof, err := plonk.Prove(artifacts.Scs, artifacts.Pk, witness)
 	if err != nil {
 		panic(err)
 	}
 
 	// Verify proof.
-	err = plonk.Verify(proof, vk, publicWitness)
+	err = plonk.Verify(proof, artifacts.Vk, publicWitness)
 	if err != nil {
 		panic(err)
 	}
@@ -82,6 +82,26 @@ pub fn prove_plonk_bn254(data_dir: &str, witness_path: &str) -> PlonkBn254Proof
     }
 }
 
+/// PLONK circuit artifacts for the docker backend.
+///
+/// Each docker run is a separate process, so the artifacts can't be kept in memory between
+/// proofs: this only records the build directory, which is read again on every proof.
+#[derive(Debug)]
+pub struct PlonkBn254Artifacts {
+    data_dir: String,
+}
+
+pub fn load_plonk_bn254(data_dir: &str) -> PlonkBn254Artifacts {
+    PlonkBn254Artifacts { data_dir: data_dir.to_string() }
+}
+
+pub fn prove_plonk_bn254_with_artifacts(
+    artifacts: &PlonkBn254Artifacts,
+    witness_path: &str,
+) -> PlonkBn254Proof {
+    prove_plonk_bn254(&artifacts.data_dir, witness_path)
+}
+
 pub fn prove_groth16_bn254(data_dir: &str, witness_path: &str) -> Groth16Bn254Proof {
     let result =
         prove(ProofSystem::Groth16, data_dir, witness_path).expect("failed to prove with docker");
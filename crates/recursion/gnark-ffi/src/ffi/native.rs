@@ -153,6 +153,38 @@ pub fn prove_plonk_bn254(data_dir: &str, witness_path: &str) -> PlonkBn254Proof
     }
 }
 
+/// PLONK circuit artifacts held in memory by the Go library, released when dropped.
+#[derive(Debug)]
+pub struct PlonkBn254Artifacts {
+    handle: u64,
+}
+
+pub fn load_plonk_bn254(data_dir: &str) -> PlonkBn254Artifacts {
+    let data_dir = CString::new(data_dir).expect("CString::new failed");
+    let handle = unsafe { bind::LoadPlonkBn254(data_dir.as_ptr() as *mut c_char) };
+    PlonkBn254Artifacts { handle }
+}
+
+pub fn prove_plonk_bn254_with_artifacts(
+    artifacts: &PlonkBn254Artifacts,
+    witness_path: &str,
+) -> PlonkBn254Proof {
+    let witness_path = CString::new(witness_path).expect("CString::new failed");
+    unsafe {
+        let proof = bind::ProvePlonkBn254WithArtifacts(
+            artifacts.handle,
+            witness_path.as_ptr() as *mut c_char,
+        );
+        PlonkBn254Proof::from_raw(proof)
+    }
+}
+
+impl Drop for PlonkBn254Artifacts {
+    fn drop(&mut self) {
+        unsafe { bind::FreePlonkBn254Artifacts(self.handle) }
+    }
+}
+
 pub fn verify_plonk_bn254(
     data_dir: &str,
     proof: &str,
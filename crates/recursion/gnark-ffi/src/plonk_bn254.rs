@@ -5,7 +5,10 @@ use std::{
 };
 
 use crate::{
-    ffi::{build_plonk_bn254, prove_plonk_bn254, test_plonk_bn254, verify_plonk_bn254},
+    ffi::{
+        build_plonk_bn254, load_plonk_bn254, prove_plonk_bn254, prove_plonk_bn254_with_artifacts,
+        test_plonk_bn254, verify_plonk_bn254, PlonkBn254Artifacts,
+    },
     witness::GnarkWitness,
     PlonkBn254Proof,
 };
@@ -22,6 +25,37 @@ use sp1_recursion_compiler::{
 #[derive(Debug, Clone)]
 pub struct PlonkBn254Prover;
 
+/// The PLONK circuit artifacts of a build directory, loaded once and reused across proofs.
+///
+/// With the `native` feature, the parsed constraint system and proving key are kept in memory
+/// until this is dropped. The docker backend can't keep them between proofs, so it reads the
+/// build directory again for each proof.
+#[derive(Debug)]
+pub struct LoadedPlonkArtifacts {
+    build_dir: PathBuf,
+    vkey_hash: [u8; 32],
+    artifacts: PlonkBn254Artifacts,
+}
+
+impl LoadedPlonkArtifacts {
+    /// Loads the PLONK artifacts from a build directory.
+    ///
+    /// Only the `native` feature parses the constraint system and proving key here, so that
+    /// [`PlonkBn254Prover::prove_with_loaded`] skips loading them. With the default docker backend
+    /// this only records the build directory, and every proof still reads the artifacts from disk.
+    pub fn load(build_dir: impl Into<PathBuf>) -> Self {
+        let build_dir = build_dir.into();
+        let vkey_hash = PlonkBn254Prover::get_vkey_hash(&build_dir);
+        let artifacts = load_plonk_bn254(build_dir.to_str().unwrap());
+        Self { build_dir, vkey_hash, artifacts }
+    }
+
+    /// The build directory the artifacts were loaded from.
+    pub fn build_dir(&self) -> &Path {
+        &self.build_dir
+    }
+}
+
 impl PlonkBn254Prover {
     /// Creates a new [PlonkBn254Prover].
     pub fn new() -> Self {
@@ -100,6 +134,27 @@ impl PlonkBn254Prover {
         proof
     }
 
+    /// Generates a PLONK proof given a witness, using artifacts loaded with
+    /// [`LoadedPlonkArtifacts::load`] instead of reading them from the build directory.
+    pub fn prove_with_loaded<C: Config>(
+        &self,
+        witness: Witness<C>,
+        artifacts: &LoadedPlonkArtifacts,
+    ) -> PlonkBn254Proof {
+        // Write witness.
+        let mut witness_file = tempfile::NamedTempFile::new().unwrap();
+        let gnark_witness = GnarkWitness::new(witness);
+        let serialized = serde_json::to_string(&gnark_witness).unwrap();
+        witness_file.write_all(serialized.as_bytes()).unwrap();
+
+        let mut proof = prove_plonk_bn254_with_artifacts(
+            &artifacts.artifacts,
+            witness_file.path().to_str().unwrap(),
+        );
+        proof.plonk_vkey_hash = artifacts.vkey_hash;
+        proof
+    }
+
     /// Verify a PLONK proof and verify that the supplied vkey_hash and committed_values_digest
     /// match.
     pub fn verify(
@@ -123,6 +178,23 @@ impl PlonkBn254Prover {
         .expect("failed to verify proof")
     }
 
+    /// Verify a PLONK proof like [`Self::verify`], but against the contents of a `plonk_vk.bin`
+    /// file instead of a full build directory.
+    ///
+    /// Verification only reads the verifying key, so verifiers can ship this small file without
+    /// the proving artifacts.
+    pub fn verify_with_vk(
+        &self,
+        proof: &PlonkBn254Proof,
+        vkey_bytes: &[u8],
+        vkey_hash: &BigUint,
+        committed_values_digest: &BigUint,
+    ) {
+        let vk_dir = tempfile::tempdir().unwrap();
+        std::fs::write(vk_dir.path().join("plonk_vk.bin"), vkey_bytes).unwrap();
+        self.verify(proof, vkey_hash, committed_values_digest, vk_dir.path());
+    }
+
     /// Modify the PlonkVerifier so that it works with the SP1Verifier.
     fn modify_plonk_verifier(file_path: &Path) {
         let mut content = String::new();
@@ -1,4 +1,8 @@
+use std::str::FromStr;
+
+use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
+use sp1_primitives::io::SP1PublicValues;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProofBn254 {
@@ -21,3 +25,153 @@ pub struct Groth16Bn254Proof {
     pub raw_proof: String,
     pub groth16_vkey_hash: [u8; 32],
 }
+
+impl PlonkBn254Proof {
+    /// The selector of `Verify(bytes,uint256[])` on the generated `PlonkVerifier` contract.
+    pub const VERIFY_SELECTOR: [u8; 4] = [0x7e, 0x4f, 0x7a, 0x8a];
+
+    /// ABI-encodes a call to `Verify(bytes proof, uint256[] public_inputs)` on the generated
+    /// `PlonkVerifier` contract, with `[vkey_hash, committed_values_digest]` as public inputs.
+    pub fn to_evm_calldata(
+        &self,
+        vkey_hash: &BigUint,
+        committed_values_digest: &BigUint,
+    ) -> Vec<u8> {
+        let proof = hex::decode(&self.encoded_proof).expect("invalid encoded proof");
+        let padded_len = proof.len().div_ceil(32) * 32;
+
+        let mut calldata = Self::VERIFY_SELECTOR.to_vec();
+        // The head holds the offsets of the two dynamic arguments, counted from its start.
+        push_uint256(&mut calldata, &BigUint::from(64u32));
+        push_uint256(&mut calldata, &BigUint::from(96 + padded_len));
+        // The proof bytes, length-prefixed and right-padded to a whole word.
+        push_uint256(&mut calldata, &BigUint::from(proof.len()));
+        calldata.extend_from_slice(&proof);
+        calldata.resize(calldata.len() + padded_len - proof.len(), 0);
+        // The public inputs, length-prefixed.
+        push_uint256(&mut calldata, &BigUint::from(2u32));
+        push_uint256(&mut calldata, vkey_hash);
+        push_uint256(&mut calldata, committed_values_digest);
+        calldata
+    }
+
+    /// Whether the public inputs of the proof are `vkey_hash` and the committed values digest of
+    /// `public_values`, as computed by [`SP1PublicValues::hash_bn254`].
+    ///
+    /// This only checks the public inputs, not the proof itself, so it does not need the circuit
+    /// artifacts.
+    pub fn public_values_match(
+        &self,
+        public_values: &SP1PublicValues,
+        vkey_hash: &BigUint,
+    ) -> bool {
+        let [proof_vkey_hash, committed_values_digest] =
+            self.public_inputs.each_ref().map(|input| BigUint::from_str(input).ok());
+        proof_vkey_hash.as_ref() == Some(vkey_hash)
+            && committed_values_digest == Some(public_values.hash_bn254())
+    }
+}
+
+impl Groth16Bn254Proof {
+    /// The selector of `Verify(uint256[8],uint256[2])` on the generated `Groth16Verifier`
+    /// contract.
+    pub const VERIFY_SELECTOR: [u8; 4] = [0xed, 0xdf, 0x24, 0x3c];
+
+    /// ABI-encodes a call to `Verify(uint256[8] proof, uint256[2] input)` on the generated
+    /// `Groth16Verifier` contract, with `[vkey_hash, committed_values_digest]` as inputs.
+    pub fn to_evm_calldata(
+        &self,
+        vkey_hash: &BigUint,
+        committed_values_digest: &BigUint,
+    ) -> Vec<u8> {
+        let proof = hex::decode(&self.encoded_proof).expect("invalid encoded proof");
+        assert_eq!(proof.len(), 8 * 32, "encoded groth16 proof must be 8 words");
+
+        let mut calldata = Self::VERIFY_SELECTOR.to_vec();
+        calldata.extend_from_slice(&proof);
+        push_uint256(&mut calldata, vkey_hash);
+        push_uint256(&mut calldata, committed_values_digest);
+        calldata
+    }
+}
+
+/// Appends `value` to `calldata` as a big-endian ABI `uint256` word.
+fn push_uint256(calldata: &mut Vec<u8>, value: &BigUint) {
+    let bytes = value.to_bytes_be();
+    assert!(bytes.len() <= 32, "value does not fit in a uint256");
+    calldata.resize(calldata.len() + 32 - bytes.len(), 0);
+    calldata.extend_from_slice(&bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use num_bigint::BigUint;
+    use sp1_primitives::io::SP1PublicValues;
+
+    use super::{Groth16Bn254Proof, PlonkBn254Proof};
+
+    fn public_inputs() -> (BigUint, BigUint) {
+        let vkey_hash = BigUint::from_str(
+            "4388110346487227347127227015298727640003416451227418545006017788474735102",
+        )
+        .unwrap();
+        let committed_values_digest = BigUint::from_str(
+            "11548637573012870512203096734049651374183012513925018620924580453478128213452",
+        )
+        .unwrap();
+        (vkey_hash, committed_values_digest)
+    }
+
+    fn fixture(hex: &str) -> Vec<u8> {
+        hex::decode(hex.trim()).unwrap()
+    }
+
+    #[test]
+    fn test_plonk_evm_calldata() {
+        // A proof whose length is not a multiple of 32 exercises the padding.
+        let proof = PlonkBn254Proof {
+            encoded_proof: hex::encode((1..=70).collect::<Vec<u8>>()),
+            ..Default::default()
+        };
+        let (vkey_hash, committed_values_digest) = public_inputs();
+        assert_eq!(
+            proof.to_evm_calldata(&vkey_hash, &committed_values_digest),
+            fixture(include_str!("../fixtures/plonk_calldata.hex"))
+        );
+    }
+
+    #[test]
+    fn test_plonk_public_values_match() {
+        let mut public_values = SP1PublicValues::new();
+        public_values.write(&42u32);
+        let (vkey_hash, _) = public_inputs();
+        let proof = PlonkBn254Proof {
+            public_inputs: [vkey_hash.to_string(), public_values.hash_bn254().to_string()],
+            ..Default::default()
+        };
+        assert!(proof.public_values_match(&public_values, &vkey_hash));
+
+        let mut other_public_values = SP1PublicValues::new();
+        other_public_values.write(&43u32);
+        assert!(!proof.public_values_match(&other_public_values, &vkey_hash));
+        assert!(!proof.public_values_match(&public_values, &(vkey_hash + 1u32)));
+        assert!(
+            !PlonkBn254Proof::default().public_values_match(&public_values, &BigUint::default())
+        );
+    }
+
+    #[test]
+    fn test_groth16_evm_calldata() {
+        let proof = Groth16Bn254Proof {
+            encoded_proof: hex::encode((0..256).map(|i| (i * 7 + 3) as u8).collect::<Vec<_>>()),
+            ..Default::default()
+        };
+        let (vkey_hash, committed_values_digest) = public_inputs();
+        assert_eq!(
+            proof.to_evm_calldata(&vkey_hash, &committed_values_digest),
+            fixture(include_str!("../fixtures/groth16_calldata.hex"))
+        );
+    }
+}
@@ -97,6 +97,28 @@ impl ProverClientBuilder {
         CpuProverBuilder { mock: false }
     }
 
+    /// Builds a [`CpuProver`] for local CPU proving.
+    ///
+    /// # Details
+    /// This is an alias for [`ProverClientBuilder::cpu`], matching the `local` value accepted by
+    /// the `SP1_PROVER` environment variable.
+    ///
+    /// # Usage
+    /// ```no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, Prover};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let prover = ProverClient::builder().local().build();
+    /// let (pk, vk) = prover.setup(elf);
+    /// let proof = prover.prove(&pk, &stdin).compressed().run().unwrap();
+    /// ```
+    #[must_use]
+    pub fn local(&self) -> CpuProverBuilder {
+        self.cpu()
+    }
+
     /// Builds a [`CudaProver`] specifically for local proving on NVIDIA GPUs.
     ///
     /// # Example
@@ -2,6 +2,8 @@
 //!
 //! This module provides a builder for simulating the execution of a program on the CPU.
 
+use std::ops::Range;
+
 use anyhow::Result;
 use sp1_core_executor::{ExecutionReport, HookEnv, SP1ContextBuilder};
 use sp1_core_machine::io::SP1Stdin;
@@ -84,6 +86,34 @@ impl<'a> CpuExecuteBuilder<'a> {
         self
     }
 
+    /// Set the number of cpu cycles above which a high cycle count warning is logged.
+    ///
+    /// # Arguments
+    /// * `warn_cycles` - The number of cycles above which to warn.
+    ///
+    /// # Details
+    /// Unlike [`Self::cycle_limit`], this is a soft threshold: the warning is logged once after
+    /// the execution and the execution still completes. Defaults to 100M cycles.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, include_elf, Prover};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::builder().cpu().build();
+    /// let builder = client.execute(elf, &stdin)
+    ///     .warn_cycles(1000000)
+    ///     .cycle_limit(10000000)
+    ///     .run();
+    /// ```
+    #[must_use]
+    pub fn warn_cycles(mut self, warn_cycles: u64) -> Self {
+        self.context_builder.warn_cycles(warn_cycles);
+        self
+    }
+
     /// Whether to skip deferred proof verification in the executor.
     ///
     /// # Arguments
@@ -112,6 +142,86 @@ impl<'a> CpuExecuteBuilder<'a> {
         self
     }
 
+    /// Record every memory access made during execution in the execution report.
+    ///
+    /// # Details
+    /// Each read and write is appended to [`ExecutionReport::memory_trace`] as a
+    /// [`sp1_core_executor::MemoryTraceEntry`] holding the cycle, address, access kind and value.
+    /// Registers live at addresses `0..32` and are included. A full trace grows with the cycle
+    /// count, so prefer [`Self::with_memory_trace_range`] for anything but small programs.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, include_elf, Prover};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::builder().cpu().build();
+    /// let (_, report) = client.execute(elf, &stdin)
+    ///     .with_memory_trace()
+    ///     .run()
+    ///     .unwrap();
+    /// println!("{} memory accesses", report.memory_trace.len());
+    /// ```
+    #[must_use]
+    pub fn with_memory_trace(self) -> Self {
+        self.with_memory_trace_range(0..u32::MAX)
+    }
+
+    /// Record the memory accesses to addresses in `addresses` in the execution report.
+    ///
+    /// # Arguments
+    /// * `addresses` - The range of addresses to trace.
+    ///
+    /// # Details
+    /// This behaves like [`Self::with_memory_trace`], but only accesses to addresses inside the
+    /// range are recorded.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, include_elf, Prover};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::builder().cpu().build();
+    /// let (_, report) = client.execute(elf, &stdin)
+    ///     .with_memory_trace_range(0x1000..0x2000)
+    ///     .run()
+    ///     .unwrap();
+    /// ```
+    #[must_use]
+    pub fn with_memory_trace_range(mut self, addresses: Range<u32>) -> Self {
+        self.context_builder.memory_trace(addresses);
+        self
+    }
+
+    /// Fail as soon as an `unconstrained` block panics.
+    ///
+    /// # Details
+    /// A panic inside an unconstrained block otherwise surfaces as a syscall used in unconstrained
+    /// mode. With this set, execution stops with
+    /// [`sp1_core_executor::ExecutionError::UnconstrainedPanic`], which holds the panic message.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, include_elf, Prover};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::builder().cpu().build();
+    /// let builder = client.execute(elf, &stdin)
+    ///     .strict_unconstrained()
+    ///     .run();
+    /// ```
+    #[must_use]
+    pub fn strict_unconstrained(mut self) -> Self {
+        self.context_builder.strict_unconstrained();
+        self
+    }
+
     /// Executes the program on the input with the built arguments.
     ///
     /// # Details
@@ -6,20 +6,28 @@ pub mod builder;
 pub mod execute;
 pub mod prove;
 
-use anyhow::Result;
+use std::{
+    collections::BTreeSet,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::{bail, Result};
 use execute::CpuExecuteBuilder;
 use hashbrown::HashMap;
 use p3_baby_bear::BabyBear;
 use p3_field::{extension::BinomialExtensionField, AbstractField, PrimeField};
 use p3_fri::{FriProof, TwoAdicFriPcsProof};
 use prove::CpuProveBuilder;
-use sp1_core_executor::{SP1Context, SP1ContextBuilder, SP1ReduceProof};
+use sp1_core_executor::{
+    syscalls::SyscallCode, ExecutionReport, SP1Context, SP1ContextBuilder, SP1ReduceProof,
+};
 use sp1_core_machine::io::SP1Stdin;
 use sp1_prover::{
     components::CpuProverComponents,
     verify::{verify_groth16_bn254_public_inputs, verify_plonk_bn254_public_inputs},
-    Groth16Bn254Proof, HashableKey, PlonkBn254Proof, SP1CoreProofData, SP1ProofWithMetadata,
-    SP1Prover,
+    Groth16Bn254Proof, HashableKey, OuterSC, PlonkBn254Proof, SP1CoreProofData,
+    SP1ProofWithMetadata, SP1Prover, SP1RecursionProverError,
 };
 use sp1_stark::{
     SP1CoreOpts, SP1ProverOpts, ShardCommitment, ShardOpenedValues, ShardProof, StarkVerifyingKey,
@@ -106,9 +114,17 @@ impl CpuProver {
             core_opts: SP1CoreOpts::default(),
             recursion_opts: SP1CoreOpts::recursion(),
             mock: self.mock,
+            plonk_artifacts_dir: None,
+            groth16_artifacts_dir: None,
+            timeout: None,
         }
     }
 
+    /// Proves the program in the given mode.
+    ///
+    /// If `artifacts_dir` is set, the PLONK or Groth16 circuit artifacts are read from it instead
+    /// of being built or downloaded. Proving stops early with an error once `cancel` is set.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn prove_impl<'a>(
         &'a self,
         pk: &SP1ProvingKey,
@@ -116,6 +132,8 @@ impl CpuProver {
         opts: SP1ProverOpts,
         context: SP1Context<'a>,
         mode: SP1ProofMode,
+        artifacts_dir: Option<&Path>,
+        cancel: &AtomicBool,
     ) -> Result<SP1ProofWithPublicValues> {
         // If we're in mock mode, return a mock proof.
         if self.mock {
@@ -124,7 +142,7 @@ impl CpuProver {
 
         // Generate the core proof.
         let proof: SP1ProofWithMetadata<SP1CoreProofData> =
-            self.prover.prove_core(pk, stdin, opts, context)?;
+            self.prover.prove_core_cancellable(pk, stdin, opts, context, cancel)?;
         if mode == SP1ProofMode::Core {
             return Ok(SP1ProofWithPublicValues {
                 proof: SP1Proof::Core(proof.proof.0),
@@ -137,25 +155,44 @@ impl CpuProver {
         let deferred_proofs =
             stdin.proofs.iter().map(|(reduce_proof, _)| reduce_proof.clone()).collect();
         let public_values = proof.public_values.clone();
-        let reduce_proof = self.prover.compress(&pk.vk, proof, deferred_proofs, opts)?;
+        let reduce_proof =
+            self.prover.compress_cancellable(&pk.vk, proof, deferred_proofs, opts, cancel)?;
         if mode == SP1ProofMode::Compressed {
             return Ok(SP1ProofWithPublicValues {
-                proof: SP1Proof::Compressed(Box::new(reduce_proof)),
+                proof: SP1Proof::Compressed(Box::new(reduce_proof.into_inner())),
                 public_values,
                 sp1_version: self.version().to_string(),
             });
         }
 
         // Generate the shrink proof.
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SP1RecursionProverError::Cancelled.into());
+        }
         let compress_proof = self.prover.shrink(reduce_proof, opts)?;
+        if mode == SP1ProofMode::Shrink {
+            return Ok(SP1ProofWithPublicValues {
+                proof: SP1Proof::Shrink(Box::new(compress_proof.into_inner())),
+                public_values,
+                sp1_version: self.version().to_string(),
+            });
+        }
 
         // Generate the wrap proof.
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SP1RecursionProverError::Cancelled.into());
+        }
         let outer_proof = self.prover.wrap_bn254(compress_proof, opts)?;
 
         // Generate the gnark proof.
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SP1RecursionProverError::Cancelled.into());
+        }
         match mode {
             SP1ProofMode::Groth16 => {
-                let groth16_bn254_artifacts = if sp1_prover::build::sp1_dev_mode() {
+                let groth16_bn254_artifacts = if let Some(artifacts_dir) = artifacts_dir {
+                    artifacts_dir.to_path_buf()
+                } else if sp1_prover::build::sp1_dev_mode() {
                     sp1_prover::build::try_build_groth16_bn254_artifacts_dev(
                         &outer_proof.vk,
                         &outer_proof.proof,
@@ -172,15 +209,7 @@ impl CpuProver {
                 });
             }
             SP1ProofMode::Plonk => {
-                let plonk_bn254_artifacts = if sp1_prover::build::sp1_dev_mode() {
-                    sp1_prover::build::try_build_plonk_bn254_artifacts_dev(
-                        &outer_proof.vk,
-                        &outer_proof.proof,
-                    )
-                } else {
-                    try_install_circuit_artifacts("plonk")
-                };
-                let proof = self.prover.wrap_plonk_bn254(outer_proof, &plonk_bn254_artifacts);
+                let proof = self.wrap_plonk_bn254(outer_proof, artifacts_dir)?;
                 return Ok(SP1ProofWithPublicValues {
                     proof: SP1Proof::Plonk(proof),
                     public_values,
@@ -191,6 +220,115 @@ impl CpuProver {
         }
     }
 
+    /// Proves the program once and returns both its compressed and its PLONK proof.
+    ///
+    /// The PLONK proof is wrapped from the same compressed proof, so the core and compress stages
+    /// are only run once. Proving stops early with an error once `cancel` is set.
+    pub(crate) fn prove_compressed_and_plonk_impl<'a>(
+        &'a self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        opts: SP1ProverOpts,
+        context: SP1Context<'a>,
+        plonk_artifacts_dir: Option<&Path>,
+        cancel: &AtomicBool,
+    ) -> Result<(SP1ProofWithPublicValues, SP1ProofWithPublicValues)> {
+        // If we're in mock mode, return mock proofs.
+        if self.mock {
+            return Ok((
+                self.mock_prove_impl(pk, stdin.clone(), SP1ProofMode::Compressed)?,
+                self.mock_prove_impl(pk, stdin.clone(), SP1ProofMode::Plonk)?,
+            ));
+        }
+
+        // Generate the core and compressed proofs.
+        let proof = self.prover.prove_core_cancellable(pk, stdin, opts, context, cancel)?;
+        let deferred_proofs =
+            stdin.proofs.iter().map(|(reduce_proof, _)| reduce_proof.clone()).collect();
+        let public_values = proof.public_values.clone();
+        let reduce_proof =
+            self.prover.compress_cancellable(&pk.vk, proof, deferred_proofs, opts, cancel)?;
+
+        // Generate the shrink, wrap and plonk proofs from a copy of the compressed proof.
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SP1RecursionProverError::Cancelled.into());
+        }
+        let compress_proof = self.prover.shrink(reduce_proof.clone(), opts)?;
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SP1RecursionProverError::Cancelled.into());
+        }
+        let outer_proof = self.prover.wrap_bn254(compress_proof, opts)?;
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SP1RecursionProverError::Cancelled.into());
+        }
+        let plonk_proof = self.wrap_plonk_bn254(outer_proof, plonk_artifacts_dir)?;
+
+        Ok((
+            SP1ProofWithPublicValues {
+                proof: SP1Proof::Compressed(Box::new(reduce_proof.into_inner())),
+                public_values: public_values.clone(),
+                sp1_version: self.version().to_string(),
+            },
+            SP1ProofWithPublicValues {
+                proof: SP1Proof::Plonk(plonk_proof),
+                public_values,
+                sp1_version: self.version().to_string(),
+            },
+        ))
+    }
+
+    /// Wraps an outer proof into a PLONK proof, reading the circuit artifacts from `artifacts_dir`
+    /// if set, and building or installing them otherwise.
+    fn wrap_plonk_bn254(
+        &self,
+        outer_proof: SP1ReduceProof<OuterSC>,
+        artifacts_dir: Option<&Path>,
+    ) -> Result<PlonkBn254Proof, SP1RecursionProverError> {
+        let plonk_bn254_artifacts = if let Some(artifacts_dir) = artifacts_dir {
+            artifacts_dir.to_path_buf()
+        } else if sp1_prover::build::sp1_dev_mode() {
+            sp1_prover::build::try_build_plonk_bn254_artifacts_dev(
+                &outer_proof.vk,
+                &outer_proof.proof,
+            )
+        } else {
+            try_install_circuit_artifacts("plonk")
+        };
+        self.prover.wrap_plonk_bn254(outer_proof, &plonk_bn254_artifacts)
+    }
+
+    /// Generates a mock proof of the program, and returns it alongside the set of syscalls the
+    /// program invoked during execution.
+    ///
+    /// This is only supported for provers in mock mode, see [`CpuProver::mock`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, SP1ProofMode, Prover};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::builder().mock().build();
+    /// let (pk, vk) = client.setup(elf);
+    /// let (proof, syscalls) =
+    ///     client.prove_with_syscall_trace(&pk, &stdin, SP1ProofMode::Core).unwrap();
+    /// ```
+    pub fn prove_with_syscall_trace(
+        &self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> Result<(SP1ProofWithPublicValues, BTreeSet<SyscallCode>)> {
+        if !self.mock {
+            bail!("syscall traces are only supported when proving in mock mode");
+        }
+        let (proof, report) = self.mock_prove_with_report(pk, stdin, mode)?;
+        let syscalls =
+            report.syscall_counts.iter().filter(|(_, &count)| count > 0).map(|(code, _)| code);
+        Ok((proof, syscalls.collect()))
+    }
+
     #[allow(clippy::needless_pass_by_value)]
     pub(crate) fn mock_prove_impl(
         &self,
@@ -198,19 +336,24 @@ impl CpuProver {
         stdin: SP1Stdin,
         mode: SP1ProofMode,
     ) -> Result<SP1ProofWithPublicValues> {
-        let context = SP1Context::default();
-        match mode {
-            SP1ProofMode::Core => {
-                let (public_values, _) = self.prover.execute(&pk.elf, &stdin, context)?;
-                Ok(SP1ProofWithPublicValues {
-                    proof: SP1Proof::Core(vec![]),
-                    public_values,
-                    sp1_version: self.version().to_string(),
-                })
-            }
-            SP1ProofMode::Compressed => {
-                let (public_values, _) = self.prover.execute(&pk.elf, &stdin, context)?;
+        self.mock_prove_with_report(pk, &stdin, mode).map(|(proof, _)| proof)
+    }
 
+    fn mock_prove_with_report(
+        &self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> Result<(SP1ProofWithPublicValues, ExecutionReport)> {
+        let context = SP1Context::default();
+        let (public_values, report) = self.prover.execute(&pk.elf, stdin, context)?;
+        let proof = match mode {
+            SP1ProofMode::Core => SP1ProofWithPublicValues {
+                proof: SP1Proof::Core(vec![]),
+                public_values,
+                sp1_version: self.version().to_string(),
+            },
+            SP1ProofMode::Compressed | SP1ProofMode::Shrink => {
                 let shard_proof = ShardProof {
                     commitment: ShardCommitment {
                         global_main_commit: [BabyBear::zero(); 8].into(),
@@ -239,50 +382,47 @@ impl CpuProver {
                     chip_ordering: HashMap::new(),
                 };
 
-                let proof = SP1Proof::Compressed(Box::new(SP1ReduceProof {
-                    vk: reduce_vk,
-                    proof: shard_proof,
-                }));
+                let reduce_proof = Box::new(SP1ReduceProof { vk: reduce_vk, proof: shard_proof });
+                let proof = if mode == SP1ProofMode::Shrink {
+                    SP1Proof::Shrink(reduce_proof)
+                } else {
+                    SP1Proof::Compressed(reduce_proof)
+                };
 
-                Ok(SP1ProofWithPublicValues {
+                SP1ProofWithPublicValues {
                     proof,
                     public_values,
                     sp1_version: self.version().to_string(),
-                })
-            }
-            SP1ProofMode::Plonk => {
-                let (public_values, _) = self.prover.execute(&pk.elf, &stdin, context)?;
-                Ok(SP1ProofWithPublicValues {
-                    proof: SP1Proof::Plonk(PlonkBn254Proof {
-                        public_inputs: [
-                            pk.vk.hash_bn254().as_canonical_biguint().to_string(),
-                            public_values.hash_bn254().to_string(),
-                        ],
-                        encoded_proof: String::new(),
-                        raw_proof: String::new(),
-                        plonk_vkey_hash: [0; 32],
-                    }),
-                    public_values,
-                    sp1_version: self.version().to_string(),
-                })
-            }
-            SP1ProofMode::Groth16 => {
-                let (public_values, _) = self.prover.execute(&pk.elf, &stdin, context)?;
-                Ok(SP1ProofWithPublicValues {
-                    proof: SP1Proof::Groth16(Groth16Bn254Proof {
-                        public_inputs: [
-                            pk.vk.hash_bn254().as_canonical_biguint().to_string(),
-                            public_values.hash_bn254().to_string(),
-                        ],
-                        encoded_proof: String::new(),
-                        raw_proof: String::new(),
-                        groth16_vkey_hash: [0; 32],
-                    }),
-                    public_values,
-                    sp1_version: self.version().to_string(),
-                })
+                }
             }
-        }
+            SP1ProofMode::Plonk => SP1ProofWithPublicValues {
+                proof: SP1Proof::Plonk(PlonkBn254Proof {
+                    public_inputs: [
+                        pk.vk.hash_bn254().as_canonical_biguint().to_string(),
+                        public_values.hash_bn254().to_string(),
+                    ],
+                    encoded_proof: String::new(),
+                    raw_proof: String::new(),
+                    plonk_vkey_hash: [0; 32],
+                }),
+                public_values,
+                sp1_version: self.version().to_string(),
+            },
+            SP1ProofMode::Groth16 => SP1ProofWithPublicValues {
+                proof: SP1Proof::Groth16(Groth16Bn254Proof {
+                    public_inputs: [
+                        pk.vk.hash_bn254().as_canonical_biguint().to_string(),
+                        public_values.hash_bn254().to_string(),
+                    ],
+                    encoded_proof: String::new(),
+                    raw_proof: String::new(),
+                    groth16_vkey_hash: [0; 32],
+                }),
+                public_values,
+                sp1_version: self.version().to_string(),
+            },
+        };
+        Ok((proof, report))
     }
 
     fn mock_verify(
@@ -318,7 +458,15 @@ impl Prover<CpuProverComponents> for CpuProver {
         stdin: &SP1Stdin,
         mode: SP1ProofMode,
     ) -> Result<SP1ProofWithPublicValues> {
-        self.prove_impl(pk, stdin, SP1ProverOpts::default(), SP1Context::default(), mode)
+        self.prove_impl(
+            pk,
+            stdin,
+            SP1ProverOpts::default(),
+            SP1Context::default(),
+            mode,
+            None,
+            &AtomicBool::new(false),
+        )
     }
 
     fn verify(
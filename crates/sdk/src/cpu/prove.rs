@@ -2,14 +2,25 @@
 //!
 //! This module provides a builder for proving a program on the CPU.
 
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, RecvTimeoutError},
+    },
+    thread,
+    time::Duration,
+};
+
 use anyhow::Result;
 use sp1_core_executor::SP1ContextBuilder;
-use sp1_core_machine::io::SP1Stdin;
-use sp1_prover::SP1ProvingKey;
+use sp1_core_machine::{io::SP1Stdin, utils::SP1CoreProverError};
+use sp1_prover::{SP1ProvingKey, SP1RecursionProverError};
 use sp1_stark::{SP1CoreOpts, SP1ProverOpts};
+use thiserror::Error;
 
 use super::CpuProver;
-use crate::{SP1ProofMode, SP1ProofWithPublicValues};
+use crate::{install::check_circuit_artifacts, SP1ProofMode, SP1ProofWithPublicValues};
 
 /// A builder for proving a program on the CPU.
 ///
@@ -24,6 +35,18 @@ pub struct CpuProveBuilder<'a> {
     pub(crate) core_opts: SP1CoreOpts,
     pub(crate) recursion_opts: SP1CoreOpts,
     pub(crate) mock: bool,
+    pub(crate) plonk_artifacts_dir: Option<PathBuf>,
+    pub(crate) groth16_artifacts_dir: Option<PathBuf>,
+    pub(crate) timeout: Option<Duration>,
+}
+
+/// The error returned by [`CpuProveBuilder::run`] when proving exceeds the
+/// [`CpuProveBuilder::timeout`].
+#[derive(Error, Debug)]
+#[error("proving did not finish within {timeout:?}")]
+pub struct ProveTimeoutError {
+    /// The timeout that was exceeded.
+    pub timeout: Duration,
 }
 
 impl<'a> CpuProveBuilder<'a> {
@@ -78,6 +101,32 @@ impl<'a> CpuProveBuilder<'a> {
         self
     }
 
+    /// Set the proof mode to [`SP1ProofMode::Shrink`] mode.
+    ///
+    /// # Details
+    /// This mode produces the compressed proof recursively proven again with a larger blowup, which
+    /// is smaller than a [`SP1ProofMode::Compressed`] proof while still being proven over BabyBear.
+    /// It is useful when the final wrapping into a SNARK is done elsewhere.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, Prover};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::builder().cpu().build();
+    /// let (pk, vk) = client.setup(elf);
+    /// let builder = client.prove(&pk, &stdin)
+    ///     .shrink()
+    ///     .run();
+    /// ```
+    #[must_use]
+    pub fn shrink(mut self) -> Self {
+        self.mode = SP1ProofMode::Shrink;
+        self
+    }
+
     /// Set the proof mode to [`SP1ProofKind::Plonk`] mode.
     ///
     /// # Details
@@ -154,6 +203,61 @@ impl<'a> CpuProveBuilder<'a> {
         self
     }
 
+    /// Read the PLONK circuit artifacts from the given directory.
+    ///
+    /// # Details
+    /// By default, the artifacts are built in dev mode or downloaded to the `~/.sp1` directory. With
+    /// this set, they are never built or downloaded: proving a [`SP1ProofKind::Plonk`] proof
+    /// fails up front with a [`crate::install::CircuitArtifactsError`] if the directory does not
+    /// contain `plonk_circuit.bin`, `plonk_pk.bin` and `plonk_vk.bin`. This is useful for
+    /// air-gapped environments.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, include_elf, Prover};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::builder().cpu().build();
+    /// let (pk, vk) = client.setup(elf);
+    /// let builder = client.prove(&pk, &stdin)
+    ///     .plonk()
+    ///     .plonk_artifacts_dir("/opt/sp1/circuits/plonk")
+    ///     .run();
+    /// ```
+    #[must_use]
+    pub fn plonk_artifacts_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.plonk_artifacts_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Read the Groth16 circuit artifacts from the given directory.
+    ///
+    /// # Details
+    /// Like [`Self::plonk_artifacts_dir`], but for [`SP1ProofKind::Groth16`] proofs, which need
+    /// `groth16_circuit.bin`, `groth16_pk.bin` and `groth16_vk.bin`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, include_elf, Prover};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::builder().cpu().build();
+    /// let (pk, vk) = client.setup(elf);
+    /// let builder = client.prove(&pk, &stdin)
+    ///     .groth16()
+    ///     .groth16_artifacts_dir("/opt/sp1/circuits/groth16")
+    ///     .run();
+    /// ```
+    #[must_use]
+    pub fn groth16_artifacts_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.groth16_artifacts_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
     /// Set the shard size for proving.
     ///
     /// # Details
@@ -162,6 +266,9 @@ impl<'a> CpuProveBuilder<'a> {
     /// complexity. By default, the value is set to some predefined values that are optimized for performance
     /// based on the available amount of RAM on the system.
     ///
+    /// The override only applies to this proof, so unlike the `SHARD_SIZE` environment variable it
+    /// is safe to use with different values from concurrent threads.
+    ///
     /// # Example
     /// ```rust,no_run
     /// use sp1_sdk::{ProverClient, SP1Stdin, include_elf, Prover};
@@ -232,6 +339,30 @@ impl<'a> CpuProveBuilder<'a> {
         self
     }
 
+    /// Set the number of cpu cycles above which a high cycle count warning is logged.
+    ///
+    /// # Details
+    /// Unlike [`Self::cycle_limit`], this does not stop the execution. Defaults to 100M cycles.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, include_elf, Prover};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::builder().cpu().build();
+    /// let (pk, vk) = client.setup(elf);
+    /// let builder = client.prove(&pk, &stdin)
+    ///     .warn_cycles(1000000)
+    ///     .run();
+    /// ```
+    #[must_use]
+    pub fn warn_cycles(mut self, warn_cycles: u64) -> Self {
+        self.context_builder.warn_cycles(warn_cycles);
+        self
+    }
+
     /// Set the skip deferred proof verification flag.
     ///
     /// # Details
@@ -257,6 +388,35 @@ impl<'a> CpuProveBuilder<'a> {
         self
     }
 
+    /// Set the timeout for the proof's generation.
+    ///
+    /// # Details
+    /// Proving runs on a worker thread, and once the timeout elapses the pipeline is cancelled and
+    /// [`CpuProveBuilder::run`] or [`CpuProveBuilder::compressed_and_plonk`] returns a
+    /// [`ProveTimeoutError`]. The core and compress stages stop at their next cancellation check,
+    /// while the shrink, wrap and SNARK stages are only skipped if they have not started yet. An
+    /// error that is not caused by the cancellation is returned unchanged.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, Prover};
+    /// use std::time::Duration;
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::builder().cpu().build();
+    /// let (pk, vk) = client.setup(elf);
+    /// let builder = client.prove(&pk, &stdin)
+    ///     .timeout(Duration::from_secs(60))
+    ///     .run();
+    /// ```
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     /// Run the prover with the built arguments.
     ///
     /// # Details
@@ -278,19 +438,141 @@ impl<'a> CpuProveBuilder<'a> {
     /// ```
     pub fn run(self) -> Result<SP1ProofWithPublicValues> {
         // Get the arguments.
-        let Self { prover, mode, pk, stdin, mut context_builder, core_opts, recursion_opts, mock } =
-            self;
+        let Self {
+            prover,
+            mode,
+            pk,
+            stdin,
+            mut context_builder,
+            core_opts,
+            recursion_opts,
+            mock,
+            plonk_artifacts_dir,
+            groth16_artifacts_dir,
+            timeout,
+        } = self;
         let opts = SP1ProverOpts { core_opts, recursion_opts };
         let context = context_builder.build();
 
+        // Check the circuit artifacts before spending any time proving.
+        let artifacts_dir = match mode {
+            SP1ProofMode::Plonk => plonk_artifacts_dir.map(|dir| (dir, "plonk")),
+            SP1ProofMode::Groth16 => groth16_artifacts_dir.map(|dir| (dir, "groth16")),
+            _ => None,
+        };
+        if let Some((dir, artifacts_type)) = &artifacts_dir {
+            check_circuit_artifacts(dir, artifacts_type)?;
+        }
+
         // Dump the program and stdin to files for debugging if `SP1_DUMP` is set.
         crate::utils::sp1_dump(&pk.elf, &stdin);
 
         // Run the prover.
         if mock {
-            prover.mock_prove_impl(pk, stdin, mode)
-        } else {
-            prover.prove_impl(pk, &stdin, opts, context, mode)
+            return prover.mock_prove_impl(pk, stdin, mode);
         }
+        let artifacts_dir = artifacts_dir.as_ref().map(|(dir, _)| dir.as_path());
+        run_with_timeout(timeout, |cancel| {
+            prover.prove_impl(pk, &stdin, opts, context, mode, artifacts_dir, cancel)
+        })
     }
+
+    /// Run the prover once and return both a [`SP1ProofMode::Compressed`] and a
+    /// [`SP1ProofMode::Plonk`] proof.
+    ///
+    /// # Details
+    /// The PLONK proof is wrapped from the compressed proof, so this is cheaper than proving in
+    /// each mode separately, which would run the core and compress stages twice. The proof mode
+    /// set on the builder is ignored, while the [`Self::timeout`] applies to the whole run.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, include_elf, Prover};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::builder().cpu().build();
+    /// let (pk, vk) = client.setup(elf);
+    /// let (compressed_proof, plonk_proof) = client.prove(&pk, &stdin)
+    ///     .compressed_and_plonk()
+    ///     .unwrap();
+    /// ```
+    pub fn compressed_and_plonk(
+        self,
+    ) -> Result<(SP1ProofWithPublicValues, SP1ProofWithPublicValues)> {
+        // Get the arguments.
+        let Self {
+            prover,
+            pk,
+            stdin,
+            mut context_builder,
+            core_opts,
+            recursion_opts,
+            plonk_artifacts_dir,
+            timeout,
+            ..
+        } = self;
+        let opts = SP1ProverOpts { core_opts, recursion_opts };
+        let context = context_builder.build();
+
+        // Check the circuit artifacts before spending any time proving.
+        if let Some(dir) = &plonk_artifacts_dir {
+            check_circuit_artifacts(dir, "plonk")?;
+        }
+
+        // Dump the program and stdin to files for debugging if `SP1_DUMP` is set.
+        crate::utils::sp1_dump(&pk.elf, &stdin);
+
+        run_with_timeout(timeout, |cancel| {
+            prover.prove_compressed_and_plonk_impl(
+                pk,
+                &stdin,
+                opts,
+                context,
+                plonk_artifacts_dir.as_deref(),
+                cancel,
+            )
+        })
+    }
+}
+
+/// Runs `prove` with a cancellation flag that is set once `timeout` elapses, if any.
+///
+/// A cancellation error is reported as a [`ProveTimeoutError`], while any other error, including
+/// one raised after the timeout elapsed, is returned as is.
+fn run_with_timeout<T>(
+    timeout: Option<Duration>,
+    prove: impl FnOnce(&AtomicBool) -> Result<T>,
+) -> Result<T> {
+    let cancel = AtomicBool::new(false);
+    let Some(timeout) = timeout else {
+        return prove(&cancel);
+    };
+
+    // Cancel the pipeline from a watchdog thread if proving exceeds the timeout. Dropping
+    // `done_tx` once proving returns wakes the watchdog up, so no thread outlives this call.
+    let result = thread::scope(|s| {
+        let (done_tx, done_rx) = channel::<()>();
+        let cancel = &cancel;
+        s.spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = done_rx.recv_timeout(timeout) {
+                tracing::warn!("proving timed out after {:?}, cancelling", timeout);
+                cancel.store(true, Ordering::SeqCst);
+            }
+        });
+        let result = prove(cancel);
+        drop(done_tx);
+        result
+    });
+    match result {
+        Err(err) if is_cancelled(&err) => Err(ProveTimeoutError { timeout }.into()),
+        result => result,
+    }
+}
+
+/// Whether `err` is the error returned by a proving stage that was cancelled.
+fn is_cancelled(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref(), Some(SP1CoreProverError::Cancelled))
+        || matches!(err.downcast_ref(), Some(SP1RecursionProverError::Cancelled))
 }
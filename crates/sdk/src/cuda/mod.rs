@@ -116,7 +116,7 @@ impl Prover<CpuProverComponents> for CudaProver {
         let reduce_proof = self.cuda_prover.compress(&pk.vk, proof, deferred_proofs)?;
         if kind == SP1ProofMode::Compressed {
             return Ok(SP1ProofWithPublicValues {
-                proof: SP1Proof::Compressed(Box::new(reduce_proof)),
+                proof: SP1Proof::Compressed(Box::new(reduce_proof.into_inner())),
                 public_values,
                 sp1_version: self.version().to_string(),
             });
@@ -124,6 +124,13 @@ impl Prover<CpuProverComponents> for CudaProver {
 
         // Generate the shrink proof.
         let compress_proof = self.cuda_prover.shrink(reduce_proof)?;
+        if kind == SP1ProofMode::Shrink {
+            return Ok(SP1ProofWithPublicValues {
+                proof: SP1Proof::Shrink(Box::new(compress_proof.into_inner())),
+                public_values,
+                sp1_version: self.version().to_string(),
+            });
+        }
 
         // Genenerate the wrap proof.
         let outer_proof = self.cuda_prover.wrap_bn254(compress_proof)?;
@@ -137,7 +144,7 @@ impl Prover<CpuProverComponents> for CudaProver {
             } else {
                 try_install_circuit_artifacts("plonk")
             };
-            let proof = self.cpu_prover.wrap_plonk_bn254(outer_proof, &plonk_bn254_artifacts);
+            let proof = self.cpu_prover.wrap_plonk_bn254(outer_proof, &plonk_bn254_artifacts)?;
             return Ok(SP1ProofWithPublicValues {
                 proof: SP1Proof::Plonk(proof),
                 public_values,
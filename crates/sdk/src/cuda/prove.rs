@@ -72,6 +72,32 @@ impl<'a> CudaProveBuilder<'a> {
         self
     }
 
+    /// Set the proof mode to [`SP1ProofMode::Shrink`] mode.
+    ///
+    /// # Details
+    /// This mode produces the compressed proof recursively proven again with a larger blowup, which
+    /// is smaller than a [`SP1ProofMode::Compressed`] proof while still being proven over BabyBear.
+    /// It is useful when the final wrapping into a SNARK is done elsewhere.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin, Prover};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::builder().cuda().build();
+    /// let (pk, vk) = client.setup(elf);
+    /// let builder = client.prove(&pk, &stdin)
+    ///     .shrink()
+    ///     .run();
+    /// ```
+    #[must_use]
+    pub fn shrink(mut self) -> Self {
+        self.mode = SP1ProofMode::Shrink;
+        self
+    }
+
     /// Set the proof mode to [`SP1ProofMode::Plonk`] mode.
     ///
     /// # Details
@@ -11,14 +11,14 @@ use anyhow::Result;
 use prove::EnvProveBuilder;
 use sp1_core_executor::SP1ContextBuilder;
 use sp1_core_machine::io::SP1Stdin;
-use sp1_prover::{components::CpuProverComponents, SP1Prover, SP1ProvingKey, SP1VerifyingKey};
+use sp1_primitives::io::SP1PublicValues;
+use sp1_prover::{
+    components::CpuProverComponents, ProgramError, SP1Prover, SP1ProvingKey, SP1VerifyingKey,
+};
 
 use super::{Prover, SP1VerificationError};
 use crate::cpu::execute::CpuExecuteBuilder;
-use crate::cpu::CpuProver;
-use crate::cuda::CudaProver;
-use crate::network::builder::NetworkProverBuilder;
-use crate::{SP1ProofMode, SP1ProofWithPublicValues};
+use crate::{ProverClient, SP1ProofMode, SP1ProofWithPublicValues};
 
 /// A prover that can execute programs and generate proofs with a different implementation based on
 /// the value of certain environment variables.
@@ -32,7 +32,8 @@ impl EnvProver {
     /// Creates a new [`EnvProver`] with the given configuration.
     ///
     /// The following environment variables are used to configure the prover:
-    /// - `SP1_PROVER`: The type of prover to use. Must be one of `mock`, `local`, `cuda`, or `network`.
+    /// - `SP1_PROVER`: The type of prover to use. Must be one of `mock`, `cpu`, `local`, `cuda`, or
+    ///   `network`.
     /// - `NETWORK_PRIVATE_KEY`: The private key to use for the network prover.
     /// - `NETWORK_RPC_URL`: The RPC URL to use for the network prover.
     #[must_use]
@@ -45,8 +46,8 @@ impl EnvProver {
         };
 
         let prover: Box<dyn Prover<CpuProverComponents>> = match mode.as_str() {
-            "mock" => Box::new(CpuProver::mock()),
-            "cpu" => Box::new(CpuProver::new()),
+            "mock" => Box::new(ProverClient::builder().mock().build()),
+            "cpu" | "local" => Box::new(ProverClient::builder().cpu().build()),
             "cuda" => {
                 #[cfg(not(feature = "cuda"))]
                 panic!(
@@ -56,7 +57,7 @@ impl EnvProver {
                 );
 
                 #[cfg(feature = "cuda")]
-                Box::new(CudaProver::new(SP1Prover::new()))
+                Box::new(ProverClient::builder().cuda().build())
             }
             "network" => {
                 #[cfg(not(feature = "network"))]
@@ -68,11 +69,11 @@ impl EnvProver {
 
                 #[cfg(feature = "network")]
                 {
-                    Box::new(NetworkProverBuilder::default().build())
+                    Box::new(ProverClient::builder().network().build())
                 }
             }
             _ => panic!(
-                "Invalid SP1_PROVER value. Expected one of: mock, cpu, cuda, or network. Got: '{mode}'.\n\
+                "Invalid SP1_PROVER value. Expected one of: mock, cpu, local, cuda, or network. Got: '{mode}'.\n\
                 Please set the SP1_PROVER environment variable to one of the supported values."
             ),
         };
@@ -158,12 +159,51 @@ impl EnvProver {
         self.prover.verify(proof, vk)
     }
 
+    /// Verifies many independent proofs in parallel, returning the result of [`Self::verify`] for
+    /// each `(proof, vk)` pair in order.
+    ///
+    /// ### Examples
+    /// ```no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin};
+    ///
+    /// let elf = &[1, 2, 3];
+    /// let stdin = SP1Stdin::new();
+    ///
+    /// let client = ProverClient::from_env();
+    /// let (pk, vk) = client.setup(elf);
+    /// let proof = client.prove(&pk, &stdin).run().unwrap();
+    /// let results = client.verify_batch(&[(&proof, &vk), (&proof, &vk)]);
+    /// assert!(results.iter().all(Result::is_ok));
+    /// ```
+    pub fn verify_batch(
+        &self,
+        items: &[(&SP1ProofWithPublicValues, &SP1VerifyingKey)],
+    ) -> Vec<Result<(), SP1VerificationError>> {
+        self.prover.verify_batch(items)
+    }
+
+    /// Verifies the given proof like [`Self::verify`], and additionally checks that its committed
+    /// public values start with `expected`.
+    pub fn verify_with_public_values(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+        vk: &SP1VerifyingKey,
+        expected: &SP1PublicValues,
+    ) -> Result<(), SP1VerificationError> {
+        self.prover.verify_with_public_values(proof, vk, expected)
+    }
+
     /// Setup a program to be proven and verified by the SP1 RISC-V zkVM by computing the proving
     /// and verifying keys.
     #[must_use]
     pub fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
         self.prover.setup(elf)
     }
+
+    /// Like [`Self::setup`], but returns an error instead of panicking if the ELF is malformed.
+    pub fn try_setup(&self, elf: &[u8]) -> Result<(SP1ProvingKey, SP1VerifyingKey), ProgramError> {
+        self.prover.try_setup(elf)
+    }
 }
 
 impl Default for EnvProver {
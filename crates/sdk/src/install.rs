@@ -3,7 +3,8 @@
 //! A library for installing the SP1 circuit artifacts.
 
 use cfg_if::cfg_if;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
 
 #[cfg(any(feature = "network", feature = "network"))]
 use {
@@ -31,6 +32,47 @@ pub fn plonk_circuit_artifacts_dir() -> PathBuf {
     dirs::home_dir().unwrap().join(".sp1").join("circuits/plonk").join(SP1_CIRCUIT_VERSION)
 }
 
+/// An error returned when a circuit artifacts directory cannot be used for wrapping.
+#[derive(Error, Debug)]
+pub enum CircuitArtifactsError {
+    /// Some of the files read by the gnark prover are missing from the directory.
+    #[error(
+        "{artifacts_type} circuit artifacts at {} are missing: {}",
+        dir.display(),
+        missing.join(", ")
+    )]
+    MissingFiles {
+        /// The kind of artifacts, either `plonk` or `groth16`.
+        artifacts_type: String,
+        /// The directory that was checked.
+        dir: PathBuf,
+        /// The names of the missing files.
+        missing: Vec<String>,
+    },
+}
+
+/// Checks that `build_dir` contains the circuit, proving key and verifying key read by the gnark
+/// prover for the given artifacts type, either `plonk` or `groth16`.
+pub fn check_circuit_artifacts(
+    build_dir: &Path,
+    artifacts_type: &str,
+) -> Result<(), CircuitArtifactsError> {
+    let missing = ["circuit.bin", "pk.bin", "vk.bin"]
+        .into_iter()
+        .map(|suffix| format!("{artifacts_type}_{suffix}"))
+        .filter(|file| !build_dir.join(file).is_file())
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(CircuitArtifactsError::MissingFiles {
+            artifacts_type: artifacts_type.to_string(),
+            dir: build_dir.to_path_buf(),
+            missing,
+        })
+    }
+}
+
 /// Tries to install the groth16 circuit artifacts if they are not already installed.
 #[must_use]
 pub fn try_install_circuit_artifacts(artifacts_type: &str) -> PathBuf {
@@ -54,14 +54,16 @@ pub use prover::SP1VerificationError;
 
 // Re-export the build utilities and executor primitives.
 pub use sp1_build::include_elf;
-pub use sp1_core_executor::{ExecutionReport, Executor, HookEnv, SP1Context, SP1ContextBuilder};
+pub use sp1_core_executor::{
+    ExecutionReport, ExecutionReportDiff, Executor, HookEnv, SP1Context, SP1ContextBuilder,
+};
 
 // Re-export the machine/prover primitives.
 pub use sp1_core_machine::{io::SP1Stdin, riscv::cost::CostEstimator, SP1_CIRCUIT_VERSION};
 pub use sp1_primitives::io::SP1PublicValues;
 pub use sp1_prover::{
-    CoreSC, HashableKey, InnerSC, OuterSC, PlonkBn254Proof, ProverMode, SP1Prover, SP1ProvingKey,
-    SP1VerifyingKey,
+    CoreSC, HashableKey, InnerSC, OuterSC, PlonkBn254Proof, ProgramError, ProverMode, SP1Prover,
+    SP1ProvingKey, SP1StdinError, SP1StdinProofExt, SP1VerifyingKey, SP1WrappedProofExt,
 };
 
 // Re-export the utilities.
@@ -69,10 +71,17 @@ pub use utils::setup_logger;
 
 #[cfg(test)]
 mod tests {
+    use std::time::{Duration, Instant};
+
+    use sp1_core_executor::{syscalls::SyscallCode, ExecutionError, MemoryAccessKind};
     use sp1_core_machine::riscv::cost::CostEstimator;
     use sp1_primitives::io::SP1PublicValues;
+    use sp1_prover::{components::CpuProverComponents, SP1Prover};
 
-    use crate::{utils, Prover, ProverClient, SP1Stdin};
+    use crate::{
+        cpu::prove::ProveTimeoutError, install::CircuitArtifactsError, utils, Prover, ProverClient,
+        SP1Proof, SP1ProofMode, SP1ProofWithPublicValues, SP1Stdin, SP1VerificationError,
+    };
 
     #[test]
     fn test_execute() {
@@ -85,6 +94,47 @@ mod tests {
         tracing::info!("gas = {}", report.estimate_gas());
     }
 
+    #[test]
+    fn test_public_values_bytes_round_trip() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let elf = test_artifacts::FIBONACCI_ELF;
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&10usize);
+        let (public_values, _) = client.execute(elf, &stdin).run().unwrap();
+        assert!(!public_values.as_slice().is_empty());
+
+        let decoded = SP1PublicValues::from_bytes(&public_values.as_bytes()).unwrap();
+        assert_eq!(decoded.as_slice(), public_values.as_slice());
+        assert_eq!(decoded.hash_bn254(), public_values.hash_bn254());
+    }
+
+    #[test]
+    fn test_execute_memory_trace() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let elf = test_artifacts::MEMORY_TRACE_ELF;
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&0xdead_beef_u32);
+
+        // The program commits the address of the word it stores the input to.
+        let (mut public_values, report) =
+            client.execute(elf, &stdin).with_memory_trace().run().unwrap();
+        let slot = public_values.read::<u32>();
+        assert!(report.memory_trace.iter().any(|entry| entry.addr == slot
+            && entry.kind == MemoryAccessKind::Write
+            && entry.value == 0xdead_beef));
+
+        // Filtering to the slot keeps the store and drops everything else.
+        let (_, report) =
+            client.execute(elf, &stdin).with_memory_trace_range(slot..slot + 4).run().unwrap();
+        assert!(report.memory_trace.iter().all(|entry| entry.addr == slot));
+        assert!(report
+            .memory_trace
+            .iter()
+            .any(|entry| entry.kind == MemoryAccessKind::Write && entry.value == 0xdead_beef));
+    }
+
     #[test]
     #[should_panic]
     fn test_execute_panic() {
@@ -107,6 +157,35 @@ mod tests {
         client.execute(elf, &stdin).cycle_limit(1).run().unwrap();
     }
 
+    #[test]
+    fn test_execute_strict_unconstrained() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let elf = test_artifacts::UNCONSTRAINED_PANIC_ELF;
+
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&3u32);
+        client.execute(elf, &stdin).strict_unconstrained().run().unwrap();
+
+        // The unconstrained block panics on inputs of 10 or more.
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&12u32);
+        let err = client.execute(elf, &stdin).strict_unconstrained().run().unwrap_err();
+        match err.downcast_ref::<ExecutionError>() {
+            Some(ExecutionError::UnconstrainedPanic(message)) => {
+                assert!(message.contains("hint for 12 is out of range"), "{message}");
+            }
+            _ => panic!("expected an unconstrained panic, got {err:?}"),
+        }
+
+        // Without the flag, the panic is reported as a syscall in unconstrained mode.
+        let err = client.execute(elf, &stdin).run().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ExecutionError>(),
+            Some(ExecutionError::InvalidSyscallUsage(_))
+        ));
+    }
+
     #[test]
     fn test_e2e_core() {
         utils::setup_logger();
@@ -127,6 +206,53 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_e2e_core_verify_with_public_values() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let elf = test_artifacts::FIBONACCI_ELF;
+        let (pk, vk) = client.setup(elf);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&10usize);
+        let proof = client.prove(&pk, &stdin).run().unwrap();
+
+        // The full output and a prefix of it should both be accepted.
+        let mut expected = SP1PublicValues::new();
+        expected.write(&55u32);
+        client.verify_with_public_values(&proof, &vk, &expected).unwrap();
+        expected.write(&89u32);
+        client.verify_with_public_values(&proof, &vk, &expected).unwrap();
+
+        // A wrong expected output should be reported as a mismatch.
+        let mut wrong = SP1PublicValues::new();
+        wrong.write(&55u32);
+        wrong.write(&90u32);
+        assert!(matches!(
+            client.verify_with_public_values(&proof, &vk, &wrong),
+            Err(SP1VerificationError::PublicValuesMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let elf = test_artifacts::FIBONACCI_ELF;
+        let (pk, vk) = client.setup(elf);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&10usize);
+        let proof = client.prove(&pk, &stdin).run().unwrap();
+
+        let mut tampered = proof.clone();
+        tampered.public_values = SP1PublicValues::from(&[255, 4, 84]);
+
+        let results = client.verify_batch(&[(&proof, &vk), (&tampered, &vk), (&proof, &vk)]);
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+    }
+
     #[test]
     fn test_e2e_compressed() {
         utils::setup_logger();
@@ -147,6 +273,72 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_e2e_shrink() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let elf = test_artifacts::FIBONACCI_ELF;
+        let (pk, vk) = client.setup(elf);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&10usize);
+
+        // Generate proof & verify.
+        let mut proof = client.prove(&pk, &stdin).shrink().run().unwrap();
+        let shrink_proof = proof.proof.clone().try_as_shrink().unwrap();
+        client.inner().verify_shrink(&shrink_proof, &vk).unwrap();
+        client.verify(&proof, &vk).unwrap();
+
+        // Test invalid public values.
+        proof.public_values = SP1PublicValues::from(&[255, 4, 84]);
+        if client.verify(&proof, &vk).is_ok() {
+            panic!("verified proof with invalid public values")
+        }
+    }
+
+    #[test]
+    fn test_security_bits() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let elf = test_artifacts::FIBONACCI_ELF;
+        let (pk, _) = client.setup(elf);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&10usize);
+
+        // The default config uses 100 queries with a blowup of 2 and 16 bits of grinding.
+        let proof = client.prove(&pk, &stdin).core().run().unwrap();
+        assert_eq!(proof.security_bits(), Some(116));
+
+        // A single query leaves only the grinding and one query's worth of soundness.
+        let prover = SP1Prover::<CpuProverComponents>::with_fri_queries(1);
+        let (pk, _) = prover.setup(elf);
+        let core_proof =
+            prover.prove_core(&pk, &stdin, Default::default(), Default::default()).unwrap();
+        let proof = SP1ProofWithPublicValues {
+            proof: SP1Proof::Core(core_proof.proof.0),
+            public_values: core_proof.public_values,
+            sp1_version: String::new(),
+        };
+        assert_eq!(proof.security_bits(), Some(17));
+    }
+
+    #[test]
+    fn test_verify_version_mismatch() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let (_, vk) = client.setup(test_artifacts::FIBONACCI_ELF);
+
+        // The version is checked before the proof itself, so an empty proof is enough.
+        let proof = SP1ProofWithPublicValues {
+            proof: SP1Proof::Plonk(Default::default()),
+            public_values: SP1PublicValues::new(),
+            sp1_version: "v0.0.0".to_string(),
+        };
+        let err = client.verify(&proof, &vk).unwrap_err();
+        assert!(
+            matches!(err, SP1VerificationError::VersionMismatch(version) if version == "v0.0.0")
+        );
+    }
+
     #[test]
     fn test_e2e_prove_plonk() {
         utils::setup_logger();
@@ -167,6 +359,112 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_e2e_compressed_and_plonk() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let elf = test_artifacts::FIBONACCI_ELF;
+        let (pk, vk) = client.setup(elf);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&10usize);
+
+        // Generate both proofs from a single run & verify.
+        let (compressed_proof, plonk_proof) =
+            client.prove(&pk, &stdin).compressed_and_plonk().unwrap();
+        assert!(matches!(compressed_proof.proof, SP1Proof::Compressed(_)));
+        assert!(matches!(plonk_proof.proof, SP1Proof::Plonk(_)));
+        client.verify(&compressed_proof, &vk).unwrap();
+        client.verify(&plonk_proof, &vk).unwrap();
+        assert_eq!(compressed_proof.public_values.as_slice(), plonk_proof.public_values.as_slice());
+    }
+
+    #[test]
+    fn test_prove_timeout() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let elf = test_artifacts::TENDERMINT_BENCHMARK_ELF;
+        let (pk, _) = client.setup(elf);
+        let stdin = SP1Stdin::new();
+
+        // The program spans many shards, so proving can't finish before the timeout.
+        let timeout = Duration::from_millis(1);
+        let start = Instant::now();
+        let err = client
+            .prove(&pk, &stdin)
+            .compressed()
+            .shard_size(1 << 19)
+            .timeout(timeout)
+            .run()
+            .unwrap_err();
+        tracing::info!("timed out proving returned after {:?}", start.elapsed());
+        assert_eq!(err.downcast::<ProveTimeoutError>().unwrap().timeout, timeout);
+        // Proving the whole program takes far longer, while cancelling only waits for the shards
+        // in flight. The bound is loose so that slow machines don't make the test flaky.
+        assert!(start.elapsed() < Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_compressed_and_plonk_timeout() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let elf = test_artifacts::TENDERMINT_BENCHMARK_ELF;
+        let (pk, _) = client.setup(elf);
+        let stdin = SP1Stdin::new();
+
+        let timeout = Duration::from_millis(1);
+        let start = Instant::now();
+        let err = client
+            .prove(&pk, &stdin)
+            .shard_size(1 << 19)
+            .timeout(timeout)
+            .compressed_and_plonk()
+            .unwrap_err();
+        assert_eq!(err.downcast::<ProveTimeoutError>().unwrap().timeout, timeout);
+        assert!(start.elapsed() < Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_prove_shard_size_in_parallel() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let elf = test_artifacts::FIBONACCI_ELF;
+        let (pk, vk) = client.setup(elf);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&10usize);
+
+        std::thread::scope(|s| {
+            let handles = [1 << 19, 1 << 21].map(|shard_size| {
+                let (client, pk, stdin) = (&client, &pk, &stdin);
+                s.spawn(move || client.prove(pk, stdin).shard_size(shard_size).run().unwrap())
+            });
+            for handle in handles {
+                client.verify(&handle.join().unwrap(), &vk).unwrap();
+            }
+        });
+    }
+
+    #[test]
+    fn test_plonk_artifacts_dir_missing_files() {
+        utils::setup_logger();
+        let client = ProverClient::builder().cpu().build();
+        let elf = test_artifacts::FIBONACCI_ELF;
+        let (pk, _) = client.setup(elf);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&10usize);
+
+        // Only the verifying key is present, so proving must fail before it starts.
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("plonk_vk.bin"), []).unwrap();
+        let err =
+            client.prove(&pk, &stdin).plonk().plonk_artifacts_dir(dir.path()).run().unwrap_err();
+        match err.downcast::<CircuitArtifactsError>().unwrap() {
+            CircuitArtifactsError::MissingFiles { artifacts_type, missing, .. } => {
+                assert_eq!(artifacts_type, "plonk");
+                assert_eq!(missing, ["plonk_circuit.bin", "plonk_pk.bin"]);
+            }
+        }
+    }
+
     #[test]
     fn test_e2e_prove_plonk_mock() {
         utils::setup_logger();
@@ -178,4 +476,35 @@ mod tests {
         let proof = client.prove(&pk, &stdin).plonk().run().unwrap();
         client.verify(&proof, &vk).unwrap();
     }
+
+    #[test]
+    fn test_builder_mock_without_env() {
+        utils::setup_logger();
+        let client = ProverClient::builder().mock().build();
+        let elf = test_artifacts::FIBONACCI_ELF;
+        let (pk, vk) = client.setup(elf);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&10usize);
+        let proof = client.prove(&pk, &stdin).compressed().run().unwrap();
+        client.verify(&proof, &vk).unwrap();
+    }
+
+    #[test]
+    fn test_mock_prove_with_syscall_trace() {
+        utils::setup_logger();
+        let client = ProverClient::builder().mock().build();
+        let elf = test_artifacts::SECP256K1_ADD_ELF;
+        let (pk, vk) = client.setup(elf);
+        let stdin = SP1Stdin::new();
+        let (proof, syscalls) =
+            client.prove_with_syscall_trace(&pk, &stdin, SP1ProofMode::Core).unwrap();
+        client.verify(&proof, &vk).unwrap();
+        assert!(syscalls.contains(&SyscallCode::SECP256K1_ADD));
+        assert!(syscalls.contains(&SyscallCode::HALT));
+        assert!(!syscalls.contains(&SyscallCode::SHA_EXTEND));
+
+        // Syscall traces are only available in mock mode.
+        let client = ProverClient::builder().cpu().build();
+        assert!(client.prove_with_syscall_trace(&pk, &stdin, SP1ProofMode::Core).is_err());
+    }
 }
@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use sp1_core_executor::SP1ReduceProof;
 use sp1_primitives::io::SP1PublicValues;
 use sp1_prover::{CoreSC, Groth16Bn254Proof, InnerSC, PlonkBn254Proof};
-use sp1_stark::ShardProof;
+use sp1_stark::{ShardProof, StarkGenericConfig};
 use strum_macros::{EnumDiscriminants, EnumTryAs};
 
 /// A proof generated by the SP1 RISC-V zkVM.
@@ -28,6 +28,11 @@ pub enum SP1Proof {
     Plonk(PlonkBn254Proof),
     /// A proof generated by the Groth16 proof mode.
     Groth16(Groth16Bn254Proof),
+    /// A proof generated by the shrink proof mode.
+    ///
+    /// This is the compressed proof recursively proven again with a larger blowup, which makes it
+    /// smaller while keeping it over BabyBear. It is the input of the BN254 wrapping step.
+    Shrink(Box<SP1ReduceProof<InnerSC>>),
 }
 
 /// A proof generated by the SP1 RISC-V zkVM bundled together with the public values and the
@@ -56,6 +61,36 @@ impl SP1ProofWithPublicValues {
             .map_err(Into::into)
     }
 
+    /// The conjectured soundness, in bits, of the FRI parameters the proof was made with.
+    ///
+    /// This is `log_blowup * num_queries + proof_of_work_bits`, where the number of queries is read
+    /// from the proof itself, so proofs made with a lowered `FRI_QUERIES` report a lower level.
+    /// Returns `None` for PLONK and Groth16 proofs, which are not FRI proofs, and for proofs
+    /// without any shard.
+    #[must_use]
+    pub fn security_bits(&self) -> Option<u32> {
+        let (num_queries, config) = match &self.proof {
+            SP1Proof::Core(shard_proofs) => (
+                shard_proofs
+                    .iter()
+                    .map(|shard| shard.opening_proof.fri_proof.query_proofs.len())
+                    .min()?,
+                CoreSC::default(),
+            ),
+            SP1Proof::Compressed(reduce_proof) => {
+                (reduce_proof.proof.opening_proof.fri_proof.query_proofs.len(), InnerSC::default())
+            }
+            SP1Proof::Shrink(reduce_proof) => (
+                reduce_proof.proof.opening_proof.fri_proof.query_proofs.len(),
+                InnerSC::compressed(),
+            ),
+            SP1Proof::Plonk(_) | SP1Proof::Groth16(_) => return None,
+        };
+        let fri_config = config.pcs().fri_config();
+        let bits = fri_config.log_blowup * num_queries + fri_config.proof_of_work_bits;
+        Some(bits as u32)
+    }
+
     /// The proof in the byte encoding the onchain verifiers accepts for [`SP1ProofMode::Groth16`] and
     /// [`SP1ProofMode::Plonk`] proofs.
     ///
@@ -7,12 +7,13 @@ use std::borrow::Borrow;
 use anyhow::Result;
 use itertools::Itertools;
 use p3_field::PrimeField32;
+use p3_maybe_rayon::prelude::*;
 use sp1_core_executor::{ExecutionReport, SP1Context};
 use sp1_core_machine::{io::SP1Stdin, SP1_CIRCUIT_VERSION};
 use sp1_primitives::io::SP1PublicValues;
 use sp1_prover::{
-    components::SP1ProverComponents, CoreSC, InnerSC, SP1CoreProofData, SP1Prover, SP1ProvingKey,
-    SP1VerifyingKey,
+    components::SP1ProverComponents, CoreSC, InnerSC, ProgramError, SP1CoreProofData, SP1Prover,
+    SP1ProvingKey, SP1VerifyingKey,
 };
 use sp1_stark::{air::PublicValues, MachineVerificationError, Word};
 use thiserror::Error;
@@ -33,6 +34,12 @@ pub trait Prover<C: SP1ProverComponents>: Send + Sync {
     /// Generate the proving and verifying keys for the given program.
     fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey);
 
+    /// Generate the proving and verifying keys for the given program, returning an error if the
+    /// ELF is malformed.
+    fn try_setup(&self, elf: &[u8]) -> Result<(SP1ProvingKey, SP1VerifyingKey), ProgramError> {
+        self.inner().try_setup(elf)
+    }
+
     /// Executes the program on the given input.
     fn execute(&self, elf: &[u8], stdin: &SP1Stdin) -> Result<(SP1PublicValues, ExecutionReport)> {
         Ok(self.inner().execute(elf, stdin, SP1Context::default())?)
@@ -56,6 +63,33 @@ pub trait Prover<C: SP1ProverComponents>: Send + Sync {
     ) -> Result<(), SP1VerificationError> {
         verify_proof(self.inner(), self.version(), bundle, vkey)
     }
+
+    /// Verify many independent proofs in parallel with [`Self::verify`], returning the result for
+    /// each `(proof, vkey)` pair in order.
+    fn verify_batch(
+        &self,
+        items: &[(&SP1ProofWithPublicValues, &SP1VerifyingKey)],
+    ) -> Vec<Result<(), SP1VerificationError>> {
+        items.par_iter().map(|(bundle, vkey)| self.verify(bundle, vkey)).collect()
+    }
+
+    /// Verify an SP1 proof with [`Self::verify`], then check that its committed public values
+    /// start with `expected`.
+    ///
+    /// Passing the full public values checks for an exact match, while passing only the leading
+    /// values that were committed checks a prefix.
+    fn verify_with_public_values(
+        &self,
+        bundle: &SP1ProofWithPublicValues,
+        vkey: &SP1VerifyingKey,
+        expected: &SP1PublicValues,
+    ) -> Result<(), SP1VerificationError> {
+        self.verify(bundle, vkey)?;
+        if !bundle.public_values.as_slice().starts_with(expected.as_slice()) {
+            return Err(SP1VerificationError::PublicValuesMismatch);
+        }
+        Ok(())
+    }
 }
 
 /// An error that occurs when calling [`Prover::verify`].
@@ -65,8 +99,11 @@ pub enum SP1VerificationError {
     #[error("Invalid public values")]
     InvalidPublicValues,
     /// An error that occurs when the SP1 version does not match the version of the circuit.
-    #[error("Version mismatch")]
+    #[error("Version mismatch: the proof was produced by SP1 {0}")]
     VersionMismatch(String),
+    /// An error that occurs when the committed public values do not match the expected ones.
+    #[error("Public values mismatch")]
+    PublicValuesMismatch,
     /// An error that occurs when the core machine verification fails.
     #[error("Core machine verification error: {0}")]
     Core(MachineVerificationError<CoreSC>),
@@ -136,6 +173,26 @@ pub(crate) fn verify_proof<C: SP1ProverComponents>(
 
             prover.verify_compressed(proof, vkey).map_err(SP1VerificationError::Recursion)
         }
+        SP1Proof::Shrink(proof) => {
+            let public_values: &PublicValues<Word<_>, _> =
+                proof.proof.public_values.as_slice().borrow();
+
+            // Get the committed value digest bytes.
+            let committed_value_digest_bytes = public_values
+                .committed_value_digest
+                .iter()
+                .flat_map(|w| w.0.iter().map(|x| x.as_canonical_u32() as u8))
+                .collect_vec();
+
+            // Make sure the committed value digest matches the public values hash.
+            for (a, b) in committed_value_digest_bytes.iter().zip_eq(bundle.public_values.hash()) {
+                if *a != b {
+                    return Err(SP1VerificationError::InvalidPublicValues);
+                }
+            }
+
+            prover.verify_shrink(proof, vkey).map_err(SP1VerificationError::Recursion)
+        }
         SP1Proof::Plonk(proof) => prover
             .verify_plonk_bn254(
                 proof,
@@ -257,6 +257,7 @@ pub mod baby_bear_poseidon2 {
         pub perm: Perm,
         pcs: Pcs,
         config_type: BabyBearPoseidon2Type,
+        fri_queries: Option<usize>,
     }
 
     impl BabyBearPoseidon2 {
@@ -269,7 +270,7 @@ pub mod baby_bear_poseidon2 {
             let dft = Dft {};
             let fri_config = default_fri_config();
             let pcs = Pcs::new(27, dft, val_mmcs, fri_config);
-            Self { pcs, perm, config_type: BabyBearPoseidon2Type::Default }
+            Self { pcs, perm, config_type: BabyBearPoseidon2Type::Default, fri_queries: None }
         }
 
         #[must_use]
@@ -281,7 +282,7 @@ pub mod baby_bear_poseidon2 {
             let dft = Dft {};
             let fri_config = compressed_fri_config();
             let pcs = Pcs::new(27, dft, val_mmcs, fri_config);
-            Self { pcs, perm, config_type: BabyBearPoseidon2Type::Compressed }
+            Self { pcs, perm, config_type: BabyBearPoseidon2Type::Compressed, fri_queries: None }
         }
 
         #[must_use]
@@ -293,15 +294,46 @@ pub mod baby_bear_poseidon2 {
             let dft = Dft {};
             let fri_config = ultra_compressed_fri_config();
             let pcs = Pcs::new(27, dft, val_mmcs, fri_config);
-            Self { pcs, perm, config_type: BabyBearPoseidon2Type::Compressed }
+            Self { pcs, perm, config_type: BabyBearPoseidon2Type::Compressed, fri_queries: None }
+        }
+
+        /// Overrides the number of FRI queries, keeping the rest of the FRI config unchanged.
+        ///
+        /// **This is for development only.** Lowering the number of queries lowers the soundness
+        /// of every proof made with this config, and proofs made with it are rejected by verifiers
+        /// using the default config.
+        #[must_use]
+        pub fn with_fri_queries(self, num_queries: usize) -> Self {
+            let hash = MyHash::new(self.perm.clone());
+            let compress = MyCompress::new(self.perm.clone());
+            let val_mmcs = ValMmcs::new(hash, compress);
+            let dft = Dft {};
+            let fri_config = self.pcs.fri_config();
+            let fri_config = FriConfig {
+                log_blowup: fri_config.log_blowup,
+                num_queries,
+                proof_of_work_bits: fri_config.proof_of_work_bits,
+                mmcs: fri_config.mmcs.clone(),
+            };
+            let pcs = Pcs::new(27, dft, val_mmcs, fri_config);
+            Self {
+                pcs,
+                perm: self.perm,
+                config_type: self.config_type,
+                fri_queries: Some(num_queries),
+            }
         }
     }
 
     impl Clone for BabyBearPoseidon2 {
         fn clone(&self) -> Self {
-            match self.config_type {
+            let config = match self.config_type {
                 BabyBearPoseidon2Type::Default => Self::new(),
                 BabyBearPoseidon2Type::Compressed => Self::compressed(),
+            };
+            match self.fri_queries {
+                Some(num_queries) => config.with_fri_queries(num_queries),
+                None => config,
             }
         }
     }
@@ -349,4 +381,36 @@ pub mod baby_bear_poseidon2 {
             DigestHash::from([Val::zero(); DIGEST_SIZE])
         }
     }
+
+    /// The state of a [`Challenger`] as plain, serializable data.
+    ///
+    /// Useful for comparing the transcripts of the prover and verifier when they diverge.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct ChallengerSnapshot {
+        pub sponge_state: [Val; 16],
+        pub input_buffer: Vec<Val>,
+        pub output_buffer: Vec<Val>,
+    }
+
+    impl ChallengerSnapshot {
+        /// Rebuild a challenger in this state, using the permutation of `config`.
+        #[must_use]
+        pub fn reconstruct(&self, config: &BabyBearPoseidon2) -> Challenger {
+            let mut challenger = config.challenger();
+            challenger.sponge_state = self.sponge_state;
+            challenger.input_buffer.clone_from(&self.input_buffer);
+            challenger.output_buffer.clone_from(&self.output_buffer);
+            challenger
+        }
+    }
+
+    impl From<&Challenger> for ChallengerSnapshot {
+        fn from(challenger: &Challenger) -> Self {
+            Self {
+                sponge_state: challenger.sponge_state,
+                input_buffer: challenger.input_buffer.clone(),
+                output_buffer: challenger.output_buffer.clone(),
+            }
+        }
+    }
 }
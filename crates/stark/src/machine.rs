@@ -7,7 +7,7 @@ use p3_field::{AbstractExtensionField, AbstractField, Field, PrimeField32};
 use p3_matrix::{dense::RowMajorMatrix, Dimensions, Matrix};
 use p3_maybe_rayon::prelude::*;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::{array, cmp::Reverse, env, fmt::Debug, time::Instant};
+use std::{array, borrow::Borrow, cmp::Reverse, env, fmt::Debug, time::Instant};
 use tracing::instrument;
 
 use super::{debug_constraints, Dom};
@@ -300,23 +300,46 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
     where
         SC::Challenger: Clone,
         A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    {
+        self.verify_lazy(vk, proof.shard_proofs.len(), |i| Ok(&proof.shard_proofs[i]), challenger)
+    }
+
+    /// Verify a proof whose shard proofs are fetched on demand by `shard_proof`.
+    ///
+    /// Only one shard proof is held at a time. Each shard is fetched twice: once to observe its
+    /// commitment and public values, and once to verify it.
+    pub fn verify_lazy<P, E>(
+        &self,
+        vk: &StarkVerifyingKey<SC>,
+        num_shards: usize,
+        shard_proof: impl Fn(usize) -> Result<P, E>,
+        challenger: &mut SC::Challenger,
+    ) -> Result<(), E>
+    where
+        P: Borrow<ShardProof<SC>>,
+        E: From<MachineVerificationError<SC>>,
+        SC::Challenger: Clone,
+        A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
     {
         let contains_global_bus = self.contains_global_bus();
 
         // Observe the preprocessed commitment.
         vk.observe_into(challenger);
         tracing::debug_span!("observe challenges for all shards").in_scope(|| {
-            proof.shard_proofs.iter().for_each(|shard_proof| {
+            for i in 0..num_shards {
+                let shard_proof = shard_proof(i)?;
+                let shard_proof = shard_proof.borrow();
                 if contains_global_bus {
                     challenger.observe(shard_proof.commitment.global_main_commit.clone());
                 }
                 challenger.observe_slice(&shard_proof.public_values[0..self.num_pv_elts()]);
-            });
-        });
+            }
+            Ok::<_, E>(())
+        })?;
 
         // Verify the shard proofs.
-        if proof.shard_proofs.is_empty() {
-            return Err(MachineVerificationError::EmptyProof);
+        if num_shards == 0 {
+            return Err(MachineVerificationError::EmptyProof.into());
         }
 
         // Obtain the challenges used for the global permutation argument.
@@ -328,8 +351,13 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
             }
         });
 
-        tracing::debug_span!("verify shard proofs").in_scope(|| {
-            for (i, shard_proof) in proof.shard_proofs.iter().enumerate() {
+        // The global cumulative sum is accumulated while verifying, so that each shard proof only
+        // needs to be fetched once more.
+        let sum = tracing::debug_span!("verify shard proofs").in_scope(|| {
+            let mut sum = SC::Challenge::zero();
+            for i in 0..num_shards {
+                let shard_proof = shard_proof(i)?;
+                let shard_proof = shard_proof.borrow();
                 tracing::debug_span!("verifying shard", shard = i).in_scope(|| {
                     let chips =
                         self.shard_chips_ordered(&shard_proof.chip_ordering).collect::<Vec<_>>();
@@ -343,24 +371,20 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
                     )
                     .map_err(MachineVerificationError::InvalidShardProof)
                 })?;
+                sum += shard_proof.cumulative_sum(InteractionScope::Global);
             }
 
-            Ok(())
+            Ok::<_, E>(sum)
         })?;
 
         // Verify the cumulative sum is 0.
         tracing::debug_span!("verify global cumulative sum is 0").in_scope(|| {
-            let sum = proof
-                .shard_proofs
-                .iter()
-                .map(|proof| proof.cumulative_sum(InteractionScope::Global))
-                .sum::<SC::Challenge>();
-
             if !sum.is_zero() {
                 return Err(MachineVerificationError::NonZeroCumulativeSum(
                     InteractionScope::Global,
                     0,
-                ));
+                )
+                .into());
             }
 
             Ok(())
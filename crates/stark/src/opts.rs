@@ -45,6 +45,38 @@ pub struct SP1CoreOpts {
     pub checkpoints_channel_capacity: usize,
     /// The capacity of the channel for records and traces.
     pub records_and_traces_channel_capacity: usize,
+    /// The number of workers generating recursion records and traces during compression.
+    pub recursion_trace_workers: usize,
+    /// The number of workers proving recursion shards during compression.
+    pub recursion_prove_workers: usize,
+    /// The order in which compression proves the layers of the recursion tree.
+    pub compress_schedule: CompressSchedule,
+}
+
+/// The order in which compression proves the layers of the recursion tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompressSchedule {
+    /// Start proving the inputs of the next layer as soon as each batch of proofs is done, so that
+    /// several layers are in flight at once.
+    #[default]
+    Pipelined,
+    /// Only generate the records and traces of a layer once the previous layer is fully proven,
+    /// trading latency for the records and traces of at most one layer alive at a time.
+    BreadthFirst,
+    /// A `COMPRESS_SCHEDULE` value that is not one of the above, which compression rejects.
+    Unrecognized,
+}
+
+impl CompressSchedule {
+    /// Parse a `COMPRESS_SCHEDULE` value, ignoring case.
+    #[must_use]
+    pub fn from_env_value(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "pipelined" => Self::Pipelined,
+            "breadth-first" => Self::BreadthFirst,
+            _ => Self::Unrecognized,
+        }
+    }
 }
 
 /// Calculate the default shard size using an empirically determined formula.
@@ -89,22 +121,24 @@ impl Default for SP1CoreOpts {
         let total_available_mem = sys.total_memory() / (1024 * 1024 * 1024);
         let default_shard_size = shard_size(total_available_mem);
         let default_shard_batch_size = shard_batch_size(total_available_mem);
+        let shard_batch_size = env::var("SHARD_BATCH_SIZE").map_or_else(
+            |_| default_shard_batch_size,
+            |s| s.parse::<usize>().unwrap_or(default_shard_batch_size),
+        );
+        let trace_gen_workers = env::var("TRACE_GEN_WORKERS").map_or_else(
+            |_| DEFAULT_TRACE_GEN_WORKERS,
+            |s| s.parse::<usize>().unwrap_or(DEFAULT_TRACE_GEN_WORKERS),
+        );
 
         Self {
             shard_size: env::var("SHARD_SIZE").map_or_else(
                 |_| default_shard_size,
                 |s| s.parse::<usize>().unwrap_or(default_shard_size),
             ),
-            shard_batch_size: env::var("SHARD_BATCH_SIZE").map_or_else(
-                |_| default_shard_batch_size,
-                |s| s.parse::<usize>().unwrap_or(default_shard_batch_size),
-            ),
+            shard_batch_size,
             split_opts: SplitOpts::new(split_threshold),
             reconstruct_commitments: true,
-            trace_gen_workers: env::var("TRACE_GEN_WORKERS").map_or_else(
-                |_| DEFAULT_TRACE_GEN_WORKERS,
-                |s| s.parse::<usize>().unwrap_or(DEFAULT_TRACE_GEN_WORKERS),
-            ),
+            trace_gen_workers,
             checkpoints_channel_capacity: env::var("CHECKPOINTS_CHANNEL_CAPACITY").map_or_else(
                 |_| DEFAULT_CHECKPOINTS_CHANNEL_CAPACITY,
                 |s| s.parse::<usize>().unwrap_or(DEFAULT_CHECKPOINTS_CHANNEL_CAPACITY),
@@ -114,6 +148,18 @@ impl Default for SP1CoreOpts {
                     |_| DEFAULT_RECORDS_AND_TRACES_CHANNEL_CAPACITY,
                     |s| s.parse::<usize>().unwrap_or(DEFAULT_RECORDS_AND_TRACES_CHANNEL_CAPACITY),
                 ),
+            recursion_trace_workers: env::var("RECURSION_TRACE_WORKERS").map_or_else(
+                |_| trace_gen_workers,
+                |s| s.parse::<usize>().unwrap_or(trace_gen_workers),
+            ),
+            recursion_prove_workers: env::var("RECURSION_PROVE_WORKERS").map_or_else(
+                |_| shard_batch_size,
+                |s| s.parse::<usize>().unwrap_or(shard_batch_size),
+            ),
+            compress_schedule: env::var("COMPRESS_SCHEDULE").map_or_else(
+                |_| CompressSchedule::default(),
+                |s| CompressSchedule::from_env_value(&s),
+            ),
         }
     }
 }
@@ -159,3 +205,19 @@ impl SplitOpts {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::CompressSchedule;
+
+    #[test]
+    fn test_compress_schedule_from_env_value() {
+        assert_eq!(CompressSchedule::from_env_value("pipelined"), CompressSchedule::Pipelined);
+        assert_eq!(
+            CompressSchedule::from_env_value("Breadth-First"),
+            CompressSchedule::BreadthFirst
+        );
+        assert_eq!(CompressSchedule::from_env_value("depth-first"), CompressSchedule::Unrecognized);
+        assert_eq!(CompressSchedule::from_env_value(""), CompressSchedule::Unrecognized);
+    }
+}
@@ -77,9 +77,11 @@ pub trait MachineProver<SC: StarkGenericConfig, A: MachineAir<SC::Val>>:
         assert!(!chips.is_empty());
 
         // For each chip, generate the trace.
-        let parent_span = tracing::debug_span!("generate traces for shard");
+        let parent_span =
+            tracing::debug_span!("generate traces for shard", scope = %interaction_scope);
         parent_span.in_scope(|| {
-            chips
+            let begin = Instant::now();
+            let traces = chips
                 .par_iter()
                 .map(|chip| {
                     let chip_name = chip.name();
@@ -93,7 +95,14 @@ pub trait MachineProver<SC: StarkGenericConfig, A: MachineAir<SC::Val>>:
                     );
                     (chip_name, trace)
                 })
-                .collect::<Vec<_>>()
+                .collect::<Vec<_>>();
+            tracing::debug!(
+                "generated {} traces for {} chips in {:?}",
+                interaction_scope,
+                traces.len(),
+                begin.elapsed()
+            );
+            traces
         })
     }
 
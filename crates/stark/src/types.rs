@@ -86,8 +86,10 @@ pub struct ShardProof<SC: StarkGenericConfig> {
     pub public_values: Vec<Val<SC>>,
 }
 
+/// The chips of a shard proof and the log2 of their trace heights, sorted by decreasing height.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq, Hash)]
 pub struct ProofShape {
+    /// The name and log2 trace height of each chip.
     pub chip_information: Vec<(String, usize)>,
 }
 
@@ -191,6 +193,8 @@ impl From<[u32; 8]> for DeferredDigest {
 }
 
 impl<SC: StarkGenericConfig> ShardProof<SC> {
+    /// The shape of the proof: the chips it proves and the heights of their traces.
+    #[must_use]
     pub fn shape(&self) -> ProofShape {
         ProofShape {
             chip_information: self
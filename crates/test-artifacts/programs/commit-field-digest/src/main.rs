@@ -0,0 +1,11 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use p3_baby_bear::BabyBear;
+use p3_field::AbstractField;
+
+pub fn main() {
+    let words = sp1_zkvm::io::read::<[u32; 8]>();
+    let digest = words.map(BabyBear::from_canonical_u32);
+    sp1_zkvm::io::commit_field_digest(&digest);
+}
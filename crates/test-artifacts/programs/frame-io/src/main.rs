@@ -0,0 +1,10 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+pub fn main() {
+    let num_frames = sp1_zkvm::io::read::<u32>();
+    for _ in 0..num_frames {
+        let frame = sp1_zkvm::io::read_frame();
+        sp1_zkvm::io::commit(&frame);
+    }
+}
@@ -0,0 +1,11 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+/// The file descriptor of the hook registered by the host test.
+const FD_INPUT_LEN_HOOK: u32 = 100;
+
+pub fn main() {
+    sp1_zkvm::io::write(FD_INPUT_LEN_HOOK, b"len");
+    let _ = sp1_zkvm::io::read_vec();
+    sp1_zkvm::io::write(FD_INPUT_LEN_HOOK, b"len");
+}
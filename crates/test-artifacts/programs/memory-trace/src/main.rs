@@ -0,0 +1,11 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+static mut SLOT: u32 = 0;
+
+pub fn main() {
+    let value = sp1_zkvm::io::read::<u32>();
+    let slot = core::ptr::addr_of_mut!(SLOT);
+    unsafe { core::ptr::write_volatile(slot, value) };
+    sp1_zkvm::io::commit(&(slot as u32));
+}
@@ -0,0 +1,15 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+pub fn main() {
+    let first = sp1_zkvm::io::read_vec();
+    let second = sp1_zkvm::io::read_vec();
+
+    sp1_zkvm::io::commit_slice(&first);
+    let first_digest = sp1_zkvm::io::public_values_digest();
+    sp1_zkvm::io::commit_slice(&second);
+    let second_digest = sp1_zkvm::io::public_values_digest();
+
+    sp1_zkvm::io::commit_slice(&first_digest);
+    sp1_zkvm::io::commit_slice(&second_digest);
+}
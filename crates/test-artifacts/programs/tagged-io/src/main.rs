@@ -0,0 +1,13 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+pub fn main() {
+    let count = sp1_zkvm::io::read_tagged::<u32>(7);
+    let name = sp1_zkvm::io::read_tagged::<String>(1);
+    let bytes = sp1_zkvm::io::read_tagged::<Vec<u8>>(3);
+    let positional = sp1_zkvm::io::read::<u64>();
+    sp1_zkvm::io::commit(&count);
+    sp1_zkvm::io::commit(&name);
+    sp1_zkvm::io::commit(&bytes);
+    sp1_zkvm::io::commit(&positional);
+}
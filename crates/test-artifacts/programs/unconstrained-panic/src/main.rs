@@ -0,0 +1,10 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+pub fn main() {
+    let n = sp1_zkvm::io::read::<u32>();
+    sp1_zkvm::lib::unconstrained! {
+        assert!(n < 10, "hint for {n} is out of range");
+    }
+    sp1_zkvm::io::commit(&n);
+}
@@ -0,0 +1,10 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+pub fn main() {
+    let slices = sp1_zkvm::io::read_vec_of_slices();
+    sp1_zkvm::io::commit(&(slices.len() as u32));
+    for slice in slices.iter() {
+        sp1_zkvm::io::commit(&slice);
+    }
+}
@@ -0,0 +1,17 @@
+//! This is a test program that takes in a list of sp1_core vkeys with an input each, and verifies
+//! the SP1 proof of each input under its own vkey.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use sha2::{Digest, Sha256};
+use sp1_zkvm::lib::verify::verify_sp1_proof;
+
+pub fn main() {
+    let proofs = sp1_zkvm::io::read::<Vec<([u32; 8], Vec<u8>)>>();
+    for (vkey, input) in proofs.iter() {
+        // Get expected pv_digest hash: sha256(input)
+        let pv_digest = Sha256::digest(input);
+        verify_sp1_proof(vkey, &pv_digest.into());
+    }
+}
@@ -10,6 +10,20 @@ use sp1_build::include_elf;
 
 pub const FIBONACCI_ELF: &[u8] = include_elf!("fibonacci-program-tests");
 
+pub const FRAME_IO_ELF: &[u8] = include_elf!("frame-io-test");
+
+pub const VEC_OF_SLICES_ELF: &[u8] = include_elf!("vec-of-slices-test");
+
+pub const TAGGED_IO_ELF: &[u8] = include_elf!("tagged-io-test");
+
+pub const PUBLIC_VALUES_DIGEST_ELF: &[u8] = include_elf!("public-values-digest-test");
+
+pub const COMMIT_FIELD_DIGEST_ELF: &[u8] = include_elf!("commit-field-digest-test");
+
+pub const MEMORY_TRACE_ELF: &[u8] = include_elf!("memory-trace-test");
+
+pub const HOOK_INPUT_LEN_ELF: &[u8] = include_elf!("hook-input-len-test");
+
 pub const ED25519_ELF: &[u8] = include_elf!("ed25519-program");
 
 pub const CYCLE_TRACKER_ELF: &[u8] = include_elf!("cycle-tracker-test");
@@ -60,8 +74,12 @@ pub const BLS12381_DECOMPRESS_ELF: &[u8] = include_elf!("bls-decompress-test");
 
 pub const VERIFY_PROOF_ELF: &[u8] = include_elf!("verify-proof");
 
+pub const VERIFY_MULTI_VKEY_ELF: &[u8] = include_elf!("verify-multi-vkey-test");
+
 pub const PANIC_ELF: &[u8] = include_elf!("panic-test");
 
+pub const UNCONSTRAINED_PANIC_ELF: &[u8] = include_elf!("unconstrained-panic-test");
+
 pub const BLS12381_FP_ELF: &[u8] = include_elf!("bls12381-fp-test");
 
 pub const BLS12381_FP2_MUL_ELF: &[u8] = include_elf!("bls12381-fp2-mul-test");
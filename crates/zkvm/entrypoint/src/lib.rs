@@ -6,6 +6,32 @@ pub mod syscalls;
 #[cfg(feature = "lib")]
 pub mod io {
     pub use sp1_lib::io::*;
+
+    /// Commit a digest of BabyBear field elements to the public values stream.
+    ///
+    /// Each element is committed as its canonical value in a little-endian `u32`, so the digest
+    /// can be read back host-side with `SP1PublicValues::read_field_digest`. The words are written
+    /// to the public values stream directly, without building a byte array in the program.
+    ///
+    /// Enable this function by adding the `verify` feature to the `sp1-zkvm` crate.
+    ///
+    /// ### Examples
+    /// ```ignore
+    /// let digest = [BabyBear::one(); 8];
+    /// sp1_zkvm::io::commit_field_digest(&digest);
+    /// ```
+    #[cfg(feature = "verify")]
+    pub fn commit_field_digest(digest: &[p3_baby_bear::BabyBear; 8]) {
+        use p3_field::PrimeField32;
+
+        // The zkVM is little-endian, so the words are laid out in memory as they are committed.
+        let words = digest.map(|element| element.as_canonical_u32());
+        crate::syscalls::syscall_write(
+            FD_PUBLIC_VALUES,
+            words.as_ptr().cast(),
+            core::mem::size_of_val(&words),
+        );
+    }
 }
 
 #[cfg(feature = "lib")]
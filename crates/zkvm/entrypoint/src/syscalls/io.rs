@@ -36,6 +36,22 @@ pub extern "C" fn syscall_write(fd: u32, write_buf: *const u8, nbytes: usize) {
     }
 }
 
+/// Writes the SHA-256 digest of the bytes written to the public values fd so far into `out`.
+///
+/// The running hasher is cloned before finalizing, so later writes keep extending the same digest.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_public_values_digest(out: *mut [u8; 32]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        let hasher = (*core::ptr::addr_of!(zkvm::PUBLIC_VALUES_HASHER)).clone().unwrap();
+        *out = sha2::Digest::finalize(hasher).into();
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
 /// Returns the length of the next element in the hint stream.
 #[allow(unused_variables)]
 #[no_mangle]
@@ -1,9 +1,15 @@
 #![allow(unused_unsafe)]
-use crate::{syscall_hint_len, syscall_hint_read, syscall_write};
+use crate::{syscall_hint_len, syscall_hint_read, syscall_public_values_digest, syscall_write};
 use serde::{de::DeserializeOwned, Serialize};
+use sp1_primitives::io::TAGGED_INPUTS_MAGIC;
 use std::{
     alloc::Layout,
+    collections::BTreeMap,
     io::{Result, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
 };
 
 /// The file descriptor for public values.
@@ -49,6 +55,8 @@ impl Write for SyscallWriter {
 /// let data: Vec<u8> = sp1_zkvm::io::read_vec();
 /// ```
 pub fn read_vec() -> Vec<u8> {
+    INPUT_READ.store(true, Ordering::Relaxed);
+
     // Round up to the nearest multiple of 4 so that the memory allocated is in whole words
     let len = unsafe { syscall_hint_len() };
     let capacity = (len + 3) / 4 * 4;
@@ -74,6 +82,141 @@ pub fn read_vec() -> Vec<u8> {
     vec
 }
 
+/// Read a length-delimited frame from the input stream.
+///
+/// The frame must have been written on the host with `SP1Stdin::write_frame`, which prefixes the
+/// bytes with their length as a little-endian `u32`.
+///
+/// ### Examples
+/// ```ignore
+/// let frame: Vec<u8> = sp1_zkvm::io::read_frame();
+/// ```
+pub fn read_frame() -> Vec<u8> {
+    let mut vec = read_vec();
+    assert!(vec.len() >= 4, "frame is missing its length prefix");
+    let len = u32::from_le_bytes(vec[..4].try_into().unwrap()) as usize;
+    assert_eq!(vec.len() - 4, len, "frame length prefix does not match its contents");
+    vec.drain(..4);
+    vec
+}
+
+/// Whether an entry of the input stream has been read.
+static INPUT_READ: AtomicBool = AtomicBool::new(false);
+
+/// The tagged inputs, read on the first call to [`read_tagged`].
+static TAGGED_INPUTS: OnceLock<BTreeMap<u32, Vec<u8>>> = OnceLock::new();
+
+/// Read the deserializable object written under `tag` with `SP1Stdin::write_tagged`.
+///
+/// The tagged inputs are stored in a single entry at the front of the input stream, which is read
+/// on the first call. A program mixing tagged and positional inputs must therefore call this
+/// before its first positional read.
+///
+/// ### Examples
+/// ```ignore
+/// let n: u32 = sp1_zkvm::io::read_tagged(0);
+/// ```
+pub fn read_tagged<T: DeserializeOwned>(tag: u32) -> T {
+    let tagged = TAGGED_INPUTS.get_or_init(|| {
+        // The tagged inputs are the first entry, so check that it is still the next one and long
+        // enough to hold the prefix before reading it.
+        assert!(
+            !INPUT_READ.load(Ordering::Relaxed),
+            "the tagged inputs must be read before any positional input"
+        );
+        let len = unsafe { syscall_hint_len() };
+        assert!(len >= TAGGED_INPUTS_MAGIC.len(), "no tagged inputs were written");
+        let vec = read_vec();
+        let mut rest = vec
+            .strip_prefix(TAGGED_INPUTS_MAGIC.as_slice())
+            .expect("no tagged inputs were written");
+        let mut tagged = BTreeMap::new();
+        while !rest.is_empty() {
+            let read_u32 = |i: usize| {
+                let bytes = rest.get(i..i + 4).expect("tagged input is truncated");
+                u32::from_le_bytes(bytes.try_into().unwrap())
+            };
+            let tag = read_u32(0);
+            let len = read_u32(4) as usize;
+            let value = rest.get(8..8 + len).expect("tagged input is truncated");
+            tagged.insert(tag, value.to_vec());
+            rest = &rest[8 + len..];
+        }
+        tagged
+    });
+    let value = tagged.get(&tag).unwrap_or_else(|| panic!("no input with tag {tag}"));
+    bincode::deserialize(value).expect("deserialization failed")
+}
+
+/// Byte slices read with [`read_vec_of_slices`].
+///
+/// The slices stay in the single buffer they were read into, and are borrowed from it on access.
+pub struct VecOfSlices {
+    buf: Vec<u8>,
+    count: usize,
+}
+
+impl VecOfSlices {
+    /// The number of slices.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Whether there are no slices.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The slice at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        if index >= self.count {
+            return None;
+        }
+        let data = &self.buf[4 * (1 + self.count)..];
+        let start = if index == 0 { 0 } else { self.end(index - 1) };
+        Some(&data[start..self.end(index)])
+    }
+
+    /// An iterator over the slices, in the order they were written.
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        (0..self.count).map(|i| self.get(i).unwrap())
+    }
+
+    fn end(&self, index: usize) -> usize {
+        let offset = 4 * (1 + index);
+        u32::from_le_bytes(self.buf[offset..offset + 4].try_into().unwrap()) as usize
+    }
+}
+
+/// Read a list of byte slices from the input stream.
+///
+/// The slices must have been written on the host with `SP1Stdin::write_vec_of_slices`. They are
+/// read into a single buffer, so reading many small slices does not allocate each of them.
+///
+/// ### Examples
+/// ```ignore
+/// let slices = sp1_zkvm::io::read_vec_of_slices();
+/// for slice in slices.iter() {
+///     sp1_zkvm::io::commit_slice(slice);
+/// }
+/// ```
+pub fn read_vec_of_slices() -> VecOfSlices {
+    let buf = read_vec();
+    assert!(buf.len() >= 4, "slice table is missing its count");
+    let count = u32::from_le_bytes(buf[..4].try_into().unwrap()) as usize;
+    let table_len = 4 * (1 + count);
+    assert!(buf.len() >= table_len, "slice table is truncated");
+    let slices = VecOfSlices { buf, count };
+    let mut start = 0;
+    for i in 0..count {
+        let end = slices.end(i);
+        assert!(start <= end, "slice table does not match its contents");
+        start = end;
+    }
+    assert_eq!(slices.buf.len() - table_len, start, "slice table does not match its contents");
+    slices
+}
+
 /// Read a deserializable object from the input stream.
 ///
 /// ### Examples
@@ -128,6 +271,24 @@ pub fn commit_slice(buf: &[u8]) {
     my_writer.write_all(buf).unwrap();
 }
 
+/// Returns the SHA-256 digest of the bytes committed to the public values so far.
+///
+/// This does not end the public values: later commits extend the same digest, and the digest
+/// exposed by the proof covers everything committed by the end of the program.
+///
+/// ### Examples
+/// ```ignore
+/// sp1_zkvm::io::commit_slice(b"state");
+/// let digest: [u8; 32] = sp1_zkvm::io::public_values_digest();
+/// ```
+pub fn public_values_digest() -> [u8; 32] {
+    let mut digest = [0u8; 32];
+    unsafe {
+        syscall_public_values_digest(&mut digest);
+    }
+    digest
+}
+
 /// Hint a serializable object to the hint stream.
 ///
 /// ### Examples
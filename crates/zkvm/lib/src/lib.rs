@@ -24,6 +24,9 @@ extern "C" {
     /// Reads the bytes from the given file descriptor into the given buffer.
     pub fn syscall_read(fd: u32, read_buf: *mut u8, nbytes: usize);
 
+    /// Writes the digest of the public values committed so far into the given buffer.
+    pub fn syscall_public_values_digest(out: *mut [u8; 32]);
+
     /// Executes the SHA-256 extend operation on the given word array.
     pub fn syscall_sha256_extend(w: *mut [u32; 64]);
 